@@ -0,0 +1,37 @@
+#![no_main]
+
+use jfifdump::{DecoderOptions, ImageDecoder, LosslessDecoder, Reader, SegmentKind};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let options = DecoderOptions {
+        max_memory: 64 << 20,
+    };
+
+    let mut image = ImageDecoder::with_options(options);
+    let mut lossless = LosslessDecoder::with_options(options);
+    let mut image_ok = true;
+    let mut lossless_ok = true;
+
+    let mut reader = match Reader::new(data) {
+        Ok(r) => r,
+        Err(_) => return,
+    };
+
+    while let Ok(segment) = reader.next_segment() {
+        if let SegmentKind::Eoi = segment.kind {
+            break;
+        }
+
+        image_ok = image_ok && image.process(&segment.kind).is_ok();
+        lossless_ok = lossless_ok && lossless.process(&segment.kind).is_ok();
+    }
+
+    if image_ok {
+        let _ = image.image();
+    }
+
+    if lossless_ok {
+        let _ = lossless.to_pnm();
+    }
+});
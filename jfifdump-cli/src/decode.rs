@@ -0,0 +1,83 @@
+use std::path::PathBuf;
+
+use clap::{value_parser, Arg, ArgAction, ArgMatches, Command};
+
+use jfifdump::*;
+
+pub fn command() -> Command {
+    Command::new("decode")
+        .about("Decode the primary image and write it to a PGM, PPM or PAM file")
+        .after_help(
+            "Grayscale images are written as PGM, color images as PPM and CMYK images as PAM.\n\
+             Lossless images keep their precision and are written with one file channel per component.",
+        )
+        .arg(
+            Arg::new("RGB")
+                .long("rgb")
+                .action(ArgAction::SetTrue)
+                .help("Convert CMYK images to RGB and write a PPM file"),
+        )
+        .arg(
+            Arg::new("INPUT")
+                .help("Jpeg file to use")
+                .value_parser(value_parser!(PathBuf))
+                .required(true),
+        )
+        .arg(
+            Arg::new("OUTPUT")
+                .help("File to write the pixels to")
+                .value_parser(value_parser!(PathBuf))
+                .required(true),
+        )
+}
+
+pub fn run(matches: &ArgMatches) -> Result<(), String> {
+    let input_path = matches
+        .get_one::<PathBuf>("INPUT")
+        .expect("Required arg present");
+    let output_path = matches
+        .get_one::<PathBuf>("OUTPUT")
+        .expect("Required arg present");
+
+    let data = std::fs::read(input_path).map_err(|err| err.to_string())?;
+
+    let mut image = match decode_image(data.as_slice()) {
+        Ok(image) => image,
+        Err(JfifError::UnsupportedFrame(0xC3)) => return run_lossless(&data, output_path),
+        Err(err) => return Err(err.to_string()),
+    };
+
+    if matches.get_flag("RGB") {
+        image = image.into_rgb();
+    }
+
+    std::fs::write(output_path, image.to_pnm()).map_err(|err| err.to_string())?;
+
+    println!(
+        "Decoded {}x{} pixels with {} channels",
+        image.width,
+        image.height,
+        image.format.channels()
+    );
+
+    Ok(())
+}
+
+fn run_lossless(data: &[u8], output_path: &PathBuf) -> Result<(), String> {
+    let decoder = decode_lossless(data).map_err(|err| err.to_string())?;
+    let pnm = decoder.to_pnm().map_err(|err| err.to_string())?;
+
+    std::fs::write(output_path, pnm).map_err(|err| err.to_string())?;
+
+    if let Some(frame) = decoder.frame() {
+        println!(
+            "Decoded {}x{} lossless samples with {} components and {} bits",
+            frame.dimension_x,
+            frame.dimension_y,
+            frame.components.len(),
+            frame.precision
+        );
+    }
+
+    Ok(())
+}
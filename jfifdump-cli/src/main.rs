@@ -2,16 +2,45 @@
 
 use std::fs::File;
 use std::io::BufReader;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::exit;
 
 use clap::{crate_description, crate_name, crate_version, Arg, Command, value_parser, ArgAction};
 
 use jfifdump::*;
 
+mod decode;
+mod strip;
+mod validate;
+
 pub fn main() {
     let matches = create_clap_app().get_matches();
 
+    match matches.subcommand() {
+        Some(("strip", matches)) => {
+            if let Err(err) = strip::run(matches) {
+                eprintln!("Error stripping file: {}", err);
+                exit(1);
+            }
+            return;
+        }
+        Some(("decode", matches)) => {
+            if let Err(err) = decode::run(matches) {
+                eprintln!("Error decoding file: {}", err);
+                exit(1);
+            }
+            return;
+        }
+        Some(("validate", matches)) => {
+            if let Err(err) = validate::run(matches) {
+                eprintln!("Invalid file: {}", err);
+                exit(1);
+            }
+            return;
+        }
+        _ => {}
+    }
+
     let path = matches.get_one::<PathBuf>("INPUT").expect("Required arg present");
 
     let format = matches.get_one::<String>("FORMAT").map(|s|s.as_str()).unwrap_or("text");
@@ -46,12 +75,87 @@ pub fn main() {
         eprintln!("Error reading file: {}", err);
         exit(1);
     }
+
+    if let Some(icc_path) = matches.get_one::<PathBuf>("ICC") {
+        if let Err(err) = write_icc_profile(path, icc_path) {
+            eprintln!("Error writing ICC profile: {}", err);
+            exit(1);
+        }
+    }
+
+    if let Some(thumbnail_path) = matches.get_one::<PathBuf>("THUMBNAIL") {
+        if let Err(err) = write_thumbnail(path, thumbnail_path) {
+            eprintln!("Error writing thumbnail: {}", err);
+            exit(1);
+        }
+    }
+}
+
+fn write_icc_profile(path: &Path, icc_path: &Path) -> Result<(), String> {
+    let file = File::open(path).map_err(|err| err.to_string())?;
+    let mut reader = Reader::new(BufReader::new(file)).map_err(|err| err.to_string())?;
+
+    // Chunks read before an error are still written
+    while reader.next_segment().is_ok() {}
+
+    let profile = reader
+        .icc_profile()
+        .ok_or_else(|| "File contains no ICC profile".to_owned())?;
+
+    std::fs::write(icc_path, profile.data).map_err(|err| err.to_string())
+}
+
+fn write_thumbnail(path: &Path, thumbnail_path: &Path) -> Result<(), String> {
+    let file = File::open(path).map_err(|err| err.to_string())?;
+    let mut reader = Reader::new(BufReader::new(file)).map_err(|err| err.to_string())?;
+
+    // Only the thumbnail of the primary image is written
+    let thumbnail = loop {
+        let segment = reader.next_segment().map_err(|err| err.to_string())?;
+
+        match segment.kind {
+            SegmentKind::App0Jfif(jfif) => {
+                if let Some(thumbnail) = jfif.rgb_thumbnail() {
+                    break thumbnail;
+                }
+            }
+            SegmentKind::App0Jfxx(App0Jfxx {
+                thumbnail: Some(thumbnail),
+                ..
+            }) => break thumbnail,
+            SegmentKind::Scan(_) | SegmentKind::Eoi => {
+                return Err("File contains no thumbnail".to_owned())
+            }
+            _ => {}
+        }
+    };
+
+    let data = thumbnail.to_file().ok_or_else(|| {
+        format!(
+            "Invalid thumbnail size: {} bytes, expected {}",
+            thumbnail.size(),
+            thumbnail.expected_size().unwrap_or_default()
+        )
+    })?;
+
+    let thumbnail_path = if thumbnail_path.extension().is_none() {
+        thumbnail_path.with_extension(thumbnail.file_extension())
+    } else {
+        thumbnail_path.to_owned()
+    };
+
+    std::fs::write(thumbnail_path, data).map_err(|err| err.to_string())
 }
 
 fn create_clap_app() -> Command {
     Command::new(crate_name!())
         .version(crate_version!())
         .about(crate_description!())
+        .subcommand_negates_reqs(true)
+        .args_conflicts_with_subcommands(true)
+        .subcommand(strip::command())
+        .subcommand(decode::command())
+        .subcommand(validate::command())
         .arg(
             Arg::new("FORMAT")
                 .short('f')
@@ -67,6 +171,20 @@ fn create_clap_app() -> Command {
                 .action(ArgAction::SetTrue)
                 .help("Make output more verbose"),
         )
+        .arg(
+            Arg::new("ICC")
+                .long("icc")
+                .value_name("FILE")
+                .value_parser(value_parser!(PathBuf))
+                .help("Write the embedded ICC profile to a .icc file"),
+        )
+        .arg(
+            Arg::new("THUMBNAIL")
+                .long("thumbnail")
+                .value_name("FILE")
+                .value_parser(value_parser!(PathBuf))
+                .help("Write the JFIF/JFXX thumbnail to a .jpg or .ppm file"),
+        )
         .arg(
             Arg::new("INPUT")
                 .help("Jpeg file to use")
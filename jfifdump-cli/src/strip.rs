@@ -0,0 +1,352 @@
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use clap::{value_parser, Arg, ArgAction, ArgMatches, Command};
+
+use jfifdump::*;
+
+/// Segments kept if neither `--keep` nor `--drop` is used
+const DEFAULT_KEEP: [Selector; 5] = [
+    Selector::Jfif,
+    Selector::Jfxx,
+    Selector::Icc,
+    Selector::Adobe,
+    Selector::Mpf,
+];
+
+/// APPn or COM segments selected by marker number or signature
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Selector {
+    App(u8),
+    Comment,
+    Jfif,
+    Jfxx,
+    Exif,
+    Xmp,
+    Icc,
+    Mpf,
+    Photoshop,
+    Adobe,
+}
+
+impl Selector {
+    fn parse(value: &str) -> Result<Selector, String> {
+        let value = value.to_ascii_lowercase();
+
+        Ok(match value.as_str() {
+            "com" | "comment" => Selector::Comment,
+            "jfif" => Selector::Jfif,
+            "jfxx" => Selector::Jfxx,
+            "exif" => Selector::Exif,
+            "xmp" => Selector::Xmp,
+            "icc" => Selector::Icc,
+            "mpf" => Selector::Mpf,
+            "photoshop" | "iptc" => Selector::Photoshop,
+            "adobe" => Selector::Adobe,
+            _ => value
+                .strip_prefix("app")
+                .and_then(|nr| nr.parse::<u8>().ok())
+                .filter(|&nr| nr < 16)
+                .map(Selector::App)
+                .ok_or_else(|| format!("Unknown segment: {}", value))?,
+        })
+    }
+
+    fn matches(&self, kind: &SegmentKind) -> bool {
+        use SegmentKind::*;
+
+        // Segments with malformed content are read as raw APP segments and matched by signature
+        let raw = |nr: u8, signature: &[u8]| match kind {
+            App { nr: app_nr, data } => *app_nr == nr && data.starts_with(signature),
+            _ => false,
+        };
+
+        match self {
+            Selector::App(nr) => app_nr(kind) == Some(*nr),
+            Selector::Comment => matches!(kind, Comment(_)),
+            Selector::Jfif => matches!(kind, App0Jfif(_)) || raw(0, b"JFIF\0"),
+            Selector::Jfxx => matches!(kind, App0Jfxx(_)) || raw(0, b"JFXX\0"),
+            Selector::Exif => matches!(kind, App1Exif(_)) || raw(1, b"Exif\0\0"),
+            Selector::Xmp => {
+                matches!(kind, App1Xmp(_) | App1XmpExtension(_))
+                    || raw(1, b"http://ns.adobe.com/xap/1.0/\0")
+                    || raw(1, b"http://ns.adobe.com/xmp/extension/\0")
+            }
+            Selector::Icc => matches!(kind, App2Icc(_)) || raw(2, b"ICC_PROFILE\0"),
+            Selector::Mpf => matches!(kind, App2Mpf(_)) || raw(2, b"MPF\0"),
+            Selector::Photoshop => matches!(kind, App13Photoshop(_)) || raw(13, b"Photoshop 3.0\0"),
+            Selector::Adobe => matches!(kind, App14Adobe(_)) || raw(14, b"Adobe"),
+        }
+    }
+}
+
+/// Marker number of APP segments
+fn app_nr(kind: &SegmentKind) -> Option<u8> {
+    use SegmentKind::*;
+
+    match kind {
+        App { nr, .. } => Some(*nr),
+        App0Jfif(_) | App0Jfxx(_) => Some(0),
+        App1Exif(_) | App1Xmp(_) | App1XmpExtension(_) => Some(1),
+        App2Icc(_) | App2Mpf(_) => Some(2),
+        App13Photoshop(_) => Some(13),
+        App14Adobe(_) => Some(14),
+        _ => None,
+    }
+}
+
+pub fn command() -> Command {
+    Command::new("strip")
+        .about("Remove APPn and COM segments and write the result to a new file")
+        .after_help(
+            "SEGMENT is one of jfif, jfxx, exif, xmp, icc, mpf, photoshop (or iptc), adobe, \
+             com or app0 to app15.\nWithout --keep and --drop all segments except \
+             jfif, jfxx, icc, adobe and mpf are removed.",
+        )
+        .arg(
+            Arg::new("KEEP")
+                .long("keep")
+                .value_name("SEGMENT")
+                .value_delimiter(',')
+                .action(ArgAction::Append)
+                .value_parser(Selector::parse)
+                .help("Keep only these APPn and COM segments"),
+        )
+        .arg(
+            Arg::new("DROP")
+                .long("drop")
+                .value_name("SEGMENT")
+                .value_delimiter(',')
+                .action(ArgAction::Append)
+                .value_parser(Selector::parse)
+                .help("Remove these APPn and COM segments"),
+        )
+        .arg(
+            Arg::new("INPUT")
+                .help("Jpeg file to use")
+                .value_parser(value_parser!(PathBuf))
+                .required(true),
+        )
+        .arg(
+            Arg::new("OUTPUT")
+                .help("File to write the result to")
+                .value_parser(value_parser!(PathBuf))
+                .required(true),
+        )
+}
+
+pub fn run(matches: &ArgMatches) -> Result<(), String> {
+    let input_path = matches
+        .get_one::<PathBuf>("INPUT")
+        .expect("Required arg present");
+    let output_path = matches
+        .get_one::<PathBuf>("OUTPUT")
+        .expect("Required arg present");
+
+    let keep: Option<Vec<Selector>> = matches
+        .get_many::<Selector>("KEEP")
+        .map(|values| values.copied().collect());
+    let drop: Vec<Selector> = matches
+        .get_many::<Selector>("DROP")
+        .map(|values| values.copied().collect())
+        .unwrap_or_default();
+
+    let keep = match keep {
+        None if drop.is_empty() => Some(DEFAULT_KEEP.to_vec()),
+        keep => keep,
+    };
+
+    let input = std::fs::read(input_path).map_err(|err| err.to_string())?;
+
+    // Kept segments are copied from the input, so scan data isn't needed
+    let options = ReaderOptions {
+        retain_scan_data: false,
+    };
+    let mut reader =
+        Reader::with_options(input.as_slice(), options).map_err(|err| err.to_string())?;
+    let mut segments = vec![];
+    // Positions of the images listed in the MPF segment of the primary image
+    let mut mp_positions: Vec<usize> = vec![];
+
+    loop {
+        let segment = match reader.next_segment() {
+            Ok(segment) => segment,
+            Err(JfifError::IoError(err)) if err.kind() == ErrorKind::UnexpectedEof => break,
+            Err(err) => return Err(err.to_string()),
+        };
+
+        let end = segment.position + segment.length;
+
+        match &segment.kind {
+            SegmentKind::App2Mpf(mpf) if reader.image_index() == 0 && mp_positions.is_empty() => {
+                mp_positions = mpf
+                    .images
+                    .iter()
+                    .filter_map(|entry| entry.position(mpf.header_position))
+                    .collect();
+            }
+            SegmentKind::Eoi if !mp_positions.iter().any(|&position| position >= end) => {
+                // Trailers after the last image aren't parsed
+                segments.push(segment);
+                break;
+            }
+            _ => {}
+        }
+
+        segments.push(segment);
+    }
+
+    let is_kept = |kind: &SegmentKind| {
+        if app_nr(kind).is_none() && !matches!(kind, SegmentKind::Comment(_)) {
+            return true;
+        }
+
+        let kept = match &keep {
+            Some(keep) => keep.iter().any(|s| s.matches(kind)),
+            None => true,
+        };
+
+        kept && !drop.iter().any(|s| s.matches(kind))
+    };
+
+    let mut output = vec![];
+    // Position of every segment in the output or None if it was removed
+    let mut positions = vec![];
+    let mut removed = 0;
+    // End of the previous segment in the input
+    let mut end = 0;
+
+    for segment in &segments {
+        // Fill bytes and other data between segments are copied unchanged
+        if segment.position > end {
+            output.extend_from_slice(&input[end..segment.position]);
+        }
+        end = segment_end(segment);
+
+        if is_kept(&segment.kind) {
+            positions.push(Some(output.len()));
+            output.extend_from_slice(&input[segment.position..end]);
+        } else {
+            println!(
+                "Removed {} at 0x{:X}: {} bytes",
+                segment_name(&segment.kind),
+                segment.position,
+                segment.length
+            );
+            positions.push(None);
+            removed += 1;
+        }
+    }
+
+    update_mpf_offsets(&mut output, &mut segments, &positions)?;
+
+    // Data after the last complete segment is copied unchanged
+    output.extend_from_slice(&input[end.min(input.len())..]);
+
+    std::fs::write(output_path, &output).map_err(|err| err.to_string())?;
+
+    println!(
+        "Removed {} segments, saved {} bytes ({} -> {} bytes)",
+        removed,
+        input.len().saturating_sub(output.len()),
+        input.len(),
+        output.len()
+    );
+
+    Ok(())
+}
+
+/// End of a segment in the input
+///
+/// The length of scans and RST segments includes the marker following their data.
+fn segment_end(segment: &Segment) -> usize {
+    match &segment.kind {
+        SegmentKind::Scan(scan) => scan.data_position + scan.data_length,
+        SegmentKind::Rst(rst) => rst.data_position + rst.data_length,
+        _ => segment.position + segment.length,
+    }
+}
+
+/// Adjust MP entries to the new sizes and positions of all images
+///
+/// Only MPF segments are written again, all other segments are unchanged copies of the input.
+fn update_mpf_offsets(
+    output: &mut [u8],
+    segments: &mut [Segment],
+    positions: &[Option<usize>],
+) -> Result<(), String> {
+    for i in 0..segments.len() {
+        let new_position = match positions[i] {
+            Some(position) => position,
+            None => continue,
+        };
+
+        let updates: Vec<(usize, u32, Option<u32>)> = match &segments[i].kind {
+            SegmentKind::App2Mpf(mpf) => {
+                // Marker, length and signature are in front of the MP header
+                let new_header_position = new_position + 8;
+
+                mpf.images
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(index, entry)| {
+                        // The first image is the one containing the MPF segment
+                        let start = match entry.position(mpf.header_position) {
+                            Some(position) => segments.iter().position(|s| {
+                                s.position == position && matches!(s.kind, SegmentKind::Soi)
+                            })?,
+                            None => segments[..i]
+                                .iter()
+                                .rposition(|s| matches!(s.kind, SegmentKind::Soi))?,
+                        };
+                        let end = start
+                            + segments[start..]
+                                .iter()
+                                .position(|s| matches!(s.kind, SegmentKind::Eoi))?;
+
+                        let image_start = positions[start]?;
+                        let size = (positions[end]? + 2 - image_start) as u32;
+
+                        let offset = if entry.offset == 0 {
+                            None
+                        } else {
+                            Some(image_start.checked_sub(new_header_position)? as u32)
+                        };
+
+                        Some((index, size, offset))
+                    })
+                    .collect()
+            }
+            _ => continue,
+        };
+
+        if let SegmentKind::App2Mpf(mpf) = &mut segments[i].kind {
+            for (index, size, offset) in updates {
+                mpf.set_image_size(index, size)
+                    .map_err(|err| err.to_string())?;
+
+                if let Some(offset) = offset {
+                    mpf.set_image_offset(index, offset)
+                        .map_err(|err| err.to_string())?;
+                }
+            }
+
+            // The size of the segment didn't change
+            let mut writer = Writer::new(vec![]);
+            writer
+                .write_segment(&segments[i].kind)
+                .map_err(|err| err.to_string())?;
+            let data = writer.into_inner();
+            output[new_position..new_position + data.len()].copy_from_slice(&data);
+        }
+    }
+
+    Ok(())
+}
+
+fn segment_name(kind: &SegmentKind) -> String {
+    match app_nr(kind) {
+        Some(nr) => format!("App(0x{:X})", nr),
+        None => "COM".to_owned(),
+    }
+}
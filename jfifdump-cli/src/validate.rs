@@ -0,0 +1,46 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+
+use clap::{value_parser, Arg, ArgMatches, Command};
+
+use jfifdump::*;
+
+pub fn command() -> Command {
+    Command::new("validate")
+        .about("Check the structure of a file against ITU T.81")
+        .after_help("Every finding is printed with its position, severity and rule id.\nThe exit code is non-zero if any error was found.")
+        .arg(
+            Arg::new("INPUT")
+                .help("Jpeg file to use")
+                .value_parser(value_parser!(PathBuf))
+                .required(true),
+        )
+}
+
+pub fn run(matches: &ArgMatches) -> Result<(), String> {
+    let input_path = matches
+        .get_one::<PathBuf>("INPUT")
+        .expect("Required arg present");
+
+    let file = File::open(input_path).map_err(|err| err.to_string())?;
+    let findings = validate(BufReader::new(file)).map_err(|err| err.to_string())?;
+
+    for finding in &findings {
+        println!("{}", finding);
+    }
+
+    let errors = findings
+        .iter()
+        .filter(|f| f.severity == Severity::Error)
+        .count();
+    let warnings = findings.len() - errors;
+
+    if errors > 0 {
+        return Err(format!("{} errors and {} warnings", errors, warnings));
+    }
+
+    println!("Valid with {} warnings", warnings);
+
+    Ok(())
+}
@@ -0,0 +1,58 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn image(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../jfifdump/tests/images")
+        .join(name)
+}
+
+/// Run the decode command and return the written file
+fn decode(name: &str) -> Vec<u8> {
+    let output = std::env::temp_dir().join(format!("jfifdump-{}-{}.pnm", std::process::id(), name));
+
+    let status = Command::new(env!("CARGO_BIN_EXE_jfifdump"))
+        .arg("decode")
+        .arg(image(name))
+        .arg(&output)
+        .status()
+        .unwrap();
+
+    assert!(status.success());
+
+    let data = std::fs::read(&output).unwrap();
+    std::fs::remove_file(&output).unwrap();
+    data
+}
+
+#[test]
+fn progressive() {
+    let expected = std::fs::read(image("progressive.pnm")).unwrap();
+
+    // The expected pixels were written by libjpeg
+    assert!(decode("progressive.jpg") == expected);
+}
+
+#[test]
+fn lossless_fallback() {
+    let pnm = decode("lossless_4.jpg");
+    let header = b"P5\n16 8\n65535\n";
+
+    assert!(pnm.starts_with(header));
+
+    let samples: Vec<u16> = pnm[header.len()..]
+        .chunks_exact(2)
+        .map(|c| u16::from_be_bytes([c[0], c[1]]))
+        .collect();
+
+    assert_eq!(samples.len(), 16 * 8);
+
+    // Samples of the 16 bit image are given by the formula in jfifdump/tests/lossless.rs
+    for (i, &sample) in samples.iter().enumerate() {
+        let (x, y) = (i % 16, i / 16);
+        assert_eq!(
+            sample as usize,
+            x * 37 + y * 11 + (x * y) % 97 + (x ^ y) * 13
+        );
+    }
+}
@@ -0,0 +1,73 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use jfifdump::{Reader, SegmentKind};
+
+fn image(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../jfifdump/tests/images")
+        .join(name)
+}
+
+/// Run the strip command with `args` and return the written file
+fn strip(name: &str, args: &[&str]) -> Vec<u8> {
+    let output =
+        std::env::temp_dir().join(format!("jfifdump-{}-strip-{}", std::process::id(), name));
+
+    let status = Command::new(env!("CARGO_BIN_EXE_jfifdump"))
+        .arg("strip")
+        .args(args)
+        .arg(image(name))
+        .arg(&output)
+        .status()
+        .unwrap();
+
+    assert!(status.success());
+
+    let data = std::fs::read(&output).unwrap();
+    std::fs::remove_file(&output).unwrap();
+    data
+}
+
+#[test]
+fn mpf_offsets_point_at_embedded_image() {
+    let input = std::fs::read(image("metadata.jpg")).unwrap();
+    let embedded = std::fs::read(image("baseline.jpg")).unwrap();
+
+    // EXIF, XMP and Photoshop segments in front of the MPF segment and after it are removed
+    let output = strip("metadata.jpg", &[]);
+    assert!(output.len() < input.len());
+
+    let mut reader = Reader::new(output.as_slice()).unwrap();
+
+    let (mpf, primary_end) = loop {
+        let segment = reader.next_segment().unwrap();
+
+        if let SegmentKind::App2Mpf(mpf) = segment.kind {
+            let end = loop {
+                let segment = reader.next_segment().unwrap();
+                if let SegmentKind::Eoi = segment.kind {
+                    break segment.position + segment.length;
+                }
+            };
+
+            break (mpf, end);
+        }
+    };
+
+    assert_eq!(mpf.images.len(), 2);
+    assert_eq!(mpf.images[0].size as usize, primary_end);
+
+    let position = mpf.images[1].position(mpf.header_position).unwrap();
+    assert_eq!(&output[position..position + 2], [0xFF, 0xD8]);
+    assert_eq!(mpf.images[1].size as usize, embedded.len());
+    assert_eq!(&output[position..], embedded.as_slice());
+}
+
+#[test]
+fn kept_segments_are_copied_unchanged() {
+    let input = std::fs::read(image("metadata.jpg")).unwrap();
+
+    // The MPF segment is rewritten with the same offsets
+    assert_eq!(strip("metadata.jpg", &["--drop", "com"]), input);
+}
@@ -0,0 +1,335 @@
+use crate::{Dac, JfifError};
+
+/// Probability estimation state machine of Table D.3 of ITU T.81
+///
+/// Every entry contains Qe, the next index after LPS and MPS and whether to switch the MPS.
+/// The last entry isn't part of the table and is used for decisions with fixed probability.
+#[rustfmt::skip]
+const QE_TABLE: [(u16, u8, u8, bool); 114] = [
+    (0x5A1D, 1, 1, true), (0x2586, 14, 2, false), (0x1114, 16, 3, false),
+    (0x080B, 18, 4, false), (0x03D8, 20, 5, false), (0x01DA, 23, 6, false),
+    (0x00E5, 25, 7, false), (0x006F, 28, 8, false), (0x0036, 30, 9, false),
+    (0x001A, 33, 10, false), (0x000D, 35, 11, false), (0x0006, 9, 12, false),
+    (0x0003, 10, 13, false), (0x0001, 12, 13, false), (0x5A7F, 15, 15, true),
+    (0x3F25, 36, 16, false), (0x2CF2, 38, 17, false), (0x207C, 39, 18, false),
+    (0x17B9, 40, 19, false), (0x1182, 42, 20, false), (0x0CEF, 43, 21, false),
+    (0x09A1, 45, 22, false), (0x072F, 46, 23, false), (0x055C, 48, 24, false),
+    (0x0406, 49, 25, false), (0x0303, 51, 26, false), (0x0240, 52, 27, false),
+    (0x01B1, 54, 28, false), (0x0144, 56, 29, false), (0x00F5, 57, 30, false),
+    (0x00B7, 59, 31, false), (0x008A, 60, 32, false), (0x0068, 62, 33, false),
+    (0x004E, 63, 34, false), (0x003B, 32, 35, false), (0x002C, 33, 9, false),
+    (0x5AE1, 37, 37, true), (0x484C, 64, 38, false), (0x3A0D, 65, 39, false),
+    (0x2EF1, 67, 40, false), (0x261F, 68, 41, false), (0x1F33, 69, 42, false),
+    (0x19A8, 70, 43, false), (0x1518, 72, 44, false), (0x1177, 73, 45, false),
+    (0x0E74, 74, 46, false), (0x0BFB, 75, 47, false), (0x09F8, 77, 48, false),
+    (0x0861, 78, 49, false), (0x0706, 79, 50, false), (0x05CD, 48, 51, false),
+    (0x04DE, 50, 52, false), (0x040F, 50, 53, false), (0x0363, 51, 54, false),
+    (0x02D4, 52, 55, false), (0x025C, 53, 56, false), (0x01F8, 54, 57, false),
+    (0x01A4, 55, 58, false), (0x0160, 56, 59, false), (0x0125, 57, 60, false),
+    (0x00F6, 58, 61, false), (0x00CB, 59, 62, false), (0x00AB, 61, 63, false),
+    (0x008F, 61, 32, false), (0x5B12, 65, 65, true), (0x4D04, 80, 66, false),
+    (0x412C, 81, 67, false), (0x37D8, 82, 68, false), (0x2FE8, 83, 69, false),
+    (0x293C, 84, 70, false), (0x2379, 86, 71, false), (0x1EDF, 87, 72, false),
+    (0x1AA9, 87, 73, false), (0x174E, 72, 74, false), (0x1424, 72, 75, false),
+    (0x119C, 74, 76, false), (0x0F6B, 74, 77, false), (0x0D51, 75, 78, false),
+    (0x0BB6, 77, 79, false), (0x0A40, 77, 48, false), (0x5832, 80, 81, true),
+    (0x4D1C, 88, 82, false), (0x438E, 89, 83, false), (0x3BDD, 90, 84, false),
+    (0x34EE, 91, 85, false), (0x2EAE, 92, 86, false), (0x299A, 93, 87, false),
+    (0x2516, 86, 71, false), (0x5570, 88, 89, true), (0x4CA9, 95, 90, false),
+    (0x44D9, 96, 91, false), (0x3E22, 97, 92, false), (0x3824, 99, 93, false),
+    (0x32B4, 99, 94, false), (0x2E17, 93, 86, false), (0x56A8, 95, 96, true),
+    (0x4F46, 101, 97, false), (0x47E5, 102, 98, false), (0x41CF, 103, 99, false),
+    (0x3C3D, 104, 100, false), (0x375E, 99, 93, false), (0x5231, 105, 102, false),
+    (0x4C0F, 106, 103, false), (0x4639, 107, 104, false), (0x415E, 103, 99, false),
+    (0x5627, 105, 106, true), (0x50E7, 108, 107, false), (0x4B85, 109, 103, false),
+    (0x5597, 110, 109, false), (0x504F, 111, 107, false), (0x5A10, 110, 111, true),
+    (0x5522, 112, 109, false), (0x59EB, 112, 111, true), (0x5A1D, 113, 113, false),
+];
+
+/// State of the fixed probability estimate of 0.5
+const FIXED_STATE: u8 = 113;
+
+/// Conditioning values of all arithmetic coding tables as set by DAC segments
+pub(crate) struct Conditioning {
+    /// Lower and upper bound of DC tables
+    dc: [(u8, u8); 4],
+    /// Threshold of AC tables
+    ac: [u8; 4],
+}
+
+impl Default for Conditioning {
+    fn default() -> Self {
+        Self {
+            dc: [(0, 1); 4],
+            ac: [5; 4],
+        }
+    }
+}
+
+impl Conditioning {
+    pub(crate) fn update(&mut self, dac: &Dac) -> Result<(), JfifError> {
+        for param in &dac.params {
+            let (class, dest) = (param.class, param.dest as usize);
+
+            match (class, param.value) {
+                (0, value) if dest < 4 && value & 0x0F <= value >> 4 => {
+                    self.dc[dest] = (value & 0x0F, value >> 4);
+                }
+                (1, value @ 1..=63) if dest < 4 => self.ac[dest] = value,
+                _ => return Err(JfifError::InvalidConditioningTable(class, param.dest)),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Binary arithmetic decoder of Annex D of ITU T.81
+struct QmDecoder<'a> {
+    data: &'a [u8],
+    position: usize,
+    /// Set if a marker was found in the data
+    marker: bool,
+    c: i64,
+    a: i64,
+    ct: i32,
+}
+
+impl<'a> QmDecoder<'a> {
+    fn next_byte(&mut self) -> u8 {
+        if self.marker {
+            return 0;
+        }
+
+        let byte = match self.data.get(self.position) {
+            Some(&byte) => byte,
+            None => return 0,
+        };
+
+        self.position += 1;
+
+        if byte != 0xFF {
+            return byte;
+        }
+
+        while self.data.get(self.position) == Some(&0xFF) {
+            self.position += 1;
+        }
+
+        if self.data.get(self.position) == Some(&0x00) {
+            self.position += 1;
+            0xFF
+        } else {
+            // Zeros are decoded after a marker
+            self.marker = true;
+            0
+        }
+    }
+
+    /// Decode a single decision updating the statistics bin `state`
+    fn decode(&mut self, state: &mut u8) -> bool {
+        // Renormalization and data input (D.2.6)
+        while self.a < 0x8000 {
+            self.ct -= 1;
+
+            if self.ct < 0 {
+                self.c = (self.c << 8) | self.next_byte() as i64;
+                self.ct += 8;
+
+                if self.ct < 0 {
+                    // The first two bytes initialize the decoder
+                    self.ct += 1;
+                    if self.ct == 0 {
+                        self.a = 0x8000;
+                    }
+                }
+            }
+
+            self.a <<= 1;
+        }
+
+        let mut value = *state;
+        let (qe, next_lps, next_mps, switch) = QE_TABLE[(value & 0x7F) as usize];
+
+        let qe = qe as i64;
+        let after_lps = (value & 0x80) ^ next_lps ^ if switch { 0x80 } else { 0 };
+        let after_mps = (value & 0x80) | next_mps;
+
+        self.a -= qe;
+        let boundary = self.a << self.ct;
+
+        if self.c >= boundary {
+            self.c -= boundary;
+
+            if self.a < qe {
+                *state = after_mps;
+            } else {
+                *state = after_lps;
+                value ^= 0x80;
+            }
+
+            self.a = qe;
+        } else if self.a < 0x8000 {
+            if self.a < qe {
+                *state = after_lps;
+                value ^= 0x80;
+            } else {
+                *state = after_mps;
+            }
+        }
+
+        value & 0x80 != 0
+    }
+
+    /// Decode the bits below the leading one of `magnitude` (Figure F.24 of ITU T.81)
+    fn decode_magnitude_bits(&mut self, magnitude: i32, state: &mut u8) -> i32 {
+        let mut value = magnitude;
+        let mut bit = magnitude >> 1;
+
+        while bit != 0 {
+            if self.decode(state) {
+                value |= bit;
+            }
+            bit >>= 1;
+        }
+
+        value + 1
+    }
+}
+
+/// Decodes coefficients of a single restart interval of an arithmetic-coded scan
+///
+/// The statistics of all tables are reset for every restart interval.
+pub(crate) struct ArithmeticDecoder<'a> {
+    decoder: QmDecoder<'a>,
+    dc_statistics: [[u8; 64]; 4],
+    ac_statistics: [[u8; 256]; 4],
+}
+
+impl<'a> ArithmeticDecoder<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        Self {
+            decoder: QmDecoder {
+                data,
+                position: 0,
+                marker: false,
+                c: 0,
+                a: 0,
+                ct: -16,
+            },
+            dc_statistics: [[0; 64]; 4],
+            ac_statistics: [[0; 256]; 4],
+        }
+    }
+
+    /// Decode a decision with fixed probability like signs and correction bits
+    pub(crate) fn decode_fixed(&mut self) -> bool {
+        let mut state = FIXED_STATE;
+        self.decoder.decode(&mut state)
+    }
+
+    /// Decode the difference to the DC prediction (F.2.4.1 of ITU T.81)
+    ///
+    /// `context` is the conditioning category of the previous difference of the component.
+    pub(crate) fn decode_dc_difference(
+        &mut self,
+        table: u8,
+        context: &mut usize,
+        conditioning: &Conditioning,
+    ) -> Result<i32, JfifError> {
+        let (lower, upper) = conditioning.dc[table as usize];
+        let statistics = &mut self.dc_statistics[table as usize];
+
+        if !self.decoder.decode(&mut statistics[*context]) {
+            *context = 0;
+            return Ok(0);
+        }
+
+        let sign = self.decoder.decode(&mut statistics[*context + 1]) as usize;
+        let mut bin = *context + 2 + sign;
+
+        let mut magnitude = self.decoder.decode(&mut statistics[bin]) as i32;
+
+        if magnitude != 0 {
+            bin = 20;
+
+            while self.decoder.decode(&mut statistics[bin]) {
+                magnitude <<= 1;
+                if magnitude == 0x8000 {
+                    return Err(JfifError::InvalidScanData);
+                }
+                bin += 1;
+            }
+        }
+
+        *context = if magnitude < (1 << lower) >> 1 {
+            0
+        } else if magnitude > (1 << upper) >> 1 {
+            12 + sign * 4
+        } else {
+            4 + sign * 4
+        };
+
+        let value = self
+            .decoder
+            .decode_magnitude_bits(magnitude, &mut statistics[bin + 14]);
+
+        Ok(if sign != 0 { -value } else { value })
+    }
+
+    /// True if there are no more nonzero coefficients from `k` to the end of the band
+    pub(crate) fn decode_end_of_block(&mut self, table: u8, k: usize) -> bool {
+        self.decoder
+            .decode(&mut self.ac_statistics[table as usize][3 * (k - 1)])
+    }
+
+    /// True if the coefficient `k` is not zero
+    pub(crate) fn decode_nonzero(&mut self, table: u8, k: usize) -> bool {
+        self.decoder
+            .decode(&mut self.ac_statistics[table as usize][3 * (k - 1) + 1])
+    }
+
+    /// Correction bit of a coefficient that was nonzero in a previous scan
+    pub(crate) fn decode_correction(&mut self, table: u8, k: usize) -> bool {
+        self.decoder
+            .decode(&mut self.ac_statistics[table as usize][3 * (k - 1) + 2])
+    }
+
+    /// Decode the nonzero AC coefficient `k` (F.2.4.2 of ITU T.81)
+    pub(crate) fn decode_ac_value(
+        &mut self,
+        table: u8,
+        k: usize,
+        conditioning: &Conditioning,
+    ) -> Result<i32, JfifError> {
+        let sign = self.decode_fixed();
+
+        let statistics = &mut self.ac_statistics[table as usize];
+        let mut bin = 3 * (k - 1) + 2;
+
+        let mut magnitude = self.decoder.decode(&mut statistics[bin]) as i32;
+
+        if magnitude != 0 && self.decoder.decode(&mut statistics[bin]) {
+            magnitude <<= 1;
+            bin = if k <= conditioning.ac[table as usize] as usize {
+                189
+            } else {
+                217
+            };
+
+            while self.decoder.decode(&mut statistics[bin]) {
+                magnitude <<= 1;
+                if magnitude == 0x8000 {
+                    return Err(JfifError::InvalidScanData);
+                }
+                bin += 1;
+            }
+        }
+
+        let value = self
+            .decoder
+            .decode_magnitude_bits(magnitude, &mut statistics[bin + 14]);
+
+        Ok(if sign { -value } else { value })
+    }
+}
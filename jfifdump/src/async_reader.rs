@@ -0,0 +1,139 @@
+//! Reader for tokio's [AsyncRead] enabled by the `tokio` feature
+
+use std::io::{Error as IoError, ErrorKind};
+
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, BufReader};
+
+use crate::{
+    handle_segment, EncoderFeatures, ExtendedXmp, Handler, IccProfile, JfifError, MpEntry,
+    PushParser, ReaderOptions, Segment, Warning,
+};
+
+/// A reader for JFIF files from an asynchronous input
+///
+/// The input is passed to a [PushParser] as it arrives, so segments are parsed
+/// like [crate::Reader] does as soon as their data is available.
+pub struct AsyncReader<R: AsyncRead + Unpin> {
+    reader: BufReader<R>,
+    parser: PushParser,
+}
+
+impl<R: AsyncRead + Unpin> AsyncReader<R> {
+    /// Create a new reader
+    pub async fn new(reader: R) -> Result<Self, JfifError> {
+        Self::with_options(reader, ReaderOptions::default()).await
+    }
+
+    /// Create a new reader with options
+    pub async fn with_options(reader: R, options: ReaderOptions) -> Result<Self, JfifError> {
+        let mut reader = BufReader::new(reader);
+        let mut buf = [0u8; 2];
+
+        match reader.read_exact(&mut buf).await {
+            Ok(_) => {}
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => {
+                return Err(JfifError::JfifMarkerNotFound)
+            }
+            Err(err) => return Err(err.into()),
+        }
+
+        if buf != [0xFF, 0xD8] {
+            return Err(JfifError::JfifMarkerNotFound);
+        }
+
+        let mut parser = PushParser::with_options(options);
+        parser.feed(&buf);
+
+        Ok(Self { reader, parser })
+    }
+
+    /// Index of the image the last segment belongs to
+    ///
+    /// The primary image has index 0. Images embedded after its EOI like
+    /// the ones listed in an MPF segment are counted up from 1.
+    pub fn image_index(&self) -> usize {
+        self.parser.image_index()
+    }
+
+    /// MP entry of the primary image's MPF segment for the image starting at `position`
+    pub fn mp_entry(&self, position: usize) -> Option<&MpEntry> {
+        self.parser.mp_entry(position)
+    }
+
+    /// Extended XMP documents of the primary image reassembled from all chunks read so far
+    pub fn extended_xmp(&self) -> Vec<ExtendedXmp> {
+        self.parser.extended_xmp()
+    }
+
+    /// ICC profile of the primary image reassembled from all chunks read so far
+    pub fn icc_profile(&self) -> Option<IccProfile> {
+        self.parser.icc_profile()
+    }
+
+    /// Problems recovered from in all segments read so far
+    pub fn warnings(&self) -> &[Warning] {
+        self.parser.warnings()
+    }
+
+    /// Read the next segment of the input
+    pub async fn next_segment(&mut self) -> Result<Segment, JfifError> {
+        loop {
+            if let Some(segment) = self.parser.next_segment()? {
+                return Ok(segment);
+            }
+
+            let buf = self.reader.fill_buf().await?;
+
+            if buf.is_empty() {
+                return match self.parser.finish()? {
+                    Some(segment) => Ok(segment),
+                    None => Err(IoError::from(ErrorKind::UnexpectedEof).into()),
+                };
+            }
+
+            let length = buf.len();
+            self.parser.feed(buf);
+            self.reader.consume(length);
+        }
+    }
+}
+
+/// Read asynchronous JFIF input and call handler for all segments as soon as they are read
+pub async fn read_async<H: Handler, R: AsyncRead + Unpin>(
+    input: R,
+    handler: &mut H,
+) -> Result<(), JfifError> {
+    let mut reader = AsyncReader::new(input).await?;
+    let mut handled_warnings = 0;
+    let mut features = EncoderFeatures::new();
+
+    loop {
+        let segment = match reader.next_segment().await {
+            Ok(segment) => segment,
+            Err(JfifError::IoError(err)) if err.kind() == ErrorKind::UnexpectedEof => break,
+            Err(err) => return Err(err),
+        };
+
+        features.process(&segment.kind);
+
+        let mp_entry = reader.mp_entry(segment.position);
+        handle_segment(handler, segment, reader.image_index(), mp_entry);
+
+        for warning in &reader.warnings()[handled_warnings..] {
+            handler.handle_warning(warning);
+        }
+        handled_warnings = reader.warnings().len();
+    }
+
+    for xmp in reader.extended_xmp() {
+        handler.handle_extended_xmp(&xmp);
+    }
+
+    if let Some(profile) = reader.icc_profile() {
+        handler.handle_icc_profile(&profile);
+    }
+
+    handler.handle_encoder(&features.identify());
+
+    Ok(())
+}
@@ -0,0 +1,803 @@
+use std::io::Read;
+
+use crate::arithmetic::{ArithmeticDecoder, Conditioning};
+use crate::huffman::{BitReader, HuffmanTable};
+use crate::{Frame, JfifError, Reader, Scan, SegmentKind};
+
+/// Natural order index of every coefficient in zigzag order
+pub(crate) const ZIGZAG: [usize; 64] = [
+    0, 1, 8, 16, 9, 2, 3, 10, 17, 24, 32, 25, 18, 11, 4, 5, 12, 19, 26, 33, 40, 48, 41, 34, 27, 20,
+    13, 6, 7, 14, 21, 28, 35, 42, 49, 56, 57, 50, 43, 36, 29, 22, 15, 23, 30, 37, 44, 51, 58, 59,
+    52, 45, 38, 31, 39, 46, 53, 60, 61, 54, 47, 55, 62, 63,
+];
+
+/// Options of the decoders
+#[derive(Debug, Clone, Copy)]
+pub struct DecoderOptions {
+    /// Maximum number of bytes allocated for the coefficients, samples or pixels of an image
+    ///
+    /// The size is derived from the frame header, so a small file can claim a huge image.
+    /// Images exceeding it fail with [JfifError::ImageTooLarge]. Defaults to 512 MiB.
+    pub max_memory: usize,
+}
+
+impl Default for DecoderOptions {
+    fn default() -> Self {
+        Self {
+            max_memory: 512 << 20,
+        }
+    }
+}
+
+impl DecoderOptions {
+    /// Fail if `bytes` exceed the memory limit
+    pub(crate) fn check_memory(&self, bytes: usize) -> Result<(), JfifError> {
+        if bytes > self.max_memory {
+            return Err(JfifError::ImageTooLarge(bytes));
+        }
+        Ok(())
+    }
+}
+
+/// Quantized DCT coefficients of a frame component
+#[derive(Debug)]
+pub struct ComponentCoefficients {
+    pub id: u8,
+    pub horizontal_sampling_factor: u8,
+    pub vertical_sampling_factor: u8,
+    pub quantization_table: u8,
+    /// Number of samples per line of the component
+    pub width: usize,
+    /// Number of lines of the component
+    pub height: usize,
+    /// Number of blocks per line including blocks padding the last MCU
+    pub blocks_per_line: usize,
+    /// Number of block lines including blocks padding the last MCU
+    pub blocks_per_column: usize,
+    /// Blocks line by line with coefficients in natural order
+    pub blocks: Vec<[i16; 64]>,
+}
+
+impl ComponentCoefficients {
+    pub fn block(&self, x: usize, y: usize) -> &[i16; 64] {
+        &self.blocks[y * self.blocks_per_line + x]
+    }
+
+    /// Number of blocks per line covering the samples of the component
+    pub fn used_blocks_per_line(&self) -> usize {
+        self.width.div_ceil(8)
+    }
+
+    /// Number of block lines covering the samples of the component
+    pub fn used_blocks_per_column(&self) -> usize {
+        self.height.div_ceil(8)
+    }
+}
+
+struct ScanComponentState {
+    /// Index into the frame components
+    index: usize,
+    dc_table: u8,
+    ac_table: u8,
+    prediction: i32,
+    /// Conditioning category of the last DC difference of arithmetic-coded scans
+    dc_context: usize,
+}
+
+/// Position of the decoder inside the current scan
+struct ScanState {
+    components: Vec<ScanComponentState>,
+    /// Index of the next MCU to decode
+    mcu: usize,
+    mcu_count: usize,
+    arithmetic: bool,
+    progressive: bool,
+    selection_start: usize,
+    selection_end: usize,
+    /// Successive approximation bit position of the previous scan
+    bit_position_high: u8,
+    /// Successive approximation bit position (point transform)
+    bit_position_low: u8,
+    /// Number of remaining blocks of the current end-of-band run
+    eob_run: u32,
+}
+
+/// Decodes quantized DCT coefficients segment by segment
+///
+/// All segments of an image have to be passed to [CoefficientDecoder::process] in order.
+/// The entropy-coded data of a [Scan] and every following [Rst](crate::Rst) segment is
+/// decoded as soon as it is processed.
+///
+/// Supported are baseline, extended sequential and progressive frames with Huffman or
+/// arithmetic coding.
+/// Coefficients of progressive images are accumulated over all scans.
+#[derive(Default)]
+pub struct CoefficientDecoder {
+    pub(crate) options: DecoderOptions,
+    frame: Option<Frame>,
+    components: Vec<ComponentCoefficients>,
+    mcus_per_line: usize,
+    mcus_per_column: usize,
+    dc_tables: [Option<HuffmanTable>; 4],
+    ac_tables: [Option<HuffmanTable>; 4],
+    conditioning: Conditioning,
+    restart_interval: u16,
+    scan: Option<ScanState>,
+}
+
+impl CoefficientDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new decoder with options
+    pub fn with_options(options: DecoderOptions) -> Self {
+        Self {
+            options,
+            ..Self::default()
+        }
+    }
+
+    /// Frame of the decoded image
+    pub fn frame(&self) -> Option<&Frame> {
+        self.frame.as_ref()
+    }
+
+    /// Coefficients of all frame components
+    pub fn components(&self) -> &[ComponentCoefficients] {
+        &self.components
+    }
+
+    /// Update the decoder state with the next segment of the image
+    pub fn process(&mut self, kind: &SegmentKind) -> Result<(), JfifError> {
+        match kind {
+            SegmentKind::Frame(frame) => self.start_frame(frame),
+            SegmentKind::Dht(tables) => {
+                for table in tables {
+                    let slot = match (table.class, table.dest) {
+                        (0, dest @ 0..=3) => &mut self.dc_tables[dest as usize],
+                        (1, dest @ 0..=3) => &mut self.ac_tables[dest as usize],
+                        _ => return Err(JfifError::InvalidHuffmanTable(table.class, table.dest)),
+                    };
+
+                    *slot = Some(HuffmanTable::new(table)?);
+                }
+                Ok(())
+            }
+            SegmentKind::Dac(dac) => self.conditioning.update(dac),
+            SegmentKind::Dri(restart_interval) => {
+                self.restart_interval = *restart_interval;
+                Ok(())
+            }
+            SegmentKind::Scan(scan) => {
+                self.start_scan(scan)?;
+                self.decode_interval(&scan.data)
+            }
+            SegmentKind::Rst(rst) => {
+                if let Some(scan) = &mut self.scan {
+                    for component in &mut scan.components {
+                        component.prediction = 0;
+                        component.dc_context = 0;
+                    }
+                    scan.eob_run = 0;
+                }
+                self.decode_interval(&rst.data)
+            }
+            SegmentKind::Eoi => {
+                self.scan = None;
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn start_frame(&mut self, frame: &Frame) -> Result<(), JfifError> {
+        if !matches!(frame.sof, 0xC0..=0xC2 | 0xC9 | 0xCA) {
+            return Err(JfifError::UnsupportedFrame(frame.sof));
+        }
+
+        if frame.dimension_x == 0 || frame.dimension_y == 0 || frame.components.is_empty() {
+            return Err(JfifError::InvalidFrameDimensions(
+                frame.dimension_x,
+                frame.dimension_y,
+            ));
+        }
+
+        let max_h = frame
+            .components
+            .iter()
+            .map(|c| c.horizontal_sampling_factor.max(1) as usize)
+            .max()
+            .unwrap_or(1);
+        let max_v = frame
+            .components
+            .iter()
+            .map(|c| c.vertical_sampling_factor.max(1) as usize)
+            .max()
+            .unwrap_or(1);
+
+        let width = frame.dimension_x as usize;
+        let height = frame.dimension_y as usize;
+
+        self.mcus_per_line = width.div_ceil(8 * max_h);
+        self.mcus_per_column = height.div_ceil(8 * max_v);
+
+        let blocks: usize = frame
+            .components
+            .iter()
+            .map(|c| {
+                let h = c.horizontal_sampling_factor.max(1) as usize;
+                let v = c.vertical_sampling_factor.max(1) as usize;
+                (self.mcus_per_line * h).saturating_mul(self.mcus_per_column * v)
+            })
+            .fold(0, usize::saturating_add);
+
+        self.options
+            .check_memory(blocks.saturating_mul(std::mem::size_of::<[i16; 64]>()))?;
+
+        self.components = frame
+            .components
+            .iter()
+            .map(|c| {
+                let h = c.horizontal_sampling_factor.max(1) as usize;
+                let v = c.vertical_sampling_factor.max(1) as usize;
+
+                let blocks_per_line = self.mcus_per_line * h;
+                let blocks_per_column = self.mcus_per_column * v;
+
+                ComponentCoefficients {
+                    id: c.id,
+                    horizontal_sampling_factor: h as u8,
+                    vertical_sampling_factor: v as u8,
+                    quantization_table: c.quantization_table,
+                    width: (width * h).div_ceil(max_h),
+                    height: (height * v).div_ceil(max_v),
+                    blocks_per_line,
+                    blocks_per_column,
+                    blocks: vec![[0; 64]; blocks_per_line * blocks_per_column],
+                }
+            })
+            .collect();
+
+        self.frame = Some(frame.clone());
+        self.scan = None;
+
+        Ok(())
+    }
+
+    fn start_scan(&mut self, scan: &Scan) -> Result<(), JfifError> {
+        let (arithmetic, progressive) = match &self.frame {
+            Some(frame) => (frame.sof >= 0xC9, matches!(frame.sof, 0xC2 | 0xCA)),
+            None => return Err(JfifError::MissingFrame),
+        };
+
+        // `approximation_low` holds the high nibble and `approximation_high` the low nibble
+        let (selection_start, selection_end, bit_position_high, bit_position_low) = if progressive {
+            (
+                scan.selection_start,
+                scan.selection_end,
+                scan.approximation_low,
+                scan.approximation_high,
+            )
+        } else {
+            (0, 63, 0, 0)
+        };
+
+        let valid = selection_start <= selection_end
+            && selection_end <= 63
+            && (selection_start > 0 || selection_end == 0 || !progressive)
+            && (selection_start == 0 || scan.components.len() == 1)
+            && bit_position_high <= 13
+            && bit_position_low <= 13;
+
+        if !valid {
+            return Err(JfifError::InvalidScanParameters);
+        }
+
+        let components = scan
+            .components
+            .iter()
+            .map(|sc| {
+                let index = self
+                    .components
+                    .iter()
+                    .position(|c| c.id == sc.id)
+                    .ok_or(JfifError::InvalidScanComponent(sc.id))?;
+
+                if arithmetic && sc.dc_table > 3 {
+                    return Err(JfifError::InvalidConditioningTable(0, sc.dc_table));
+                }
+                if arithmetic && sc.ac_table > 3 {
+                    return Err(JfifError::InvalidConditioningTable(1, sc.ac_table));
+                }
+
+                Ok(ScanComponentState {
+                    index,
+                    dc_table: sc.dc_table,
+                    ac_table: sc.ac_table,
+                    prediction: 0,
+                    dc_context: 0,
+                })
+            })
+            .collect::<Result<Vec<_>, JfifError>>()?;
+
+        let mcu_count = match components.as_slice() {
+            [component] => {
+                let component = &self.components[component.index];
+                component.used_blocks_per_line() * component.used_blocks_per_column()
+            }
+            _ => self.mcus_per_line * self.mcus_per_column,
+        };
+
+        self.scan = Some(ScanState {
+            components,
+            mcu: 0,
+            mcu_count,
+            arithmetic,
+            progressive,
+            selection_start: selection_start as usize,
+            selection_end: selection_end as usize,
+            bit_position_high,
+            bit_position_low,
+            eob_run: 0,
+        });
+
+        Ok(())
+    }
+
+    /// Decode the MCUs of a single restart interval or of the whole scan without restart interval
+    fn decode_interval(&mut self, data: &[u8]) -> Result<(), JfifError> {
+        let mut scan = match self.scan.take() {
+            Some(scan) => scan,
+            None => return Ok(()),
+        };
+
+        let remaining = scan.mcu_count - scan.mcu;
+
+        let count = if self.restart_interval > 0 {
+            remaining.min(self.restart_interval as usize)
+        } else {
+            remaining
+        };
+
+        let components = &mut self.components;
+        let mcus_per_line = self.mcus_per_line;
+
+        let result = if scan.arithmetic {
+            let mut decoder = ArithmeticDecoder::new(data);
+            let conditioning = &self.conditioning;
+
+            decode_mcus(
+                components,
+                mcus_per_line,
+                &mut scan,
+                count,
+                |scan, index, block| {
+                    decode_block_arithmetic(&mut decoder, conditioning, scan, index, block)
+                },
+            )
+        } else {
+            let mut reader = BitReader::new(data);
+            let dc_tables = &self.dc_tables;
+            let ac_tables = &self.ac_tables;
+
+            decode_mcus(
+                components,
+                mcus_per_line,
+                &mut scan,
+                count,
+                |scan, index, block| {
+                    let sc = &scan.components[index];
+
+                    let dc_table = if scan.selection_start == 0 && scan.bit_position_high == 0 {
+                        Some(huffman_table(dc_tables, 0, sc.dc_table)?)
+                    } else {
+                        None
+                    };
+                    let ac_table = if scan.selection_end > 0 {
+                        Some(huffman_table(ac_tables, 1, sc.ac_table)?)
+                    } else {
+                        None
+                    };
+
+                    decode_block(&mut reader, scan, index, dc_table, ac_table, block)
+                },
+            )
+        };
+
+        self.scan = Some(scan);
+
+        result
+    }
+}
+
+/// Decode the next `count` MCUs of the scan calling `decode_block` for every block
+fn decode_mcus<F>(
+    components: &mut [ComponentCoefficients],
+    mcus_per_line: usize,
+    scan: &mut ScanState,
+    count: usize,
+    mut decode_block: F,
+) -> Result<(), JfifError>
+where
+    F: FnMut(&mut ScanState, usize, &mut [i16; 64]) -> Result<(), JfifError>,
+{
+    let interleaved = scan.components.len() > 1;
+
+    for _ in 0..count {
+        for index in 0..scan.components.len() {
+            let component = &mut components[scan.components[index].index];
+
+            if interleaved {
+                let h = component.horizontal_sampling_factor as usize;
+                let v = component.vertical_sampling_factor as usize;
+
+                let mcu_x = scan.mcu % mcus_per_line;
+                let mcu_y = scan.mcu / mcus_per_line;
+
+                for y in 0..v {
+                    for x in 0..h {
+                        let block = (mcu_y * v + y) * component.blocks_per_line + mcu_x * h + x;
+                        decode_block(scan, index, &mut component.blocks[block])?;
+                    }
+                }
+            } else {
+                let blocks_per_line = component.used_blocks_per_line();
+                let x = scan.mcu % blocks_per_line;
+                let y = scan.mcu / blocks_per_line;
+                let block = y * component.blocks_per_line + x;
+
+                decode_block(scan, index, &mut component.blocks[block])?;
+            }
+        }
+
+        scan.mcu += 1;
+    }
+
+    Ok(())
+}
+
+pub(crate) fn huffman_table(
+    tables: &[Option<HuffmanTable>; 4],
+    class: u8,
+    dest: u8,
+) -> Result<&HuffmanTable, JfifError> {
+    tables
+        .get(dest as usize)
+        .and_then(Option::as_ref)
+        .ok_or(JfifError::MissingHuffmanTable(class, dest))
+}
+
+/// Decode a Huffman-coded block of the scan component at `index`
+fn decode_block(
+    reader: &mut BitReader,
+    scan: &mut ScanState,
+    index: usize,
+    dc_table: Option<&HuffmanTable>,
+    ac_table: Option<&HuffmanTable>,
+    block: &mut [i16; 64],
+) -> Result<(), JfifError> {
+    if scan.selection_start == 0 {
+        match dc_table {
+            Some(dc_table) => {
+                let prediction = &mut scan.components[index].prediction;
+                decode_dc(reader, dc_table, prediction, scan.bit_position_low, block)?
+            }
+            None => decode_dc_refine(reader, scan.bit_position_low, block),
+        }
+    }
+
+    let ac_table = match ac_table {
+        Some(ac_table) => ac_table,
+        None => return Ok(()),
+    };
+
+    if !scan.progressive {
+        decode_ac_sequential(reader, ac_table, block)
+    } else if scan.bit_position_high == 0 {
+        decode_ac_first(reader, ac_table, scan, block)
+    } else {
+        decode_ac_refine(reader, ac_table, scan, block)
+    }
+}
+
+/// Decode the DC coefficient of a sequential or first progressive scan
+fn decode_dc(
+    reader: &mut BitReader,
+    dc_table: &HuffmanTable,
+    prediction: &mut i32,
+    bit_position: u8,
+    block: &mut [i16; 64],
+) -> Result<(), JfifError> {
+    let length = dc_table.decode(reader)?;
+
+    if length > 16 {
+        return Err(JfifError::InvalidScanData);
+    }
+
+    *prediction = prediction.wrapping_add(reader.receive_extend(length as u32));
+    block[0] = (*prediction << bit_position) as i16;
+
+    Ok(())
+}
+
+/// Add the next bit of the DC coefficient
+fn decode_dc_refine(reader: &mut BitReader, bit_position: u8, block: &mut [i16; 64]) {
+    if reader.read_bit() != 0 {
+        block[0] |= 1 << bit_position;
+    }
+}
+
+fn decode_ac_sequential(
+    reader: &mut BitReader,
+    ac_table: &HuffmanTable,
+    block: &mut [i16; 64],
+) -> Result<(), JfifError> {
+    let mut k = 1;
+
+    while k < 64 {
+        let rs = ac_table.decode(reader)?;
+        let run = (rs >> 4) as usize;
+        let length = rs & 0x0F;
+
+        if length == 0 {
+            if run != 15 {
+                break;
+            }
+            k += 16;
+            continue;
+        }
+
+        k += run;
+
+        if k > 63 {
+            return Err(JfifError::InvalidScanData);
+        }
+
+        block[ZIGZAG[k]] = reader.receive_extend(length as u32) as i16;
+        k += 1;
+    }
+
+    Ok(())
+}
+
+/// Decode the AC coefficients of the spectral band in the first progressive scan (G.1.2.2 of ITU T.81)
+fn decode_ac_first(
+    reader: &mut BitReader,
+    ac_table: &HuffmanTable,
+    scan: &mut ScanState,
+    block: &mut [i16; 64],
+) -> Result<(), JfifError> {
+    if scan.eob_run > 0 {
+        scan.eob_run -= 1;
+        return Ok(());
+    }
+
+    let mut k = scan.selection_start;
+
+    while k <= scan.selection_end {
+        let rs = ac_table.decode(reader)?;
+        let run = (rs >> 4) as u32;
+        let length = rs & 0x0F;
+
+        if length == 0 {
+            if run != 15 {
+                scan.eob_run = (1 << run) - 1 + reader.read_bits(run);
+                break;
+            }
+            k += 16;
+            continue;
+        }
+
+        k += run as usize;
+
+        if k > scan.selection_end {
+            return Err(JfifError::InvalidScanData);
+        }
+
+        let value = reader.receive_extend(length as u32) << scan.bit_position_low;
+        block[ZIGZAG[k]] = value as i16;
+        k += 1;
+    }
+
+    Ok(())
+}
+
+/// Add the next bit of the AC coefficients in the spectral band (G.1.2.3 of ITU T.81)
+fn decode_ac_refine(
+    reader: &mut BitReader,
+    ac_table: &HuffmanTable,
+    scan: &mut ScanState,
+    block: &mut [i16; 64],
+) -> Result<(), JfifError> {
+    let positive = 1i16 << scan.bit_position_low;
+    let negative = -1i16 << scan.bit_position_low;
+
+    let mut k = scan.selection_start;
+
+    if scan.eob_run == 0 {
+        while k <= scan.selection_end {
+            let rs = ac_table.decode(reader)?;
+            let mut run = (rs >> 4) as i32;
+            let length = rs & 0x0F;
+
+            let value = match length {
+                0 if run != 15 => {
+                    scan.eob_run = (1 << run) + reader.read_bits(run as u32);
+                    break;
+                }
+                0 => 0,
+                1 if reader.read_bit() != 0 => positive,
+                1 => negative,
+                _ => return Err(JfifError::InvalidScanData),
+            };
+
+            // Skip `run` zero coefficients refining all nonzero coefficients on the way
+            while k <= scan.selection_end {
+                let coefficient = &mut block[ZIGZAG[k]];
+
+                if *coefficient != 0 {
+                    refine_ac(reader, coefficient, positive, negative);
+                } else {
+                    run -= 1;
+                    if run < 0 {
+                        break;
+                    }
+                }
+
+                k += 1;
+            }
+
+            if value != 0 {
+                if k > scan.selection_end {
+                    return Err(JfifError::InvalidScanData);
+                }
+                block[ZIGZAG[k]] = value;
+            }
+
+            k += 1;
+        }
+    }
+
+    if scan.eob_run > 0 {
+        while k <= scan.selection_end {
+            let coefficient = &mut block[ZIGZAG[k]];
+
+            if *coefficient != 0 {
+                refine_ac(reader, coefficient, positive, negative);
+            }
+
+            k += 1;
+        }
+
+        scan.eob_run -= 1;
+    }
+
+    Ok(())
+}
+
+fn refine_ac(reader: &mut BitReader, coefficient: &mut i16, positive: i16, negative: i16) {
+    if reader.read_bit() != 0 && *coefficient & positive == 0 {
+        if *coefficient >= 0 {
+            *coefficient += positive;
+        } else {
+            *coefficient += negative;
+        }
+    }
+}
+
+/// Decode an arithmetic-coded block of the scan component at `index` (Annex F and G of ITU T.81)
+fn decode_block_arithmetic(
+    decoder: &mut ArithmeticDecoder,
+    conditioning: &Conditioning,
+    scan: &mut ScanState,
+    index: usize,
+    block: &mut [i16; 64],
+) -> Result<(), JfifError> {
+    let sc = &mut scan.components[index];
+    let bit_position = scan.bit_position_low;
+
+    if scan.selection_start == 0 {
+        if scan.bit_position_high == 0 {
+            let difference =
+                decoder.decode_dc_difference(sc.dc_table, &mut sc.dc_context, conditioning)?;
+            sc.prediction = sc.prediction.wrapping_add(difference);
+            block[0] = (sc.prediction << bit_position) as i16;
+        } else if decoder.decode_fixed() {
+            block[0] |= 1 << bit_position;
+        }
+    }
+
+    if scan.selection_end == 0 {
+        return Ok(());
+    }
+
+    let table = sc.ac_table;
+    let start = scan.selection_start.max(1);
+    let end = scan.selection_end;
+
+    if scan.bit_position_high == 0 {
+        let mut k = start;
+
+        while k <= end {
+            if decoder.decode_end_of_block(table, k) {
+                break;
+            }
+
+            while !decoder.decode_nonzero(table, k) {
+                k += 1;
+                if k > end {
+                    return Err(JfifError::InvalidScanData);
+                }
+            }
+
+            let value = decoder.decode_ac_value(table, k, conditioning)?;
+            block[ZIGZAG[k]] = (value << bit_position) as i16;
+            k += 1;
+        }
+    } else {
+        let positive = 1i16 << bit_position;
+        let negative = -1i16 << bit_position;
+
+        // End of block of the previous scan
+        let last = (1..=end)
+            .rev()
+            .find(|&k| block[ZIGZAG[k]] != 0)
+            .unwrap_or(0);
+
+        let mut k = start;
+
+        while k <= end {
+            if k > last && decoder.decode_end_of_block(table, k) {
+                break;
+            }
+
+            loop {
+                let coefficient = &mut block[ZIGZAG[k]];
+
+                if *coefficient != 0 {
+                    if decoder.decode_correction(table, k) {
+                        *coefficient += if *coefficient < 0 { negative } else { positive };
+                    }
+                    break;
+                }
+
+                if decoder.decode_nonzero(table, k) {
+                    *coefficient = if decoder.decode_fixed() {
+                        negative
+                    } else {
+                        positive
+                    };
+                    break;
+                }
+
+                k += 1;
+                if k > end {
+                    return Err(JfifError::InvalidScanData);
+                }
+            }
+
+            k += 1;
+        }
+    }
+
+    Ok(())
+}
+
+/// Decode the quantized DCT coefficients of the primary image
+pub fn decode_coefficients<R: Read>(input: R) -> Result<CoefficientDecoder, JfifError> {
+    let mut reader = Reader::new(input)?;
+    let mut decoder = CoefficientDecoder::new();
+
+    loop {
+        let segment = reader.next_segment()?;
+
+        if let SegmentKind::Eoi = segment.kind {
+            break;
+        }
+
+        decoder.process(&segment.kind)?;
+    }
+
+    Ok(decoder)
+}
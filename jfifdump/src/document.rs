@@ -0,0 +1,154 @@
+//! Owned model of all segments of a JFIF file
+
+use std::io::{ErrorKind, Read};
+
+use crate::{
+    ColorSpace, Dht, Dqt, ExtendedXmp, Frame, IccProfile, JfifError, Reader, Scan, Segment,
+    SegmentKind, Warning,
+};
+
+/// All segments of a JFIF file with queries for commonly needed content
+///
+/// Queries only consider the primary image, i.e. all segments up to the first EOI.
+/// Segments of embedded images are still available with [JfifDocument::segments].
+#[derive(Debug)]
+pub struct JfifDocument {
+    segments: Vec<Segment>,
+    icc_profile: Option<IccProfile>,
+    extended_xmp: Vec<ExtendedXmp>,
+    warnings: Vec<Warning>,
+}
+
+impl JfifDocument {
+    /// Read all segments of the input
+    ///
+    /// Like [crate::read] a truncated input ends the document without an error.
+    pub fn parse<R: Read>(input: R) -> Result<JfifDocument, JfifError> {
+        let mut reader = Reader::new(input)?;
+        let mut segments = vec![];
+
+        loop {
+            match reader.next_segment() {
+                Ok(segment) => segments.push(segment),
+                Err(JfifError::IoError(err)) if err.kind() == ErrorKind::UnexpectedEof => break,
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(JfifDocument {
+            segments,
+            icc_profile: reader.icc_profile(),
+            extended_xmp: reader.extended_xmp(),
+            warnings: reader.warnings().to_vec(),
+        })
+    }
+
+    /// All segments in the order of the file including those of embedded images
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// Segments of the primary image
+    fn primary(&self) -> impl Iterator<Item = &SegmentKind> {
+        let end = self
+            .segments
+            .iter()
+            .position(|s| matches!(s.kind, SegmentKind::Eoi))
+            .map_or(self.segments.len(), |index| index + 1);
+
+        self.segments[..end].iter().map(|s| &s.kind)
+    }
+
+    /// First frame header of the primary image
+    pub fn frame(&self) -> Option<&Frame> {
+        self.primary().find_map(|kind| match kind {
+            SegmentKind::Frame(frame) => Some(frame),
+            _ => None,
+        })
+    }
+
+    /// Width and height of the primary image
+    ///
+    /// The height is 0 if it is defined by a DNL segment.
+    pub fn dimensions(&self) -> Option<(u16, u16)> {
+        self.frame()
+            .map(|frame| (frame.dimension_x, frame.dimension_y))
+    }
+
+    /// Color space of the primary image determined like libjpeg does
+    pub fn color_space(&self) -> Option<ColorSpace> {
+        let jfif = self
+            .primary()
+            .any(|kind| matches!(kind, SegmentKind::App0Jfif(_)));
+
+        let adobe = self.primary().find_map(|kind| match kind {
+            SegmentKind::App14Adobe(adobe) => Some(adobe),
+            _ => None,
+        });
+
+        self.frame().map(|frame| frame.color_space(jfif, adobe))
+    }
+
+    /// Whether the primary image uses progressive coding
+    pub fn is_progressive(&self) -> bool {
+        self.frame()
+            .is_some_and(|frame| matches!(frame.sof, 0xC2 | 0xC6 | 0xCA | 0xCE))
+    }
+
+    /// Quantization tables of the primary image in the order they are defined
+    pub fn quant_tables(&self) -> Vec<&Dqt> {
+        self.primary()
+            .filter_map(|kind| match kind {
+                SegmentKind::Dqt(tables) => Some(tables.iter()),
+                _ => None,
+            })
+            .flatten()
+            .collect()
+    }
+
+    /// Huffman tables of the primary image in the order they are defined
+    pub fn huffman_tables(&self) -> Vec<&Dht> {
+        self.primary()
+            .filter_map(|kind| match kind {
+                SegmentKind::Dht(tables) => Some(tables.iter()),
+                _ => None,
+            })
+            .flatten()
+            .collect()
+    }
+
+    /// ICC profile of the primary image reassembled from all chunks
+    pub fn icc_profile(&self) -> Option<&IccProfile> {
+        self.icc_profile.as_ref()
+    }
+
+    /// Extended XMP documents of the primary image reassembled from all chunks
+    pub fn extended_xmp(&self) -> &[ExtendedXmp] {
+        &self.extended_xmp
+    }
+
+    /// Content of all COM segments of the primary image
+    pub fn comments(&self) -> Vec<&[u8]> {
+        self.primary()
+            .filter_map(|kind| match kind {
+                SegmentKind::Comment(data) => Some(data.as_slice()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Scans of the primary image including their entropy-coded data
+    pub fn scans(&self) -> Vec<&Scan> {
+        self.primary()
+            .filter_map(|kind| match kind {
+                SegmentKind::Scan(scan) => Some(scan),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Problems the reader recovered from in the whole file
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+}
@@ -8,9 +8,29 @@ pub enum JfifError {
     InvalidMarkerLength(usize),
     InvalidDhtSegmentLength(usize),
     InvalidDqtSegmentLength(usize),
+    InvalidDqtPrecision(u8),
     InvalidFrameSegmentLength(usize),
     InvalidDriLength(usize),
     InvalidScanHeaderLength(usize),
+    InvalidTiffHeader,
+    InvalidTiffOffset(usize),
+    SegmentTooLong(usize),
+    InvalidHuffmanTable(u8, u8),
+    MissingHuffmanTable(u8, u8),
+    InvalidHuffmanCode,
+    InvalidScanData,
+    MissingScanData,
+    InvalidScanComponent(u8),
+    InvalidScanParameters,
+    InvalidConditioningTable(u8, u8),
+    MissingFrame,
+    UnsupportedFrame(u8),
+    InvalidFrameDimensions(u16, u16),
+    UnsupportedPrecision(u8),
+    MissingQuantizationTable(u8),
+    UnsupportedColorSpace(&'static str),
+    UnsupportedSampling,
+    ImageTooLarge(usize),
     IoError(std::io::Error),
 }
 
@@ -29,9 +49,37 @@ impl Display for JfifError {
             InvalidMarkerLength(length) => write!(f, "Invalid length for marker: {}", length),
             InvalidDhtSegmentLength(length) => write!(f, "Invalid dht segment length: {}", length),
             InvalidDqtSegmentLength(length) => write!(f, "Invalid dqt segment length: {}", length),
+            InvalidDqtPrecision(precision) => write!(f, "Invalid dqt precision: {}", precision),
             InvalidFrameSegmentLength(length) => write!(f, "Invalid dqt segment length: {}", length),
             InvalidDriLength(length) => write!(f, "Invalid dri length: {}", length),
             InvalidScanHeaderLength(length) => write!(f, "Invalid scan header length: {}", length),
+            InvalidTiffHeader => write!(f, "Invalid tiff header"),
+            InvalidTiffOffset(offset) => write!(f, "Invalid tiff offset: {}", offset),
+            SegmentTooLong(length) => write!(f, "Segment too long: {}", length),
+            InvalidHuffmanTable(class, dest) => {
+                write!(f, "Invalid huffman table: class {} dest {}", class, dest)
+            }
+            MissingHuffmanTable(class, dest) => {
+                write!(f, "Missing huffman table: class {} dest {}", class, dest)
+            }
+            InvalidHuffmanCode => write!(f, "Invalid huffman code"),
+            InvalidScanData => write!(f, "Invalid scan data"),
+            MissingScanData => write!(f, "Entropy-coded data was not retained"),
+            InvalidScanComponent(id) => write!(f, "Invalid scan component: {}", id),
+            InvalidScanParameters => {
+                write!(f, "Invalid spectral selection or successive approximation")
+            }
+            InvalidConditioningTable(class, dest) => {
+                write!(f, "Invalid dac table: class {} dest {}", class, dest)
+            }
+            MissingFrame => write!(f, "Scan without frame"),
+            UnsupportedFrame(sof) => write!(f, "Unsupported frame type: 0x{:X}", sof),
+            InvalidFrameDimensions(x, y) => write!(f, "Invalid frame dimensions: {}x{}", x, y),
+            UnsupportedPrecision(precision) => write!(f, "Unsupported precision: {}", precision),
+            MissingQuantizationTable(dest) => write!(f, "Missing quantization table: {}", dest),
+            UnsupportedColorSpace(name) => write!(f, "Unsupported color space: {}", name),
+            UnsupportedSampling => write!(f, "Unsupported sampling factors"),
+            ImageTooLarge(bytes) => write!(f, "Image too large: {} bytes needed", bytes),
             IoError(err) => err.fmt(f),
         }
     }
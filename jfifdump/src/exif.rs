@@ -0,0 +1,681 @@
+use std::collections::HashSet;
+
+use crate::JfifError;
+
+const TAG_EXIF_IFD: u16 = 0x8769;
+const TAG_GPS_IFD: u16 = 0x8825;
+const TAG_INTEROP_IFD: u16 = 0xA005;
+
+/// Maximum number of IFDs followed in the IFD0 -> IFD1 -> ... chain
+const MAX_IFD_CHAIN: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteOrder {
+    LittleEndian,
+    BigEndian,
+}
+
+impl ByteOrder {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ByteOrder::LittleEndian => "Little endian",
+            ByteOrder::BigEndian => "Big endian",
+        }
+    }
+}
+
+/// Minimal reader for TIFF structured data as used by EXIF and MPF
+pub(crate) struct Tiff<'a> {
+    data: &'a [u8],
+    byte_order: ByteOrder,
+}
+
+impl<'a> Tiff<'a> {
+    /// Parse the TIFF header and return the reader with the offset of the first IFD
+    pub(crate) fn new(data: &'a [u8]) -> Result<(Self, u32), JfifError> {
+        let byte_order = match data.get(0..2) {
+            Some(b"II") => ByteOrder::LittleEndian,
+            Some(b"MM") => ByteOrder::BigEndian,
+            _ => return Err(JfifError::InvalidTiffHeader),
+        };
+
+        let tiff = Tiff { data, byte_order };
+
+        if tiff.u16(2)? != 42 {
+            return Err(JfifError::InvalidTiffHeader);
+        }
+
+        let first_ifd = tiff.u32(4)?;
+
+        Ok((tiff, first_ifd))
+    }
+
+    pub(crate) fn byte_order(&self) -> ByteOrder {
+        self.byte_order
+    }
+
+    fn slice(&self, offset: usize, length: usize) -> Result<&'a [u8], JfifError> {
+        offset
+            .checked_add(length)
+            .and_then(|end| self.data.get(offset..end))
+            .ok_or(JfifError::InvalidTiffOffset(offset))
+    }
+
+    fn u16(&self, offset: usize) -> Result<u16, JfifError> {
+        let bytes = self.slice(offset, 2)?;
+        Ok(self.u16_from(bytes))
+    }
+
+    fn u32(&self, offset: usize) -> Result<u32, JfifError> {
+        let bytes = self.slice(offset, 4)?;
+        Ok(self.u32_from(bytes))
+    }
+
+    pub(crate) fn u16_from(&self, bytes: &[u8]) -> u16 {
+        let bytes = [bytes[0], bytes[1]];
+        match self.byte_order {
+            ByteOrder::LittleEndian => u16::from_le_bytes(bytes),
+            ByteOrder::BigEndian => u16::from_be_bytes(bytes),
+        }
+    }
+
+    pub(crate) fn u32_from(&self, bytes: &[u8]) -> u32 {
+        let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        match self.byte_order {
+            ByteOrder::LittleEndian => u32::from_le_bytes(bytes),
+            ByteOrder::BigEndian => u32::from_be_bytes(bytes),
+        }
+    }
+
+    fn u64_from(&self, bytes: &[u8]) -> u64 {
+        let bytes = [
+            bytes[0], bytes[1], bytes[2], bytes[3], bytes[4], bytes[5], bytes[6], bytes[7],
+        ];
+        match self.byte_order {
+            ByteOrder::LittleEndian => u64::from_le_bytes(bytes),
+            ByteOrder::BigEndian => u64::from_be_bytes(bytes),
+        }
+    }
+
+    /// Read all entries of the IFD at `offset` and return them with the offset of the next IFD
+    pub(crate) fn read_ifd(&self, offset: u32) -> Result<(Vec<IfdEntry>, u32), JfifError> {
+        let offset = offset as usize;
+        let count = self.u16(offset)? as usize;
+
+        let mut entries = Vec::with_capacity(count);
+
+        for i in 0..count {
+            let entry_offset = entry_offset(offset, i)?;
+
+            let tag = self.u16(entry_offset)?;
+            let field_type = self.u16(entry_offset + 2)?;
+            let count = self.u32(entry_offset + 4)?;
+
+            let value = self.read_value(field_type, count, entry_offset + 8)?;
+
+            entries.push(IfdEntry {
+                tag,
+                field_type,
+                count,
+                value,
+            });
+        }
+
+        let next = self.u32(entry_offset(offset, count)?)?;
+
+        Ok((entries, next))
+    }
+
+    /// Offset of the value data of the first entry with `tag` in the IFD at `offset`
+    pub(crate) fn value_offset(&self, offset: u32, tag: u16) -> Result<Option<usize>, JfifError> {
+        let offset = offset as usize;
+        let count = self.u16(offset)? as usize;
+
+        for i in 0..count {
+            let entry_offset = entry_offset(offset, i)?;
+
+            if self.u16(entry_offset)? != tag {
+                continue;
+            }
+
+            let size = field_type_size(self.u16(entry_offset + 2)?).unwrap_or(1);
+            let total = (self.u32(entry_offset + 4)? as usize).saturating_mul(size);
+
+            return if total <= 4 {
+                Ok(Some(entry_offset + 8))
+            } else {
+                Ok(Some(self.u32(entry_offset + 8)? as usize))
+            };
+        }
+
+        Ok(None)
+    }
+
+    fn read_value(
+        &self,
+        field_type: u16,
+        count: u32,
+        value_offset: usize,
+    ) -> Result<ExifValue, JfifError> {
+        let size = match field_type_size(field_type) {
+            Some(size) => size,
+            None => return Ok(ExifValue::Unknown(self.slice(value_offset, 4)?.to_vec())),
+        };
+
+        let total = (count as usize)
+            .checked_mul(size)
+            .ok_or(JfifError::InvalidTiffOffset(value_offset))?;
+
+        let data = if total <= 4 {
+            self.slice(value_offset, total)?
+        } else {
+            let offset = self.u32(value_offset)?;
+
+            // Broken offsets are common in MakerNotes and don't affect other entries
+            match self.slice(offset as usize, total) {
+                Ok(data) => data,
+                Err(_) => return Ok(ExifValue::InvalidOffset(offset)),
+            }
+        };
+
+        let chunks = data.chunks_exact(size);
+
+        Ok(match field_type {
+            1 => ExifValue::Byte(data.to_vec()),
+            2 => ExifValue::Ascii(data.to_vec()),
+            3 => ExifValue::Short(chunks.map(|c| self.u16_from(c)).collect()),
+            4 => ExifValue::Long(chunks.map(|c| self.u32_from(c)).collect()),
+            5 => ExifValue::Rational(
+                chunks
+                    .map(|c| (self.u32_from(c), self.u32_from(&c[4..])))
+                    .collect(),
+            ),
+            6 => ExifValue::SByte(data.iter().map(|&v| v as i8).collect()),
+            7 => ExifValue::Undefined(data.to_vec()),
+            8 => ExifValue::SShort(chunks.map(|c| self.u16_from(c) as i16).collect()),
+            9 => ExifValue::SLong(chunks.map(|c| self.u32_from(c) as i32).collect()),
+            10 => ExifValue::SRational(
+                chunks
+                    .map(|c| (self.u32_from(c) as i32, self.u32_from(&c[4..]) as i32))
+                    .collect(),
+            ),
+            11 => ExifValue::Float(chunks.map(|c| f32::from_bits(self.u32_from(c))).collect()),
+            12 => ExifValue::Double(chunks.map(|c| f64::from_bits(self.u64_from(c))).collect()),
+            13 => ExifValue::Ifd(chunks.map(|c| self.u32_from(c)).collect()),
+            _ => unreachable!(),
+        })
+    }
+}
+
+fn field_type_size(field_type: u16) -> Option<usize> {
+    match field_type {
+        1 | 2 | 6 | 7 => Some(1),
+        3 | 8 => Some(2),
+        4 | 9 | 11 | 13 => Some(4),
+        5 | 10 | 12 => Some(8),
+        _ => None,
+    }
+}
+
+/// Parse the content of an APP1 segment after the `Exif\0\0` signature
+pub(crate) fn parse_exif(data: &[u8]) -> Result<App1Exif<&[u8]>, JfifError> {
+    let (tiff, first_ifd) = Tiff::new(data)?;
+    let byte_order = tiff.byte_order();
+
+    let mut visited = HashSet::new();
+    let mut ifds = vec![];
+    let mut offset = first_ifd;
+
+    while offset != 0 && ifds.len() < MAX_IFD_CHAIN {
+        let ifd = match read_ifd_tree(&tiff, IfdKind::Ifd(ifds.len()), offset, &mut visited) {
+            Ok(ifd) => ifd,
+            // IFDs before a broken link are kept
+            Err(_) if !ifds.is_empty() => break,
+            Err(err) => return Err(err),
+        };
+        offset = ifd.1;
+        ifds.push(ifd.0);
+    }
+
+    Ok(App1Exif {
+        byte_order,
+        ifds,
+        data,
+    })
+}
+
+/// Offset of the entry at `index` of the IFD at `offset`
+///
+/// An `index` equal to the entry count gives the offset of the next IFD offset.
+fn entry_offset(offset: usize, index: usize) -> Result<usize, JfifError> {
+    index
+        .checked_mul(12)
+        .and_then(|size| size.checked_add(2))
+        .and_then(|size| offset.checked_add(size))
+        .ok_or(JfifError::InvalidTiffOffset(offset))
+}
+
+fn read_ifd_tree(
+    tiff: &Tiff,
+    kind: IfdKind,
+    offset: u32,
+    visited: &mut HashSet<u32>,
+) -> Result<(Ifd, u32), JfifError> {
+    if !visited.insert(offset) {
+        return Err(JfifError::InvalidTiffOffset(offset as usize));
+    }
+
+    let (entries, next) = tiff.read_ifd(offset)?;
+
+    let mut sub_ifds = vec![];
+
+    for entry in &entries {
+        let sub_kind = match (kind, entry.tag) {
+            (IfdKind::Ifd(0), TAG_EXIF_IFD) => IfdKind::Exif,
+            (IfdKind::Ifd(0), TAG_GPS_IFD) => IfdKind::Gps,
+            (IfdKind::Exif, TAG_INTEROP_IFD) => IfdKind::Interop,
+            _ => continue,
+        };
+
+        // A broken sub IFD is skipped, its offset remains visible in the entry
+        if let Some(sub_offset) = entry.value.as_offset() {
+            if let Ok((sub_ifd, _)) = read_ifd_tree(tiff, sub_kind, sub_offset, visited) {
+                sub_ifds.push(sub_ifd);
+            }
+        }
+    }
+
+    Ok((
+        Ifd {
+            kind,
+            offset,
+            entries,
+            sub_ifds,
+        },
+        next,
+    ))
+}
+
+/// Content of an APP1 segment with `Exif\0\0` signature
+///
+/// The raw data is borrowed from the input if read by [SliceReader](crate::SliceReader).
+#[derive(Debug)]
+pub struct App1Exif<D = Vec<u8>> {
+    pub byte_order: ByteOrder,
+    /// IFD chain starting with IFD0. Exif, GPS and Interop IFDs are attached as sub IFDs.
+    pub ifds: Vec<Ifd>,
+    /// Raw TIFF data without the `Exif\0\0` signature
+    pub data: D,
+}
+
+impl App1Exif<&[u8]> {
+    /// Copy the borrowed raw data
+    pub fn into_owned(self) -> App1Exif {
+        App1Exif {
+            byte_order: self.byte_order,
+            ifds: self.ifds,
+            data: self.data.to_vec(),
+        }
+    }
+}
+
+impl<D> App1Exif<D> {
+    /// Find the first entry with the given tag in all IFDs of the given kind
+    pub fn find(&self, kind: IfdKind, tag: u16) -> Option<&IfdEntry> {
+        fn find_in(ifds: &[Ifd], kind: IfdKind, tag: u16) -> Option<&IfdEntry> {
+            ifds.iter().find_map(|ifd| {
+                if ifd.kind == kind {
+                    ifd.entries.iter().find(|e| e.tag == tag)
+                } else {
+                    find_in(&ifd.sub_ifds, kind, tag)
+                }
+            })
+        }
+
+        find_in(&self.ifds, kind, tag)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IfdKind {
+    /// Position in the main IFD chain (IFD0 is the primary image, IFD1 the thumbnail)
+    Ifd(usize),
+    Exif,
+    Gps,
+    Interop,
+    /// MP Index IFD of a Multi-Picture Format segment
+    MpIndex,
+    /// MP Attribute IFD of a Multi-Picture Format segment
+    MpAttribute,
+}
+
+impl IfdKind {
+    pub fn name(&self) -> String {
+        match self {
+            IfdKind::Ifd(nr) => format!("IFD{}", nr),
+            IfdKind::Exif => "Exif IFD".to_owned(),
+            IfdKind::Gps => "GPS IFD".to_owned(),
+            IfdKind::Interop => "Interop IFD".to_owned(),
+            IfdKind::MpIndex => "MP Index IFD".to_owned(),
+            IfdKind::MpAttribute => "MP Attribute IFD".to_owned(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Ifd {
+    pub kind: IfdKind,
+    /// Offset of the IFD relative to the start of the TIFF header
+    pub offset: u32,
+    pub entries: Vec<IfdEntry>,
+    pub sub_ifds: Vec<Ifd>,
+}
+
+#[derive(Debug)]
+pub struct IfdEntry {
+    pub tag: u16,
+    pub field_type: u16,
+    pub count: u32,
+    pub value: ExifValue,
+}
+
+impl IfdEntry {
+    /// Name of the tag if it is known for the given IFD
+    pub fn tag_name(&self, kind: IfdKind) -> Option<&'static str> {
+        tag_name(kind, self.tag)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExifValue {
+    Byte(Vec<u8>),
+    Ascii(Vec<u8>),
+    Short(Vec<u16>),
+    Long(Vec<u32>),
+    Rational(Vec<(u32, u32)>),
+    SByte(Vec<i8>),
+    Undefined(Vec<u8>),
+    SShort(Vec<i16>),
+    SLong(Vec<i32>),
+    SRational(Vec<(i32, i32)>),
+    Float(Vec<f32>),
+    Double(Vec<f64>),
+    Ifd(Vec<u32>),
+    /// Field type not defined by TIFF 6.0. Contains the raw value field.
+    Unknown(Vec<u8>),
+    /// Value stored outside the TIFF data at the contained offset
+    InvalidOffset(u32),
+}
+
+impl ExifValue {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            ExifValue::Byte(_) => "BYTE",
+            ExifValue::Ascii(_) => "ASCII",
+            ExifValue::Short(_) => "SHORT",
+            ExifValue::Long(_) => "LONG",
+            ExifValue::Rational(_) => "RATIONAL",
+            ExifValue::SByte(_) => "SBYTE",
+            ExifValue::Undefined(_) => "UNDEFINED",
+            ExifValue::SShort(_) => "SSHORT",
+            ExifValue::SLong(_) => "SLONG",
+            ExifValue::SRational(_) => "SRATIONAL",
+            ExifValue::Float(_) => "FLOAT",
+            ExifValue::Double(_) => "DOUBLE",
+            ExifValue::Ifd(_) => "IFD",
+            ExifValue::Unknown(_) => "UNKNOWN",
+            ExifValue::InvalidOffset(_) => "INVALID",
+        }
+    }
+
+    /// Return the ASCII value without trailing NUL bytes
+    pub fn as_ascii(&self) -> Option<String> {
+        match self {
+            ExifValue::Ascii(data) => {
+                let end = data.iter().position(|&v| v == 0).unwrap_or(data.len());
+                Some(String::from_utf8_lossy(&data[..end]).into_owned())
+            }
+            _ => None,
+        }
+    }
+
+    /// Return the first value as unsigned integer if this is an integer type
+    pub fn as_u32(&self) -> Option<u32> {
+        match self {
+            ExifValue::Byte(v) => v.first().map(|&v| v as u32),
+            ExifValue::Short(v) => v.first().map(|&v| v as u32),
+            ExifValue::Long(v) | ExifValue::Ifd(v) => v.first().copied(),
+            _ => None,
+        }
+    }
+
+    fn as_offset(&self) -> Option<u32> {
+        match self {
+            ExifValue::Long(v) | ExifValue::Ifd(v) => v.first().copied(),
+            _ => None,
+        }
+    }
+
+    /// Format the value for display showing at most `max` elements
+    pub fn format(&self, max: usize) -> String {
+        fn join<T, F: Fn(&T) -> String>(values: &[T], max: usize, f: F) -> String {
+            let mut result = values
+                .iter()
+                .take(max)
+                .map(f)
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            if values.len() > max {
+                result.push_str(&format!(", ... ({} values)", values.len()));
+            }
+
+            result
+        }
+
+        match self {
+            ExifValue::Ascii(_) => format!("{:?}", self.as_ascii().unwrap_or_default()),
+            ExifValue::Byte(v) | ExifValue::Undefined(v) | ExifValue::Unknown(v) => {
+                join(v, max, |v| format!("0x{:02X}", v))
+            }
+            ExifValue::Short(v) => join(v, max, |v| v.to_string()),
+            ExifValue::Long(v) => join(v, max, |v| v.to_string()),
+            ExifValue::Ifd(v) => join(v, max, |v| format!("0x{:X}", v)),
+            ExifValue::Rational(v) => join(v, max, |(n, d)| format!("{}/{}", n, d)),
+            ExifValue::SByte(v) => join(v, max, |v| v.to_string()),
+            ExifValue::SShort(v) => join(v, max, |v| v.to_string()),
+            ExifValue::SLong(v) => join(v, max, |v| v.to_string()),
+            ExifValue::SRational(v) => join(v, max, |(n, d)| format!("{}/{}", n, d)),
+            ExifValue::Float(v) => join(v, max, |v| v.to_string()),
+            ExifValue::Double(v) => join(v, max, |v| v.to_string()),
+            ExifValue::InvalidOffset(offset) => format!("invalid offset 0x{:X}", offset),
+        }
+    }
+}
+
+/// Return the name of well known tags
+pub fn tag_name(kind: IfdKind, tag: u16) -> Option<&'static str> {
+    match kind {
+        IfdKind::Gps => gps_tag_name(tag),
+        IfdKind::Interop => match tag {
+            0x0001 => Some("InteroperabilityIndex"),
+            0x0002 => Some("InteroperabilityVersion"),
+            _ => None,
+        },
+        IfdKind::MpIndex | IfdKind::MpAttribute => mpf_tag_name(tag),
+        _ => tiff_tag_name(tag),
+    }
+}
+
+fn tiff_tag_name(tag: u16) -> Option<&'static str> {
+    Some(match tag {
+        0x0100 => "ImageWidth",
+        0x0101 => "ImageLength",
+        0x0102 => "BitsPerSample",
+        0x0103 => "Compression",
+        0x0106 => "PhotometricInterpretation",
+        0x010E => "ImageDescription",
+        0x010F => "Make",
+        0x0110 => "Model",
+        0x0111 => "StripOffsets",
+        0x0112 => "Orientation",
+        0x0115 => "SamplesPerPixel",
+        0x0116 => "RowsPerStrip",
+        0x0117 => "StripByteCounts",
+        0x011A => "XResolution",
+        0x011B => "YResolution",
+        0x011C => "PlanarConfiguration",
+        0x0128 => "ResolutionUnit",
+        0x012D => "TransferFunction",
+        0x0131 => "Software",
+        0x0132 => "DateTime",
+        0x013B => "Artist",
+        0x013E => "WhitePoint",
+        0x013F => "PrimaryChromaticities",
+        0x0201 => "JPEGInterchangeFormat",
+        0x0202 => "JPEGInterchangeFormatLength",
+        0x0211 => "YCbCrCoefficients",
+        0x0212 => "YCbCrSubSampling",
+        0x0213 => "YCbCrPositioning",
+        0x0214 => "ReferenceBlackWhite",
+        0x02BC => "XMLPacket",
+        0x8298 => "Copyright",
+        0x829A => "ExposureTime",
+        0x829D => "FNumber",
+        0x83BB => "IPTC-NAA",
+        0x8649 => "ImageResources",
+        0x8769 => "ExifIFDPointer",
+        0x8773 => "InterColorProfile",
+        0x8822 => "ExposureProgram",
+        0x8824 => "SpectralSensitivity",
+        0x8825 => "GPSInfoIFDPointer",
+        0x8827 => "PhotographicSensitivity",
+        0x8828 => "OECF",
+        0x8830 => "SensitivityType",
+        0x8832 => "RecommendedExposureIndex",
+        0x9000 => "ExifVersion",
+        0x9003 => "DateTimeOriginal",
+        0x9004 => "DateTimeDigitized",
+        0x9010 => "OffsetTime",
+        0x9011 => "OffsetTimeOriginal",
+        0x9012 => "OffsetTimeDigitized",
+        0x9101 => "ComponentsConfiguration",
+        0x9102 => "CompressedBitsPerPixel",
+        0x9201 => "ShutterSpeedValue",
+        0x9202 => "ApertureValue",
+        0x9203 => "BrightnessValue",
+        0x9204 => "ExposureBiasValue",
+        0x9205 => "MaxApertureValue",
+        0x9206 => "SubjectDistance",
+        0x9207 => "MeteringMode",
+        0x9208 => "LightSource",
+        0x9209 => "Flash",
+        0x920A => "FocalLength",
+        0x9214 => "SubjectArea",
+        0x927C => "MakerNote",
+        0x9286 => "UserComment",
+        0x9290 => "SubSecTime",
+        0x9291 => "SubSecTimeOriginal",
+        0x9292 => "SubSecTimeDigitized",
+        0xA000 => "FlashpixVersion",
+        0xA001 => "ColorSpace",
+        0xA002 => "PixelXDimension",
+        0xA003 => "PixelYDimension",
+        0xA004 => "RelatedSoundFile",
+        0xA005 => "InteroperabilityIFDPointer",
+        0xA20B => "FlashEnergy",
+        0xA20E => "FocalPlaneXResolution",
+        0xA20F => "FocalPlaneYResolution",
+        0xA210 => "FocalPlaneResolutionUnit",
+        0xA214 => "SubjectLocation",
+        0xA215 => "ExposureIndex",
+        0xA217 => "SensingMethod",
+        0xA300 => "FileSource",
+        0xA301 => "SceneType",
+        0xA302 => "CFAPattern",
+        0xA401 => "CustomRendered",
+        0xA402 => "ExposureMode",
+        0xA403 => "WhiteBalance",
+        0xA404 => "DigitalZoomRatio",
+        0xA405 => "FocalLengthIn35mmFilm",
+        0xA406 => "SceneCaptureType",
+        0xA407 => "GainControl",
+        0xA408 => "Contrast",
+        0xA409 => "Saturation",
+        0xA40A => "Sharpness",
+        0xA40C => "SubjectDistanceRange",
+        0xA420 => "ImageUniqueID",
+        0xA430 => "CameraOwnerName",
+        0xA431 => "BodySerialNumber",
+        0xA432 => "LensSpecification",
+        0xA433 => "LensMake",
+        0xA434 => "LensModel",
+        0xA435 => "LensSerialNumber",
+        0xA460 => "CompositeImage",
+        0xC4A5 => "PrintImageMatching",
+        _ => return None,
+    })
+}
+
+fn mpf_tag_name(tag: u16) -> Option<&'static str> {
+    Some(match tag {
+        0xB000 => "MPFVersion",
+        0xB001 => "NumberOfImages",
+        0xB002 => "MPEntry",
+        0xB003 => "ImageUIDList",
+        0xB004 => "TotalFrames",
+        0xB101 => "MPIndividualNum",
+        0xB201 => "PanOrientation",
+        0xB202 => "PanOverlap_H",
+        0xB203 => "PanOverlap_V",
+        0xB204 => "BaseViewpointNum",
+        0xB205 => "ConvergenceAngle",
+        0xB206 => "BaselineLength",
+        0xB207 => "VerticalDivergence",
+        0xB208 => "AxisDistance_X",
+        0xB209 => "AxisDistance_Y",
+        0xB20A => "AxisDistance_Z",
+        0xB20B => "YawAngle",
+        0xB20C => "PitchAngle",
+        0xB20D => "RollAngle",
+        _ => return None,
+    })
+}
+
+fn gps_tag_name(tag: u16) -> Option<&'static str> {
+    Some(match tag {
+        0x0000 => "GPSVersionID",
+        0x0001 => "GPSLatitudeRef",
+        0x0002 => "GPSLatitude",
+        0x0003 => "GPSLongitudeRef",
+        0x0004 => "GPSLongitude",
+        0x0005 => "GPSAltitudeRef",
+        0x0006 => "GPSAltitude",
+        0x0007 => "GPSTimeStamp",
+        0x0008 => "GPSSatellites",
+        0x0009 => "GPSStatus",
+        0x000A => "GPSMeasureMode",
+        0x000B => "GPSDOP",
+        0x000C => "GPSSpeedRef",
+        0x000D => "GPSSpeed",
+        0x000E => "GPSTrackRef",
+        0x000F => "GPSTrack",
+        0x0010 => "GPSImgDirectionRef",
+        0x0011 => "GPSImgDirection",
+        0x0012 => "GPSMapDatum",
+        0x0013 => "GPSDestLatitudeRef",
+        0x0014 => "GPSDestLatitude",
+        0x0015 => "GPSDestLongitudeRef",
+        0x0016 => "GPSDestLongitude",
+        0x0017 => "GPSDestBearingRef",
+        0x0018 => "GPSDestBearing",
+        0x0019 => "GPSDestDistanceRef",
+        0x001A => "GPSDestDistance",
+        0x001B => "GPSProcessingMethod",
+        0x001C => "GPSAreaInformation",
+        0x001D => "GPSDateStamp",
+        0x001E => "GPSDifferential",
+        0x001F => "GPSHPositioningError",
+        _ => return None,
+    })
+}
@@ -0,0 +1,437 @@
+//! Identification of the software that probably produced an image
+
+use std::fmt::Display;
+use std::io::{ErrorKind, Read};
+
+use crate::quality::{estimate_scaled_quality, update_tables, BaseTables, QualityEstimate};
+use crate::{estimate_quality, Dqt, IfdKind, JfifError, Reader, ReaderOptions, SegmentKind};
+
+/// Default quantization table of mozjpeg (N. Robidoux) used for all destinations
+const MOZJPEG: [u16; 64] = [
+    16, 16, 16, 18, 25, 37, 56, 85, 16, 17, 20, 27, 34, 40, 53, 75, 16, 20, 24, 31, 43, 62, 91,
+    135, 18, 27, 31, 40, 53, 74, 106, 156, 25, 34, 43, 53, 69, 94, 131, 189, 37, 40, 62, 74, 94,
+    124, 169, 238, 56, 53, 91, 106, 131, 169, 226, 311, 85, 75, 135, 156, 189, 238, 311, 418,
+];
+
+const MOZJPEG_TABLES: BaseTables = [&MOZJPEG, &MOZJPEG];
+
+/// Code lengths of the DC and AC Huffman tables of Annex K.3 of ITU T.81
+const STANDARD_HUFFMAN: [(u8, [u8; 16]); 4] = [
+    (0, [0, 1, 5, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0]),
+    (0, [0, 3, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0]),
+    (1, [0, 2, 1, 3, 3, 2, 4, 3, 5, 5, 4, 4, 0, 0, 1, 125]),
+    (1, [0, 2, 1, 2, 4, 4, 3, 4, 7, 5, 4, 4, 0, 1, 2, 119]),
+];
+
+const ANDROID_MAKES: &[&str] = &[
+    "samsung", "Google", "HUAWEI", "Xiaomi", "OnePlus", "motorola", "LGE", "OPPO", "vivo",
+];
+
+/// Software that can be identified by [EncoderFeatures::identify]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoder {
+    /// libjpeg or libjpeg-turbo
+    ///
+    /// Both write the same tables and markers for the same settings, so they
+    /// can't be told apart by the segments of an image.
+    Libjpeg,
+    Mozjpeg,
+    Photoshop,
+    PhotoshopSaveForWeb,
+    Apple,
+    AndroidCamera,
+    Gimp,
+    ImageMagick,
+    Guetzli,
+}
+
+impl Encoder {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Encoder::Libjpeg => "libjpeg/libjpeg-turbo",
+            Encoder::Mozjpeg => "mozjpeg",
+            Encoder::Photoshop => "Adobe Photoshop",
+            Encoder::PhotoshopSaveForWeb => "Adobe Photoshop (Save for Web)",
+            Encoder::Apple => "Apple camera",
+            Encoder::AndroidCamera => "Android camera",
+            Encoder::Gimp => "GIMP",
+            Encoder::ImageMagick => "ImageMagick",
+            Encoder::Guetzli => "guetzli",
+        }
+    }
+}
+
+/// Property of an image that hints at the software that produced it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Evidence {
+    /// Quantization tables are Annex K tables scaled like libjpeg does
+    IjgTables,
+    /// IJG tables of a specific quality
+    IjgQuality(u8),
+    /// Quantization tables are the scaled default tables of mozjpeg
+    MozjpegTables,
+    /// Quantization tables are neither IJG nor mozjpeg tables
+    CustomTables,
+    /// All Huffman tables have the code lengths of the Annex K tables
+    StandardHuffman,
+    /// Huffman tables were optimized for the image
+    OptimizedHuffman,
+    Progressive,
+    Sequential,
+    /// Every DQT segment contains a single table
+    SeparateDqt,
+    /// All quantization tables are stored in one DQT segment
+    CombinedDqt,
+    /// Every DHT segment contains a single table
+    SeparateDht,
+    /// All Huffman tables of the first scan are stored in one DHT segment
+    CombinedDht,
+    /// Marker of the segment following SOI
+    FirstSegment(u8),
+    /// JFIF density of 72x72 dots per inch
+    Jfif72Dpi,
+    /// APP12 segment with `Ducky` signature
+    Ducky,
+    /// APP13 Photoshop segment
+    PhotoshopResources,
+    /// APP14 Adobe segment
+    Adobe,
+    /// Comment starting with the text
+    Comment(&'static str),
+    /// Exif make equal to one of the texts
+    ExifMake(&'static [&'static str]),
+    /// Exif software starting with the text
+    ExifSoftware(&'static str),
+}
+
+impl Display for Evidence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use Evidence::*;
+        match self {
+            IjgTables => write!(f, "IJG quantization tables"),
+            IjgQuality(quality) => write!(f, "IJG quality {}", quality),
+            MozjpegTables => write!(f, "mozjpeg quantization tables"),
+            CustomTables => write!(f, "custom quantization tables"),
+            StandardHuffman => write!(f, "standard Huffman tables"),
+            OptimizedHuffman => write!(f, "optimized Huffman tables"),
+            Progressive => write!(f, "progressive"),
+            Sequential => write!(f, "sequential"),
+            SeparateDqt => write!(f, "one table per DQT segment"),
+            CombinedDqt => write!(f, "single DQT segment"),
+            SeparateDht => write!(f, "one table per DHT segment"),
+            CombinedDht => write!(f, "single DHT segment"),
+            FirstSegment(marker) => write!(f, "first segment 0x{:X}", marker),
+            Jfif72Dpi => write!(f, "JFIF density 72 dpi"),
+            Ducky => write!(f, "Ducky segment"),
+            PhotoshopResources => write!(f, "Photoshop segment"),
+            Adobe => write!(f, "Adobe segment"),
+            Comment(text) => write!(f, "comment \"{}\"", text),
+            ExifMake(makes) => write!(f, "Exif make {}", makes.join("/")),
+            ExifSoftware(text) => write!(f, "Exif software \"{}\"", text),
+        }
+    }
+}
+
+/// Signature database with the weight of every evidence for all encoders
+const SIGNATURES: &[(Encoder, &[(Evidence, u32)])] = &[
+    (
+        Encoder::Libjpeg,
+        &[
+            (Evidence::IjgTables, 3),
+            (Evidence::StandardHuffman, 2),
+            (Evidence::SeparateDqt, 1),
+            (Evidence::SeparateDht, 1),
+            (Evidence::FirstSegment(0xE0), 1),
+        ],
+    ),
+    (
+        Encoder::Mozjpeg,
+        &[
+            (Evidence::MozjpegTables, 5),
+            (Evidence::OptimizedHuffman, 1),
+            (Evidence::Progressive, 1),
+            (Evidence::SeparateDqt, 1),
+            (Evidence::FirstSegment(0xE0), 1),
+        ],
+    ),
+    (
+        Encoder::Photoshop,
+        &[
+            (Evidence::PhotoshopResources, 4),
+            (Evidence::Adobe, 2),
+            (Evidence::ExifSoftware("Adobe Photoshop"), 2),
+        ],
+    ),
+    (
+        Encoder::PhotoshopSaveForWeb,
+        &[
+            (Evidence::Ducky, 5),
+            (Evidence::Adobe, 1),
+            (Evidence::CombinedDqt, 1),
+            (Evidence::FirstSegment(0xEC), 1),
+        ],
+    ),
+    (
+        Encoder::Apple,
+        &[
+            (Evidence::ExifMake(&["Apple"]), 6),
+            (Evidence::FirstSegment(0xE1), 1),
+        ],
+    ),
+    (
+        Encoder::AndroidCamera,
+        &[
+            (Evidence::ExifMake(ANDROID_MAKES), 5),
+            (Evidence::FirstSegment(0xE1), 1),
+            (Evidence::CustomTables, 1),
+        ],
+    ),
+    (
+        Encoder::Gimp,
+        &[
+            (Evidence::Comment("Created with GIMP"), 8),
+            (Evidence::IjgTables, 2),
+        ],
+    ),
+    (
+        Encoder::ImageMagick,
+        &[
+            (Evidence::Jfif72Dpi, 3),
+            (Evidence::IjgTables, 2),
+            (Evidence::OptimizedHuffman, 2),
+            (Evidence::IjgQuality(92), 1),
+        ],
+    ),
+    (
+        Encoder::Guetzli,
+        &[
+            (Evidence::CustomTables, 2),
+            (Evidence::CombinedDqt, 2),
+            (Evidence::CombinedDht, 2),
+            (Evidence::OptimizedHuffman, 1),
+            (Evidence::Sequential, 1),
+        ],
+    ),
+];
+
+/// Encoder matching the features of an image
+#[derive(Debug, Clone)]
+pub struct EncoderMatch {
+    pub encoder: Encoder,
+    /// Sum of the weights of the evidence found in the image
+    pub score: u32,
+    /// Sum of the weights of all evidence of the signature
+    pub max_score: u32,
+    pub evidence: Vec<Evidence>,
+}
+
+/// Collects the features of the primary image used to identify its encoder
+///
+/// All segments have to be passed to [EncoderFeatures::process] in order.
+/// Segments after the first EOI are ignored.
+#[derive(Debug, Default)]
+pub struct EncoderFeatures {
+    /// Markers of all segments up to the first scan
+    markers: Vec<u8>,
+    /// Number of tables of every DQT segment
+    dqt_sizes: Vec<usize>,
+    /// Number of tables of every DHT segment
+    dht_sizes: Vec<usize>,
+    quantization_tables: Vec<Dqt>,
+    /// Exact IJG quality of the quantization tables
+    ijg: Option<QualityEstimate>,
+    /// Exact mozjpeg quality of the quantization tables
+    mozjpeg: Option<QualityEstimate>,
+    /// Class and code lengths of all Huffman tables
+    huffman_tables: Vec<(u8, [u8; 16])>,
+    /// All Huffman tables have the code lengths of the Annex K tables
+    standard_huffman: bool,
+    /// Unit and density of the JFIF segment
+    jfif_density: Option<(u8, u16, u16)>,
+    ducky: bool,
+    photoshop: bool,
+    adobe: bool,
+    exif_make: Option<String>,
+    exif_software: Option<String>,
+    comments: Vec<String>,
+    sof: Option<u8>,
+    complete: bool,
+}
+
+impl EncoderFeatures {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Update the features with the next segment of the image
+    pub fn process(&mut self, kind: &SegmentKind) {
+        if self.complete {
+            return;
+        }
+
+        if !self.markers.contains(&0xDA) {
+            self.markers.push(marker(kind));
+        }
+
+        match kind {
+            SegmentKind::Eoi => self.complete = true,
+            SegmentKind::App { nr: 12, data } => self.ducky |= data.starts_with(b"Ducky"),
+            SegmentKind::App0Jfif(jfif) => {
+                self.jfif_density = Some((jfif.unit, jfif.x_density, jfif.y_density))
+            }
+            SegmentKind::App1Exif(exif) => {
+                let ascii = |tag| {
+                    exif.find(IfdKind::Ifd(0), tag)
+                        .and_then(|entry| entry.value.as_ascii())
+                };
+                self.exif_make = ascii(0x010F);
+                self.exif_software = ascii(0x0131);
+            }
+            SegmentKind::App13Photoshop(_) => self.photoshop = true,
+            SegmentKind::App14Adobe(_) => self.adobe = true,
+            SegmentKind::Dqt(tables) => {
+                self.dqt_sizes.push(tables.len());
+                update_tables(&mut self.quantization_tables, tables);
+
+                self.ijg = estimate_quality(&self.quantization_tables).filter(|q| q.exact);
+                self.mozjpeg = estimate_scaled_quality(&self.quantization_tables, &MOZJPEG_TABLES)
+                    .filter(|q| q.exact);
+            }
+            SegmentKind::Dht(tables) => {
+                self.dht_sizes.push(tables.len());
+                self.huffman_tables
+                    .extend(tables.iter().map(|t| (t.class, t.code_lengths)));
+
+                self.standard_huffman = !self.huffman_tables.is_empty()
+                    && self
+                        .huffman_tables
+                        .iter()
+                        .all(|table| STANDARD_HUFFMAN.contains(table));
+            }
+            SegmentKind::Frame(frame) => self.sof = Some(frame.sof),
+            SegmentKind::Comment(data) => self
+                .comments
+                .push(String::from_utf8_lossy(data).trim().to_owned()),
+            _ => {}
+        }
+    }
+
+    /// Whether the image has the evidence
+    pub fn has(&self, evidence: &Evidence) -> bool {
+        match evidence {
+            Evidence::IjgTables => self.ijg.is_some(),
+            Evidence::IjgQuality(quality) => self.ijg.is_some_and(|q| q.quality == *quality),
+            Evidence::MozjpegTables => self.mozjpeg.is_some(),
+            Evidence::CustomTables => {
+                !self.quantization_tables.is_empty() && self.ijg.is_none() && self.mozjpeg.is_none()
+            }
+            Evidence::StandardHuffman => self.standard_huffman,
+            Evidence::OptimizedHuffman => !self.huffman_tables.is_empty() && !self.standard_huffman,
+            Evidence::Progressive => matches!(self.sof, Some(0xC2 | 0xC6 | 0xCA | 0xCE)),
+            Evidence::Sequential => matches!(self.sof, Some(0xC0 | 0xC1 | 0xC9)),
+            Evidence::SeparateDqt => {
+                !self.dqt_sizes.is_empty() && self.dqt_sizes.iter().all(|&size| size == 1)
+            }
+            Evidence::CombinedDqt => self.dqt_sizes.len() == 1 && self.dqt_sizes[0] > 1,
+            Evidence::SeparateDht => {
+                !self.dht_sizes.is_empty() && self.dht_sizes.iter().all(|&size| size == 1)
+            }
+            Evidence::CombinedDht => self.dht_sizes.first().is_some_and(|&size| size > 1),
+            Evidence::FirstSegment(marker) => self.markers.get(1) == Some(marker),
+            Evidence::Jfif72Dpi => self.jfif_density == Some((1, 72, 72)),
+            Evidence::Ducky => self.ducky,
+            Evidence::PhotoshopResources => self.photoshop,
+            Evidence::Adobe => self.adobe,
+            Evidence::Comment(text) => self.comments.iter().any(|c| c.starts_with(text)),
+            Evidence::ExifMake(makes) => self
+                .exif_make
+                .as_ref()
+                .is_some_and(|make| makes.contains(&make.trim())),
+            Evidence::ExifSoftware(text) => self
+                .exif_software
+                .as_ref()
+                .is_some_and(|software| software.starts_with(text)),
+        }
+    }
+
+    /// Encoders with at least two thirds of the weight of their signature found in the image
+    ///
+    /// The best match comes first.
+    pub fn identify(&self) -> Vec<EncoderMatch> {
+        let mut matches: Vec<EncoderMatch> = SIGNATURES
+            .iter()
+            .map(|(encoder, signature)| {
+                let found: Vec<&(Evidence, u32)> =
+                    signature.iter().filter(|(e, _)| self.has(e)).collect();
+
+                EncoderMatch {
+                    encoder: *encoder,
+                    score: found.iter().map(|(_, weight)| weight).sum(),
+                    max_score: signature.iter().map(|(_, weight)| weight).sum(),
+                    evidence: found.iter().map(|(e, _)| *e).collect(),
+                }
+            })
+            .filter(|m| m.score * 3 >= m.max_score * 2)
+            .collect();
+
+        matches.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then((b.score * a.max_score).cmp(&(a.score * b.max_score)))
+        });
+
+        matches
+    }
+}
+
+/// Marker of a segment without the leading 0xFF
+fn marker(kind: &SegmentKind) -> u8 {
+    match kind {
+        SegmentKind::Soi => 0xD8,
+        SegmentKind::Eoi => 0xD9,
+        SegmentKind::App { nr, .. } => 0xE0 + nr,
+        SegmentKind::App0Jfif(_) | SegmentKind::App0Jfxx(_) => 0xE0,
+        SegmentKind::App1Exif(_) | SegmentKind::App1Xmp(_) | SegmentKind::App1XmpExtension(_) => {
+            0xE1
+        }
+        SegmentKind::App2Icc(_) | SegmentKind::App2Mpf(_) => 0xE2,
+        SegmentKind::App13Photoshop(_) => 0xED,
+        SegmentKind::App14Adobe(_) => 0xEE,
+        SegmentKind::Dqt(_) => 0xDB,
+        SegmentKind::Dht(_) => 0xC4,
+        SegmentKind::Dac(_) => 0xCC,
+        SegmentKind::Frame(frame) => frame.sof,
+        SegmentKind::Scan(_) => 0xDA,
+        SegmentKind::Dri(_) => 0xDD,
+        SegmentKind::Rst(rst) => 0xD0 + rst.nr,
+        SegmentKind::Comment(_) => 0xFE,
+        SegmentKind::Unknown { marker, .. } => *marker,
+    }
+}
+
+/// Identify the likely encoder of the primary image
+pub fn identify_encoder<R: Read>(input: R) -> Result<Vec<EncoderMatch>, JfifError> {
+    let options = ReaderOptions {
+        retain_scan_data: false,
+    };
+
+    let mut reader = Reader::with_options(input, options)?;
+    let mut features = EncoderFeatures::new();
+
+    loop {
+        let segment = match reader.next_segment() {
+            Ok(segment) => segment,
+            // Truncated files are identified by the segments read so far
+            Err(JfifError::IoError(err)) if err.kind() == ErrorKind::UnexpectedEof => break,
+            Err(err) => return Err(err),
+        };
+
+        features.process(&segment.kind);
+
+        if let SegmentKind::Eoi = segment.kind {
+            break;
+        }
+    }
+
+    Ok(features.identify())
+}
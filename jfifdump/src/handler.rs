@@ -1,10 +1,46 @@
-use crate::{App0Jfif, Dac, Dht, Dqt, Frame, Rst, Scan};
+use crate::{
+    App0Jfif, App0Jfxx, App13Photoshop, App14Adobe, App1Exif, App1Xmp, App1XmpExtension, App2Icc,
+    App2Mpf, Dac, Dht, Dqt, EncoderMatch, ExtendedXmp, Frame, IccProfile, MpEntry, Rst, Scan,
+    Warning,
+};
 
 pub trait Handler {
     fn handle_app(&mut self, position: usize, length: usize, nr: u8, data: &[u8]);
 
     fn handle_app0_jfif(&mut self, position: usize, length: usize, jfif: &App0Jfif);
 
+    fn handle_app0_jfxx(&mut self, position: usize, length: usize, jfxx: &App0Jfxx);
+
+    fn handle_app1_exif(&mut self, position: usize, length: usize, exif: &App1Exif);
+
+    fn handle_app1_xmp(&mut self, position: usize, length: usize, xmp: &App1Xmp);
+
+    fn handle_app1_xmp_extension(
+        &mut self,
+        position: usize,
+        length: usize,
+        extension: &App1XmpExtension,
+    );
+
+    /// Called after all segments were read for every extended XMP document
+    fn handle_extended_xmp(&mut self, xmp: &ExtendedXmp);
+
+    fn handle_app2_icc(&mut self, position: usize, length: usize, chunk: &App2Icc);
+
+    /// Called after all segments were read if the file contains an ICC profile
+    fn handle_icc_profile(&mut self, profile: &IccProfile);
+
+    fn handle_app2_mpf(&mut self, position: usize, length: usize, mpf: &App2Mpf);
+
+    fn handle_app13_photoshop(
+        &mut self,
+        position: usize,
+        length: usize,
+        photoshop: &App13Photoshop,
+    );
+
+    fn handle_app14_adobe(&mut self, position: usize, length: usize, adobe: &App14Adobe);
+
     fn handle_dqt(&mut self, position: usize, length: usize, tables: &[Dqt]);
 
     fn handle_dht(&mut self, position: usize, length: usize, tables: &[Dht]);
@@ -26,4 +62,13 @@ pub trait Handler {
     fn handle_eoi(&mut self, position: usize, length: usize);
 
     fn handle_soi(&mut self, position: usize, length: usize);
+
+    /// Called before the SOI of every image embedded after the EOI of the primary image
+    fn handle_embedded_image(&mut self, index: usize, position: usize, entry: Option<&MpEntry>);
+
+    /// Called after a segment for every problem the reader recovered from while reading it
+    fn handle_warning(&mut self, warning: &Warning);
+
+    /// Called after all segments were read with the likely encoders of the primary image
+    fn handle_encoder(&mut self, matches: &[EncoderMatch]);
 }
@@ -0,0 +1,346 @@
+use std::fmt::Display;
+
+use crate::{Dht, Frame, JfifError};
+
+/// Number of bits resolved with a single table lookup
+const LOOKUP_BITS: u32 = 9;
+
+/// Reads bits from entropy-coded data removing stuffed zero bytes
+///
+/// Missing bits at the end of the data are read as zeros.
+pub(crate) struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    /// Buffered bits aligned to the most significant bit
+    bits: u64,
+    count: u32,
+}
+
+impl<'a> BitReader<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            position: 0,
+            bits: 0,
+            count: 0,
+        }
+    }
+
+    fn next_byte(&mut self) -> u8 {
+        let byte = match self.data.get(self.position) {
+            Some(&byte) => byte,
+            None => return 0,
+        };
+
+        self.position += 1;
+
+        if byte == 0xFF {
+            // Fill bytes followed by the stuffed zero byte
+            while self.data.get(self.position) == Some(&0xFF) {
+                self.position += 1;
+            }
+            if self.data.get(self.position) == Some(&0x00) {
+                self.position += 1;
+            }
+        }
+
+        byte
+    }
+
+    fn fill(&mut self) {
+        while self.count <= 56 {
+            self.bits |= (self.next_byte() as u64) << (56 - self.count);
+            self.count += 8;
+        }
+    }
+
+    /// Return the next `length` bits without consuming them
+    pub(crate) fn peek(&mut self, length: u32) -> u32 {
+        if self.count < length {
+            self.fill();
+        }
+        (self.bits >> (64 - length)) as u32
+    }
+
+    pub(crate) fn consume(&mut self, length: u32) {
+        self.bits <<= length;
+        self.count -= length;
+    }
+
+    pub(crate) fn read_bits(&mut self, length: u32) -> u32 {
+        if length == 0 {
+            return 0;
+        }
+        let value = self.peek(length);
+        self.consume(length);
+        value
+    }
+
+    pub(crate) fn read_bit(&mut self) -> u32 {
+        self.read_bits(1)
+    }
+
+    /// Read `length` bits and convert them into a signed value (F.2.2.1 of ITU T.81)
+    pub(crate) fn receive_extend(&mut self, length: u32) -> i32 {
+        if length == 0 {
+            return 0;
+        }
+
+        let value = self.read_bits(length) as i32;
+
+        if value < 1 << (length - 1) {
+            value - (1 << length) + 1
+        } else {
+            value
+        }
+    }
+}
+
+/// Canonical Huffman decoder built from a DHT table
+pub(crate) struct HuffmanTable {
+    /// Code length and value for every code with up to `LOOKUP_BITS` bits
+    lookup: Vec<(u8, u8)>,
+    /// Largest code of every length or -1 if there is none
+    max_code: [i32; 17],
+    min_code: [i32; 17],
+    /// Index of the first value of every length
+    value_offset: [i32; 17],
+    values: Vec<u8>,
+}
+
+impl HuffmanTable {
+    pub(crate) fn new(dht: &Dht) -> Result<Self, JfifError> {
+        let count: usize = dht.code_lengths.iter().map(|&v| v as usize).sum();
+
+        if dht.values.len() < count {
+            return Err(JfifError::InvalidHuffmanTable(dht.class, dht.dest));
+        }
+
+        let mut lookup = vec![(0, 0); 1 << LOOKUP_BITS];
+        let mut max_code = [-1; 17];
+        let mut min_code = [0; 17];
+        let mut value_offset = [0; 17];
+
+        let mut code = 0i32;
+        let mut index = 0i32;
+
+        for length in 1..=16 {
+            let codes = dht.code_lengths[length - 1] as i32;
+
+            value_offset[length] = index;
+            min_code[length] = code;
+
+            if codes > 0 {
+                max_code[length] = code + codes - 1;
+            }
+
+            if code + codes > 1 << length {
+                return Err(JfifError::InvalidHuffmanTable(dht.class, dht.dest));
+            }
+
+            if length as u32 <= LOOKUP_BITS {
+                let shift = LOOKUP_BITS - length as u32;
+
+                for i in 0..codes {
+                    let first = ((code + i) << shift) as usize;
+                    let value = dht.values[(index + i) as usize];
+
+                    for entry in &mut lookup[first..first + (1 << shift)] {
+                        *entry = (length as u8, value);
+                    }
+                }
+            }
+
+            code = (code + codes) << 1;
+            index += codes;
+        }
+
+        Ok(Self {
+            lookup,
+            max_code,
+            min_code,
+            value_offset,
+            values: dht.values[..count].to_vec(),
+        })
+    }
+
+    pub(crate) fn decode(&self, reader: &mut BitReader) -> Result<u8, JfifError> {
+        let (length, value) = self.lookup[reader.peek(LOOKUP_BITS) as usize];
+
+        if length > 0 {
+            reader.consume(length as u32);
+            return Ok(value);
+        }
+
+        let mut code = 0i32;
+
+        for length in 1..=16 {
+            code = (code << 1) | reader.read_bit() as i32;
+
+            if code <= self.max_code[length] {
+                let index = self.value_offset[length] + code - self.min_code[length];
+                return Ok(self.values[index as usize]);
+            }
+        }
+
+        Err(JfifError::InvalidHuffmanCode)
+    }
+}
+
+/// Problems of a DHT table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HuffmanTableIssue {
+    /// Class other than 0 for DC or 1 for AC tables
+    InvalidClass(u8),
+    /// Destination greater than 3
+    InvalidDest(u8),
+    /// More codes of this length than the remaining code space allows
+    OverSubscribed(u8),
+    /// The last code consists only of 1 bits which is reserved (C.2 of ITU T.81)
+    AllOnesCode,
+    /// DC difference category not possible with the precision of the frame
+    InvalidDcValue(u8),
+    /// AC run length and size combination not possible with the frame
+    InvalidAcValue(u8),
+    /// Value assigned to more than one code
+    DuplicateValue(u8),
+}
+
+impl Display for HuffmanTableIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use HuffmanTableIssue::*;
+        match self {
+            InvalidClass(class) => write!(f, "Invalid class {}", class),
+            InvalidDest(dest) => write!(f, "Invalid destination {}", dest),
+            OverSubscribed(length) => write!(f, "Too many codes of length {}", length),
+            AllOnesCode => write!(f, "Code with all bits set"),
+            InvalidDcValue(value) => write!(f, "Invalid DC value 0x{:02X}", value),
+            InvalidAcValue(value) => write!(f, "Invalid AC value 0x{:02X}", value),
+            DuplicateValue(value) => write!(f, "Duplicate value 0x{:02X}", value),
+        }
+    }
+}
+
+/// Code assigned to a value of a DHT table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HuffmanCode {
+    pub value: u8,
+    pub code: u16,
+    pub length: u8,
+}
+
+impl HuffmanCode {
+    /// Bits of the code starting with the most significant one
+    pub fn bits(&self) -> String {
+        format!("{:0width$b}", self.code, width = self.length as usize)
+    }
+}
+
+impl Dht {
+    /// Canonical codes of all values (C.2 of ITU T.81)
+    ///
+    /// Values without room in the code space of an over-subscribed table are omitted.
+    pub fn codes(&self) -> Vec<HuffmanCode> {
+        let mut codes = vec![];
+        let mut values = self.values.iter();
+        let mut code = 0u32;
+
+        for length in 1..=16 {
+            for _ in 0..self.code_lengths[length - 1] {
+                let value = match values.next() {
+                    Some(&value) => value,
+                    None => return codes,
+                };
+
+                if code < 1 << length {
+                    codes.push(HuffmanCode {
+                        value,
+                        code: code as u16,
+                        length: length as u8,
+                    });
+                }
+
+                code += 1;
+            }
+
+            code <<= 1;
+        }
+
+        codes
+    }
+
+    /// Check the code lengths and values of the table
+    ///
+    /// The allowed values depend on the frame. Without a frame only values that are
+    /// invalid for all frame types are reported.
+    pub fn issues(&self, frame: Option<&Frame>) -> Vec<HuffmanTableIssue> {
+        let mut issues = vec![];
+
+        if self.class > 1 {
+            issues.push(HuffmanTableIssue::InvalidClass(self.class));
+        }
+
+        if self.dest > 3 {
+            issues.push(HuffmanTableIssue::InvalidDest(self.dest));
+        }
+
+        // Sum of all codes weighted by the part of the code space they use
+        let mut used = 0u32;
+
+        for length in 1..=16 {
+            used += (self.code_lengths[length - 1] as u32) << (16 - length);
+
+            if used > 1 << 16 {
+                issues.push(HuffmanTableIssue::OverSubscribed(length as u8));
+                break;
+            }
+        }
+
+        if used == 1 << 16 {
+            issues.push(HuffmanTableIssue::AllOnesCode);
+        }
+
+        let lossless = frame.is_some_and(|f| matches!(f.sof, 0xC3 | 0xC7 | 0xCB | 0xCF));
+        let progressive = frame.map(|f| matches!(f.sof, 0xC2 | 0xC6 | 0xCA | 0xCE));
+        let precision = frame.map(|f| f.precision);
+
+        let max_dc = match precision {
+            _ if lossless => 16,
+            Some(8) => 11,
+            Some(_) => 15,
+            None => 16,
+        };
+
+        let max_ac_size = if precision == Some(8) { 10 } else { 14 };
+
+        let mut seen = [false; 256];
+
+        for &value in &self.values {
+            if seen[value as usize] {
+                issues.push(HuffmanTableIssue::DuplicateValue(value));
+            }
+            seen[value as usize] = true;
+
+            match self.class {
+                0 if value > max_dc => issues.push(HuffmanTableIssue::InvalidDcValue(value)),
+                1 if !lossless => {
+                    let run = value >> 4;
+                    let size = value & 0x0F;
+
+                    // Runs of end of band without size are only used by progressive frames
+                    let valid = match size {
+                        0 => run == 0 || run == 15 || progressive != Some(false),
+                        _ => size <= max_ac_size,
+                    };
+
+                    if !valid {
+                        issues.push(HuffmanTableIssue::InvalidAcValue(value));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        issues
+    }
+}
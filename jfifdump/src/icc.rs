@@ -0,0 +1,277 @@
+use std::fmt::Display;
+
+pub(crate) const ICC_SIGNATURE: &[u8] = b"ICC_PROFILE\0";
+
+/// A chunk of an ICC profile stored in an APP2 segment
+///
+/// The chunk data is borrowed from the input if read by [SliceReader](crate::SliceReader).
+#[derive(Debug)]
+pub struct App2Icc<D = Vec<u8>> {
+    /// Sequence number of the chunk starting with 1
+    pub sequence: u8,
+    /// Total number of chunks
+    pub count: u8,
+    pub data: D,
+}
+
+impl App2Icc<&[u8]> {
+    /// Copy the borrowed chunk data
+    pub fn into_owned(self) -> App2Icc {
+        App2Icc {
+            sequence: self.sequence,
+            count: self.count,
+            data: self.data.to_vec(),
+        }
+    }
+}
+
+pub(crate) fn parse_icc_chunk(data: &[u8]) -> Option<App2Icc<&[u8]>> {
+    let data = data.strip_prefix(ICC_SIGNATURE)?;
+
+    if data.len() < 2 {
+        return None;
+    }
+
+    Some(App2Icc {
+        sequence: data[0],
+        count: data[1],
+        data: &data[2..],
+    })
+}
+
+/// Problems found while reassembling an ICC profile from its chunks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IccChunkIssue {
+    /// Chunk with this sequence number was not found
+    Missing(u8),
+    /// Chunk with this sequence number was found more than once. Only the first one is used.
+    Duplicate(u8),
+    /// Chunk with this sequence number appeared after a chunk with a higher number
+    OutOfOrder(u8),
+    /// Sequence number is 0 or greater than the chunk count
+    InvalidSequence(u8),
+    /// Chunk announced a different chunk count than the first chunk
+    InconsistentCount(u8),
+}
+
+impl Display for IccChunkIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use IccChunkIssue::*;
+        match self {
+            Missing(nr) => write!(f, "Missing chunk {}", nr),
+            Duplicate(nr) => write!(f, "Duplicate chunk {}", nr),
+            OutOfOrder(nr) => write!(f, "Chunk {} out of order", nr),
+            InvalidSequence(nr) => write!(f, "Invalid chunk sequence number {}", nr),
+            InconsistentCount(count) => write!(f, "Inconsistent chunk count {}", count),
+        }
+    }
+}
+
+/// ICC profile reassembled from all APP2 chunks of a file
+#[derive(Debug)]
+pub struct IccProfile {
+    /// Raw profile data
+    pub data: Vec<u8>,
+    /// Parsed profile header if the data is long enough
+    pub header: Option<IccHeader>,
+    pub issues: Vec<IccChunkIssue>,
+}
+
+#[derive(Debug)]
+pub struct IccHeader {
+    /// Profile size as stored in the header
+    pub size: u32,
+    pub cmm: [u8; 4],
+    pub version_major: u8,
+    pub version_minor: u8,
+    pub version_bugfix: u8,
+    pub class: [u8; 4],
+    pub color_space: [u8; 4],
+    pub pcs: [u8; 4],
+    pub rendering_intent: u32,
+    /// Content of the `desc` tag
+    pub description: Option<String>,
+}
+
+impl IccHeader {
+    pub fn class_name(&self) -> &'static str {
+        match &self.class {
+            b"scnr" => "Input device",
+            b"mntr" => "Display device",
+            b"prtr" => "Output device",
+            b"link" => "Device link",
+            b"spac" => "Color space",
+            b"abst" => "Abstract",
+            b"nmcl" => "Named color",
+            _ => "Unknown",
+        }
+    }
+
+    pub fn rendering_intent_name(&self) -> &'static str {
+        match self.rendering_intent {
+            0 => "Perceptual",
+            1 => "Media-relative colorimetric",
+            2 => "Saturation",
+            3 => "ICC-absolute colorimetric",
+            _ => "Unknown",
+        }
+    }
+}
+
+/// Format a four character signature like `RGB ` or `mntr`
+pub fn signature_string(signature: &[u8; 4]) -> String {
+    signature
+        .iter()
+        .map(|&v| {
+            if v.is_ascii_graphic() || v == b' ' {
+                v as char
+            } else {
+                '?'
+            }
+        })
+        .collect::<String>()
+        .trim_end()
+        .to_owned()
+}
+
+/// Collects ICC profile chunks across segments
+#[derive(Default)]
+pub(crate) struct IccAssembler {
+    chunks: Vec<(u8, u8, Vec<u8>)>,
+}
+
+impl IccAssembler {
+    pub(crate) fn add(&mut self, chunk: &App2Icc<&[u8]>) {
+        self.chunks
+            .push((chunk.sequence, chunk.count, chunk.data.to_vec()));
+    }
+
+    pub(crate) fn finish(&self) -> Option<IccProfile> {
+        let count = self.chunks.first()?.1;
+
+        let mut issues = vec![];
+        let mut parts: Vec<Option<&[u8]>> = vec![None; count as usize];
+        let mut last_sequence = 0;
+
+        for (sequence, chunk_count, data) in &self.chunks {
+            let sequence = *sequence;
+
+            if *chunk_count != count {
+                issues.push(IccChunkIssue::InconsistentCount(*chunk_count));
+            }
+
+            if sequence == 0 || sequence > count {
+                issues.push(IccChunkIssue::InvalidSequence(sequence));
+                continue;
+            }
+
+            let part = &mut parts[sequence as usize - 1];
+
+            if part.is_some() {
+                issues.push(IccChunkIssue::Duplicate(sequence));
+                continue;
+            }
+
+            if sequence < last_sequence {
+                issues.push(IccChunkIssue::OutOfOrder(sequence));
+            }
+
+            last_sequence = sequence;
+            *part = Some(data.as_slice());
+        }
+
+        let mut data = vec![];
+
+        for (i, part) in parts.iter().enumerate() {
+            match part {
+                Some(part) => data.extend_from_slice(part),
+                None => issues.push(IccChunkIssue::Missing(i as u8 + 1)),
+            }
+        }
+
+        let header = parse_icc_header(&data);
+
+        Some(IccProfile {
+            data,
+            header,
+            issues,
+        })
+    }
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset.checked_add(4)?)?;
+    Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn read_signature(data: &[u8], offset: usize) -> [u8; 4] {
+    let mut signature = [0u8; 4];
+    signature.copy_from_slice(&data[offset..offset + 4]);
+    signature
+}
+
+fn parse_icc_header(data: &[u8]) -> Option<IccHeader> {
+    if data.len() < 128 {
+        return None;
+    }
+
+    Some(IccHeader {
+        size: read_u32(data, 0)?,
+        cmm: read_signature(data, 4),
+        version_major: data[8],
+        version_minor: data[9] >> 4,
+        version_bugfix: data[9] & 0x0F,
+        class: read_signature(data, 12),
+        color_space: read_signature(data, 16),
+        pcs: read_signature(data, 20),
+        rendering_intent: read_u32(data, 64)?,
+        description: parse_description(data),
+    })
+}
+
+fn parse_description(data: &[u8]) -> Option<String> {
+    let tag_count = read_u32(data, 128)? as usize;
+
+    for i in 0..tag_count.min(1024) {
+        let entry = 132 + i * 12;
+
+        if data.get(entry..entry + 4)? != b"desc" {
+            continue;
+        }
+
+        let offset = read_u32(data, entry + 4)? as usize;
+        let size = read_u32(data, entry + 8)? as usize;
+        let tag = data.get(offset..offset.checked_add(size)?)?;
+
+        return match tag.get(0..4)? {
+            // textDescriptionType of ICC v2
+            b"desc" => {
+                let length = read_u32(tag, 8)? as usize;
+                let text = tag.get(12..12usize.checked_add(length)?)?;
+                let end = text.iter().position(|&v| v == 0).unwrap_or(text.len());
+                Some(String::from_utf8_lossy(&text[..end]).into_owned())
+            }
+            // multiLocalizedUnicodeType of ICC v4. Only the first record is used.
+            b"mluc" => {
+                if read_u32(tag, 8)? == 0 {
+                    return None;
+                }
+                let length = read_u32(tag, 20)? as usize;
+                let offset = read_u32(tag, 24)? as usize;
+                let text = tag.get(offset..offset.checked_add(length)?)?;
+                let text: Vec<u16> = text
+                    .chunks_exact(2)
+                    .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                    .collect();
+                Some(
+                    String::from_utf16_lossy(&text)
+                        .trim_end_matches('\0')
+                        .to_owned(),
+                )
+            }
+            _ => None,
+        };
+    }
+
+    None
+}
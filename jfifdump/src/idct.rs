@@ -0,0 +1,137 @@
+use crate::decoder::ZIGZAG;
+
+const CONST_BITS: u32 = 13;
+const PASS1_BITS: u32 = 2;
+
+const FIX_0_298631336: i64 = 2446;
+const FIX_0_390180644: i64 = 3196;
+const FIX_0_541196100: i64 = 4433;
+const FIX_0_765366865: i64 = 6270;
+const FIX_0_899976223: i64 = 7373;
+const FIX_1_175875602: i64 = 9633;
+const FIX_1_501321110: i64 = 12299;
+const FIX_1_847759065: i64 = 15137;
+const FIX_1_961570560: i64 = 16069;
+const FIX_2_053119869: i64 = 16819;
+const FIX_2_562915447: i64 = 20995;
+const FIX_3_072711026: i64 = 25172;
+
+/// Dequantize a block and apply the inverse DCT
+///
+/// `quantization` is in zigzag order as stored in [Dqt](crate::Dqt) segments.
+/// The samples are written to `output` with `stride` bytes per line.
+///
+/// This is the accurate integer IDCT of libjpeg (`jidctint.c`) and produces the same results,
+/// including the wrap-around of its range limit table for out-of-range values.
+pub(crate) fn idct(
+    coefficients: &[i16; 64],
+    quantization: &[u16; 64],
+    output: &mut [u8],
+    stride: usize,
+) {
+    let mut input = [0i64; 64];
+
+    for (&index, &q) in ZIGZAG.iter().zip(quantization.iter()) {
+        input[index] = coefficients[index] as i64 * q as i64;
+    }
+
+    let mut workspace = [0i64; 64];
+
+    // Columns of the input into the workspace
+    for column in 0..8 {
+        let x = |row: usize| input[row * 8 + column];
+
+        if (1..8).all(|row| x(row) == 0) {
+            let dc = x(0) << PASS1_BITS;
+            for row in 0..8 {
+                workspace[row * 8 + column] = dc;
+            }
+            continue;
+        }
+
+        let values = butterfly([x(0), x(1), x(2), x(3), x(4), x(5), x(6), x(7)]);
+
+        for (row, value) in values.iter().enumerate() {
+            workspace[row * 8 + column] = descale(*value, CONST_BITS - PASS1_BITS);
+        }
+    }
+
+    // Rows of the workspace into the output
+    for row in 0..8 {
+        let w = &workspace[row * 8..row * 8 + 8];
+        let values = butterfly([w[0], w[1], w[2], w[3], w[4], w[5], w[6], w[7]]);
+
+        for (column, value) in values.iter().enumerate() {
+            let sample = descale(*value, CONST_BITS + PASS1_BITS + 3);
+            output[row * stride + column] = range_limit(sample);
+        }
+    }
+}
+
+/// Level shift an IDCT output like the range limit table of libjpeg
+///
+/// Only the lowest 10 bits are used, so values more than 384 outside of
+/// the sample range wrap around instead of being clamped.
+fn range_limit(value: i64) -> u8 {
+    match value & 0x3FF {
+        v @ 0..=127 => (v + 128) as u8,
+        128..=511 => 255,
+        512..=895 => 0,
+        v => (v - 896) as u8,
+    }
+}
+
+fn descale(value: i64, bits: u32) -> i64 {
+    (value + (1 << (bits - 1))) >> bits
+}
+
+/// One dimensional IDCT of 8 values scaled by `1 << CONST_BITS`
+fn butterfly(x: [i64; 8]) -> [i64; 8] {
+    // Even part
+    let z1 = (x[2] + x[6]) * FIX_0_541196100;
+    let tmp2 = z1 - x[6] * FIX_1_847759065;
+    let tmp3 = z1 + x[2] * FIX_0_765366865;
+
+    let tmp0 = (x[0] + x[4]) << CONST_BITS;
+    let tmp1 = (x[0] - x[4]) << CONST_BITS;
+
+    let tmp10 = tmp0 + tmp3;
+    let tmp13 = tmp0 - tmp3;
+    let tmp11 = tmp1 + tmp2;
+    let tmp12 = tmp1 - tmp2;
+
+    // Odd part
+    let (tmp0, tmp1, tmp2, tmp3) = (x[7], x[5], x[3], x[1]);
+
+    let z1 = tmp0 + tmp3;
+    let z2 = tmp1 + tmp2;
+    let z3 = tmp0 + tmp2;
+    let z4 = tmp1 + tmp3;
+    let z5 = (z3 + z4) * FIX_1_175875602;
+
+    let tmp0 = tmp0 * FIX_0_298631336;
+    let tmp1 = tmp1 * FIX_2_053119869;
+    let tmp2 = tmp2 * FIX_3_072711026;
+    let tmp3 = tmp3 * FIX_1_501321110;
+
+    let z1 = -z1 * FIX_0_899976223;
+    let z2 = -z2 * FIX_2_562915447;
+    let z3 = -z3 * FIX_1_961570560 + z5;
+    let z4 = -z4 * FIX_0_390180644 + z5;
+
+    let tmp0 = tmp0 + z1 + z3;
+    let tmp1 = tmp1 + z2 + z4;
+    let tmp2 = tmp2 + z2 + z3;
+    let tmp3 = tmp3 + z1 + z4;
+
+    [
+        tmp10 + tmp3,
+        tmp11 + tmp2,
+        tmp12 + tmp1,
+        tmp13 + tmp0,
+        tmp13 - tmp0,
+        tmp12 - tmp1,
+        tmp11 - tmp2,
+        tmp10 - tmp3,
+    ]
+}
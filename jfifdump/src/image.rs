@@ -0,0 +1,328 @@
+use std::fmt::Write;
+use std::io::Read;
+
+use crate::decoder::{CoefficientDecoder, DecoderOptions};
+use crate::idct::idct;
+use crate::{App14Adobe, ColorSpace, JfifError, Reader, SegmentKind};
+
+/// Channels of decoded pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelFormat {
+    Grayscale,
+    Rgb,
+    /// CMYK with 0 for no ink, even if the file stores inverted values like Adobe does
+    Cmyk,
+}
+
+impl PixelFormat {
+    pub fn channels(&self) -> usize {
+        match self {
+            PixelFormat::Grayscale => 1,
+            PixelFormat::Rgb => 3,
+            PixelFormat::Cmyk => 4,
+        }
+    }
+}
+
+/// Decoded pixels of an image
+#[derive(Debug)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub format: PixelFormat,
+    /// Samples line by line with interleaved channels
+    pub data: Vec<u8>,
+}
+
+impl Image {
+    /// Convert CMYK images to RGB and return all other images unchanged
+    pub fn into_rgb(self) -> Image {
+        if self.format != PixelFormat::Cmyk {
+            return self;
+        }
+
+        let data = self
+            .data
+            .chunks_exact(4)
+            .flat_map(|cmyk| {
+                let k = 255 - cmyk[3] as u32;
+                let channel = |value: u8| ((255 - value as u32) * k / 255) as u8;
+                [channel(cmyk[0]), channel(cmyk[1]), channel(cmyk[2])]
+            })
+            .collect();
+
+        Image {
+            format: PixelFormat::Rgb,
+            data,
+            ..self
+        }
+    }
+
+    /// Content of a binary PGM file for grayscale, PPM for RGB or PAM for CMYK images
+    pub fn to_pnm(&self) -> Vec<u8> {
+        let mut header = String::new();
+
+        match self.format {
+            PixelFormat::Grayscale => write!(header, "P5\n{} {}\n255\n", self.width, self.height),
+            PixelFormat::Rgb => write!(header, "P6\n{} {}\n255\n", self.width, self.height),
+            PixelFormat::Cmyk => write!(
+                header,
+                "P7\nWIDTH {}\nHEIGHT {}\nDEPTH 4\nMAXVAL 255\nTUPLTYPE CMYK\nENDHDR\n",
+                self.width, self.height
+            ),
+        }
+        .unwrap();
+
+        let mut file = header.into_bytes();
+        file.extend_from_slice(&self.data);
+        file
+    }
+}
+
+/// Decodes the pixels of an image segment by segment
+///
+/// Coefficients are dequantized with the DQT tables in effect when the first scan of
+/// a component starts. Subsampled components are upsampled by replication.
+#[derive(Default)]
+pub struct ImageDecoder {
+    coefficients: CoefficientDecoder,
+    quantization_tables: [Option<[u16; 64]>; 4],
+    /// Quantization table of every frame component
+    component_tables: Vec<Option<[u16; 64]>>,
+    jfif: bool,
+    adobe: Option<App14Adobe>,
+}
+
+impl ImageDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new decoder with options
+    pub fn with_options(options: DecoderOptions) -> Self {
+        Self {
+            coefficients: CoefficientDecoder::with_options(options),
+            ..Self::default()
+        }
+    }
+
+    /// Decoder of the DCT coefficients
+    pub fn coefficients(&self) -> &CoefficientDecoder {
+        &self.coefficients
+    }
+
+    /// Update the decoder state with the next segment of the image
+    pub fn process(&mut self, kind: &SegmentKind) -> Result<(), JfifError> {
+        match kind {
+            SegmentKind::App0Jfif(_) => self.jfif = true,
+            SegmentKind::App14Adobe(adobe) => self.adobe = Some(adobe.clone()),
+            SegmentKind::Dqt(tables) => {
+                for table in tables {
+                    if let Some(slot) = self.quantization_tables.get_mut(table.dest as usize) {
+                        *slot = Some(*table.values);
+                    }
+                }
+            }
+            SegmentKind::Frame(frame) => {
+                // Reject unsupported frame types before their precision
+                self.coefficients.process(kind)?;
+
+                if frame.precision != 8 {
+                    return Err(JfifError::UnsupportedPrecision(frame.precision));
+                }
+                self.component_tables = vec![None; frame.components.len()];
+                return Ok(());
+            }
+            SegmentKind::Scan(scan) => {
+                if let Some(frame) = self.coefficients.frame() {
+                    for sc in &scan.components {
+                        let index = frame.components.iter().position(|c| c.id == sc.id);
+
+                        if let Some(index) = index {
+                            if self.component_tables[index].is_none() {
+                                let dest = frame.components[index].quantization_table;
+
+                                let table = self
+                                    .quantization_tables
+                                    .get(dest as usize)
+                                    .copied()
+                                    .flatten()
+                                    .ok_or(JfifError::MissingQuantizationTable(dest))?;
+
+                                self.component_tables[index] = Some(table);
+                            }
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        self.coefficients.process(kind)
+    }
+
+    /// Color space of the encoded image
+    pub fn color_space(&self) -> Option<ColorSpace> {
+        let frame = self.coefficients.frame()?;
+        Some(frame.color_space(self.jfif, self.adobe.as_ref()))
+    }
+
+    /// Decode the pixels from the coefficients of all scans processed so far
+    ///
+    /// Components without any scan are decoded as zero coefficients.
+    pub fn image(&self) -> Result<Image, JfifError> {
+        let frame = self.coefficients.frame().ok_or(JfifError::MissingFrame)?;
+        let color_space = frame.color_space(self.jfif, self.adobe.as_ref());
+
+        let width = frame.dimension_x as usize;
+        let height = frame.dimension_y as usize;
+
+        let max_h = frame
+            .components
+            .iter()
+            .map(|c| c.horizontal_sampling_factor.max(1) as usize)
+            .max()
+            .unwrap_or(1);
+        let max_v = frame
+            .components
+            .iter()
+            .map(|c| c.vertical_sampling_factor.max(1) as usize)
+            .max()
+            .unwrap_or(1);
+
+        // Samples of all blocks, the upsampled planes and the converted pixels
+        let memory = self
+            .coefficients
+            .components()
+            .iter()
+            .map(|c| {
+                (c.blocks.len() * 64).saturating_add(width.saturating_mul(height).saturating_mul(2))
+            })
+            .fold(0, usize::saturating_add);
+
+        self.coefficients.options.check_memory(memory)?;
+
+        // Samples of every component upsampled to the image size
+        let planes: Vec<Vec<u8>> = self
+            .coefficients
+            .components()
+            .iter()
+            .zip(&self.component_tables)
+            .map(|(component, table)| {
+                let stride = component.blocks_per_line * 8;
+                let mut samples = vec![0; stride * component.blocks_per_column * 8];
+                let table = table.unwrap_or([1; 64]);
+
+                for y in 0..component.blocks_per_column {
+                    for x in 0..component.blocks_per_line {
+                        let offset = y * 8 * stride + x * 8;
+                        idct(
+                            component.block(x, y),
+                            &table,
+                            &mut samples[offset..],
+                            stride,
+                        );
+                    }
+                }
+
+                let h = component.horizontal_sampling_factor as usize;
+                let v = component.vertical_sampling_factor as usize;
+
+                if h == max_h && v == max_v {
+                    return samples
+                        .chunks_exact(stride)
+                        .take(height)
+                        .flat_map(|line| &line[..width])
+                        .copied()
+                        .collect();
+                }
+
+                let mut plane = Vec::with_capacity(width * height);
+
+                for y in 0..height {
+                    let line = &samples[y * v / max_v * stride..];
+                    plane.extend((0..width).map(|x| line[x * h / max_h]));
+                }
+
+                plane
+            })
+            .collect();
+
+        let (format, data) = match (color_space, planes.as_slice()) {
+            (ColorSpace::Grayscale, [y]) => (PixelFormat::Grayscale, y.clone()),
+            (ColorSpace::YCbCr, [y, cb, cr]) => {
+                let mut data = Vec::with_capacity(width * height * 3);
+                for i in 0..width * height {
+                    data.extend_from_slice(&ycc_to_rgb(y[i], cb[i], cr[i]));
+                }
+                (PixelFormat::Rgb, data)
+            }
+            (ColorSpace::Rgb, [r, g, b]) => {
+                let mut data = Vec::with_capacity(width * height * 3);
+                for i in 0..width * height {
+                    data.extend_from_slice(&[r[i], g[i], b[i]]);
+                }
+                (PixelFormat::Rgb, data)
+            }
+            (ColorSpace::Cmyk, [c, m, y, k]) => {
+                // Adobe applications write inverted CMYK values
+                let invert = if self.adobe.is_some() { 255 } else { 0 };
+
+                let mut data = Vec::with_capacity(width * height * 4);
+                for i in 0..width * height {
+                    data.extend_from_slice(&[c[i], m[i], y[i], k[i]].map(|v| v ^ invert));
+                }
+                (PixelFormat::Cmyk, data)
+            }
+            (ColorSpace::Ycck, [y, cb, cr, k]) => {
+                let mut data = Vec::with_capacity(width * height * 4);
+                for i in 0..width * height {
+                    let [r, g, b] = ycc_to_rgb(y[i], cb[i], cr[i]);
+                    data.extend_from_slice(&[r, g, b, 255 - k[i]]);
+                }
+                (PixelFormat::Cmyk, data)
+            }
+            _ => return Err(JfifError::UnsupportedColorSpace(color_space.name())),
+        };
+
+        Ok(Image {
+            width,
+            height,
+            format,
+            data,
+        })
+    }
+}
+
+/// Convert YCbCr to RGB with the fixed point arithmetic of libjpeg (`jdcolor.c`)
+fn ycc_to_rgb(y: u8, cb: u8, cr: u8) -> [u8; 3] {
+    const ONE_HALF: i32 = 1 << 15;
+
+    let y = y as i32;
+    let cb = cb as i32 - 128;
+    let cr = cr as i32 - 128;
+
+    let r = y + ((91881 * cr + ONE_HALF) >> 16);
+    let g = y + ((-22554 * cb + ONE_HALF - 46802 * cr) >> 16);
+    let b = y + ((116130 * cb + ONE_HALF) >> 16);
+
+    [r, g, b].map(|v| v.clamp(0, 255) as u8)
+}
+
+/// Decode the pixels of the primary image
+pub fn decode_image<R: Read>(input: R) -> Result<Image, JfifError> {
+    let mut reader = Reader::new(input)?;
+    let mut decoder = ImageDecoder::new();
+
+    loop {
+        let segment = reader.next_segment()?;
+
+        if let SegmentKind::Eoi = segment.kind {
+            break;
+        }
+
+        decoder.process(&segment.kind)?;
+    }
+
+    decoder.image()
+}
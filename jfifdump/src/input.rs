@@ -0,0 +1,152 @@
+use std::io::{BufRead, Error as IoError, ErrorKind, Read, Seek, SeekFrom};
+
+/// Size of the blocks read ahead from seekable input
+const BLOCK_SIZE: usize = 8192;
+
+/// How the input is read
+enum Mode<R> {
+    /// Read one byte at a time while scanning, so nothing after the last segment is consumed
+    Direct,
+    /// Scan the buffer of a [BufRead]
+    Buffered {
+        fill_buf: for<'a> fn(&'a mut R) -> Result<&'a [u8], IoError>,
+        consume: fn(&mut R, usize),
+    },
+    /// Read ahead in blocks, skip by seeking and seek back over unused bytes when dropped
+    Seekable {
+        seek: fn(&mut R, SeekFrom) -> Result<u64, IoError>,
+    },
+}
+
+/// Input of a [Reader](crate::Reader)
+///
+/// Bytes are only read ahead if they can be given back: either they stay in the
+/// buffer of a [BufRead] or the input is seeked back to the last consumed byte.
+pub(crate) struct Input<R: Read> {
+    reader: R,
+    mode: Mode<R>,
+    buffer: Vec<u8>,
+    start: usize,
+    end: usize,
+}
+
+impl<R: Read> Input<R> {
+    pub(crate) fn new(reader: R) -> Self {
+        Self::with_mode(reader, Mode::Direct, 1)
+    }
+
+    pub(crate) fn with_buf_read(reader: R) -> Self
+    where
+        R: BufRead,
+    {
+        let mode = Mode::Buffered {
+            fill_buf: <R as BufRead>::fill_buf,
+            consume: <R as BufRead>::consume,
+        };
+
+        Self::with_mode(reader, mode, 0)
+    }
+
+    pub(crate) fn with_seek(reader: R) -> Self
+    where
+        R: Seek,
+    {
+        let mode = Mode::Seekable {
+            seek: <R as Seek>::seek,
+        };
+
+        Self::with_mode(reader, mode, BLOCK_SIZE)
+    }
+
+    fn with_mode(reader: R, mode: Mode<R>, buffer_size: usize) -> Self {
+        Self {
+            reader,
+            mode,
+            buffer: vec![0u8; buffer_size],
+            start: 0,
+            end: 0,
+        }
+    }
+
+    pub(crate) fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), IoError> {
+        let count = (self.end - self.start).min(buf.len());
+        buf[..count].copy_from_slice(&self.buffer[self.start..self.start + count]);
+        self.start += count;
+
+        self.reader.read_exact(&mut buf[count..])
+    }
+
+    /// Input that isn't consumed yet or an error at the end of the input
+    pub(crate) fn fill_buf(&mut self) -> Result<&[u8], IoError> {
+        let buf = match &self.mode {
+            Mode::Buffered { fill_buf, .. } => fill_buf(&mut self.reader)?,
+            Mode::Direct | Mode::Seekable { .. } => {
+                if self.start == self.end {
+                    self.start = 0;
+                    self.end = loop {
+                        match self.reader.read(&mut self.buffer) {
+                            Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                            result => break result?,
+                        }
+                    };
+                }
+
+                &self.buffer[self.start..self.end]
+            }
+        };
+
+        if buf.is_empty() {
+            return Err(IoError::from(ErrorKind::UnexpectedEof));
+        }
+
+        Ok(buf)
+    }
+
+    /// Mark `length` bytes returned by [Input::fill_buf] as consumed
+    pub(crate) fn consume(&mut self, length: usize) {
+        match &self.mode {
+            Mode::Buffered { consume, .. } => consume(&mut self.reader, length),
+            Mode::Direct | Mode::Seekable { .. } => self.start += length,
+        }
+    }
+
+    pub(crate) fn skip(&mut self, length: usize) -> Result<(), IoError> {
+        let count = (self.end - self.start).min(length);
+        self.start += count;
+        let length = length - count;
+
+        if length == 0 {
+            return Ok(());
+        }
+
+        if let Mode::Seekable { seek } = &self.mode {
+            // Seeking past the end succeeds, the next read reports the end of the input
+            seek(&mut self.reader, SeekFrom::Current(length as i64))?;
+            return Ok(());
+        }
+
+        let skipped = std::io::copy(
+            &mut (&mut self.reader).take(length as u64),
+            &mut std::io::sink(),
+        )?;
+
+        if skipped < length as u64 {
+            return Err(IoError::from(ErrorKind::UnexpectedEof));
+        }
+
+        Ok(())
+    }
+}
+
+impl<R: Read> Drop for Input<R> {
+    fn drop(&mut self) {
+        if let Mode::Seekable { seek } = &self.mode {
+            let unused = self.end - self.start;
+
+            if unused > 0 {
+                // Errors can't be reported here, the input is left after the read-ahead
+                let _ = seek(&mut self.reader, SeekFrom::Current(-(unused as i64)));
+            }
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use std::fmt::Write;
+
+pub(crate) const JFXX_SIGNATURE: &[u8] = b"JFXX\0";
+
+/// Content of an APP0 segment with `JFXX\0` signature
+#[derive(Debug)]
+pub struct App0Jfxx {
+    /// 0x10: JPEG, 0x11: 1 byte per pixel with palette, 0x13: 3 bytes per pixel RGB
+    pub extension_code: u8,
+    /// Thumbnail of known extension codes
+    pub thumbnail: Option<Thumbnail>,
+    /// Raw extension data after the extension code
+    pub data: Vec<u8>,
+}
+
+/// Thumbnail stored in a JFIF or JFXX segment
+#[derive(Debug)]
+pub enum Thumbnail {
+    /// JPEG-coded thumbnail
+    Jpeg(Vec<u8>),
+    /// 1 byte per pixel referencing a palette of 256 RGB values
+    Palette {
+        width: u8,
+        height: u8,
+        palette: Vec<u8>,
+        pixels: Vec<u8>,
+    },
+    /// 3 bytes per pixel RGB
+    Rgb {
+        width: u8,
+        height: u8,
+        pixels: Vec<u8>,
+    },
+}
+
+impl Thumbnail {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Thumbnail::Jpeg(_) => "JPEG",
+            Thumbnail::Palette { .. } => "Palette",
+            Thumbnail::Rgb { .. } => "RGB",
+        }
+    }
+
+    /// Number of bytes of uncompressed thumbnails as given by their dimensions
+    pub fn expected_size(&self) -> Option<usize> {
+        match self {
+            Thumbnail::Jpeg(_) => None,
+            Thumbnail::Palette { width, height, .. } => Some(*width as usize * *height as usize),
+            Thumbnail::Rgb { width, height, .. } => Some(3 * *width as usize * *height as usize),
+        }
+    }
+
+    /// Number of bytes of the thumbnail data without palette
+    pub fn size(&self) -> usize {
+        match self {
+            Thumbnail::Jpeg(data) => data.len(),
+            Thumbnail::Palette { pixels, .. } | Thumbnail::Rgb { pixels, .. } => pixels.len(),
+        }
+    }
+
+    /// True if the size of an uncompressed thumbnail matches its dimensions
+    pub fn is_valid(&self) -> bool {
+        match self {
+            Thumbnail::Palette { palette, .. } if palette.len() != 768 => false,
+            _ => match self.expected_size() {
+                Some(size) => size == self.size(),
+                None => true,
+            },
+        }
+    }
+
+    /// File extension matching the output of [Thumbnail::to_file]
+    pub fn file_extension(&self) -> &'static str {
+        match self {
+            Thumbnail::Jpeg(_) => "jpg",
+            _ => "ppm",
+        }
+    }
+
+    /// Content of a JPEG file for JPEG thumbnails or a binary PPM file otherwise
+    ///
+    /// Returns `None` for invalid uncompressed thumbnails.
+    pub fn to_file(&self) -> Option<Vec<u8>> {
+        let (width, height, rgb) = match self {
+            Thumbnail::Jpeg(data) => return Some(data.clone()),
+            _ if !self.is_valid() => return None,
+            Thumbnail::Palette {
+                width,
+                height,
+                palette,
+                pixels,
+            } => {
+                let rgb = pixels
+                    .iter()
+                    .flat_map(|&index| {
+                        let index = index as usize * 3;
+                        palette[index..index + 3].iter().copied()
+                    })
+                    .collect();
+                (width, height, rgb)
+            }
+            Thumbnail::Rgb {
+                width,
+                height,
+                pixels,
+            } => (width, height, pixels.clone()),
+        };
+
+        let mut header = String::new();
+        write!(header, "P6\n{} {}\n255\n", width, height).unwrap();
+
+        let mut file = header.into_bytes();
+        file.extend_from_slice(&rgb);
+
+        Some(file)
+    }
+}
+
+/// Parse the content of an APP0 segment after the `JFXX\0` signature
+pub(crate) fn parse_jfxx(data: &[u8]) -> Option<App0Jfxx> {
+    let (&extension_code, data) = data.split_first()?;
+
+    let thumbnail = match extension_code {
+        0x10 => Some(Thumbnail::Jpeg(data.to_vec())),
+        0x11 if data.len() >= 2 + 768 => Some(Thumbnail::Palette {
+            width: data[0],
+            height: data[1],
+            palette: data[2..2 + 768].to_vec(),
+            pixels: data[2 + 768..].to_vec(),
+        }),
+        0x13 if data.len() >= 2 => Some(Thumbnail::Rgb {
+            width: data[0],
+            height: data[1],
+            pixels: data[2..].to_vec(),
+        }),
+        _ => None,
+    };
+
+    Some(App0Jfxx {
+        extension_code,
+        thumbnail,
+        data: data.to_vec(),
+    })
+}
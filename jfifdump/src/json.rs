@@ -1,4 +1,10 @@
-use crate::{App0Jfif, Dac, Dht, Dqt, Frame, Handler, Rst, Scan};
+use crate::quality::{frame_tables, update_tables};
+use crate::{
+    estimate_quality, estimate_table_quality, signature_string, App0Jfif, App0Jfxx, App13Photoshop,
+    App14Adobe, App1Exif, App1Xmp, App1XmpExtension, App2Icc, App2Mpf, Dac, Dht, Dqt, EncoderMatch,
+    ExifValue, ExtendedXmp, Frame, Handler, IccProfile, Ifd, MpEntry, Rst, Scan, Thumbnail,
+    Warning,
+};
 
 use std::fmt::Write;
 use jzon::object::Object;
@@ -17,10 +23,48 @@ pub fn get_marker_string(data: &[u8], max: usize) -> String {
     result
 }
 
+fn exif_value_to_json(value: &ExifValue) -> JsonValue {
+    match value {
+        ExifValue::Ascii(_) => value.as_ascii().unwrap_or_default().into(),
+        ExifValue::Byte(v) | ExifValue::Undefined(v) | ExifValue::Unknown(v) => v.clone().into(),
+        ExifValue::Short(v) => v.clone().into(),
+        ExifValue::Long(v) | ExifValue::Ifd(v) => v.clone().into(),
+        ExifValue::Rational(v) => v
+            .iter()
+            .map(|&(n, d)| JsonValue::from(vec![n, d]))
+            .collect::<Vec<_>>()
+            .into(),
+        ExifValue::SByte(v) => v.clone().into(),
+        ExifValue::SShort(v) => v.clone().into(),
+        ExifValue::SLong(v) => v.clone().into(),
+        ExifValue::SRational(v) => v
+            .iter()
+            .map(|&(n, d)| JsonValue::from(vec![n, d]))
+            .collect::<Vec<_>>()
+            .into(),
+        ExifValue::Float(v) => v.clone().into(),
+        ExifValue::Double(v) => v.clone().into(),
+        ExifValue::InvalidOffset(offset) => object! { invalid_offset: *offset },
+    }
+}
+
+fn mp_entry_to_json(entry: &MpEntry) -> JsonValue {
+    object! {
+        type: entry.image_type_name(),
+        attribute: entry.attribute,
+        size: entry.size,
+        offset: entry.offset,
+        dependent_images: vec![entry.dependent_image_1, entry.dependent_image_2],
+    }
+}
 
 pub struct JsonFormat {
     markers: Vec<JsonValue>,
     verbose: bool,
+    jfif: bool,
+    adobe: Option<App14Adobe>,
+    quantization_tables: Vec<Dqt>,
+    frame: Option<Frame>,
 }
 
 impl JsonFormat {
@@ -28,6 +72,10 @@ impl JsonFormat {
         JsonFormat {
             markers: vec![],
             verbose,
+            jfif: false,
+            adobe: None,
+            quantization_tables: vec![],
+            frame: None,
         }
     }
 
@@ -35,6 +83,46 @@ impl JsonFormat {
         self.markers.push(JsonValue::Object(value));
     }
 
+    fn ifd_to_json(&self, ifd: &Ifd) -> JsonValue {
+        let entries: Vec<JsonValue> = ifd
+            .entries
+            .iter()
+            .map(|entry| {
+                let mut e_value = Object::new();
+                e_value.insert("tag", entry.tag.into());
+                if let Some(name) = entry.tag_name(ifd.kind) {
+                    e_value.insert("name", name.into());
+                }
+                e_value.insert("type", entry.value.type_name().into());
+                e_value.insert("count", entry.count.into());
+
+                let is_blob = matches!(
+                    entry.value,
+                    ExifValue::Byte(_) | ExifValue::Undefined(_) | ExifValue::Unknown(_)
+                );
+
+                if self.verbose || !is_blob || entry.count <= 16 {
+                    e_value.insert("value", exif_value_to_json(&entry.value));
+                }
+
+                JsonValue::Object(e_value)
+            })
+            .collect();
+
+        let sub_ifds: Vec<JsonValue> = ifd
+            .sub_ifds
+            .iter()
+            .map(|sub_ifd| self.ifd_to_json(sub_ifd))
+            .collect();
+
+        object! {
+            name: ifd.kind.name(),
+            offset: ifd.offset,
+            entries: entries,
+            sub_ifds: sub_ifds,
+        }
+    }
+
     pub fn stringify(&self) -> String {
         jzon::stringify_pretty(JsonValue::Array(self.markers.clone()), 4)
     }
@@ -62,6 +150,8 @@ impl Handler for JsonFormat {
         value.insert("length", length.into());
         value.insert("marker", "App(0x0):JFIF".into());
 
+        self.jfif = true;
+
         let mut density = Object::new();
 
         match jfif.unit {
@@ -79,6 +169,10 @@ impl Handler for JsonFormat {
         thumbnail.insert("width", jfif.x_thumbnail.into());
         thumbnail.insert("height", jfif.y_thumbnail.into());
 
+        if let Some(rgb_thumbnail) = jfif.rgb_thumbnail() {
+            thumbnail.insert("valid", rgb_thumbnail.is_valid().into());
+        }
+
         if self.verbose {
             if let Some(data) = &jfif.thumbnail {
                 thumbnail.insert("data", data.clone().into());
@@ -90,7 +184,256 @@ impl Handler for JsonFormat {
         self.add(value);
     }
 
+    fn handle_app0_jfxx(&mut self, position: usize, length: usize, jfxx: &App0Jfxx) {
+        let mut value = Object::new();
+        value.insert("position", position.into());
+        value.insert("length", length.into());
+        value.insert("marker", "App(0x0):JFXX".into());
+        value.insert("extension_code", jfxx.extension_code.into());
+
+        if let Some(jfxx_thumbnail) = &jfxx.thumbnail {
+            let mut thumbnail = Object::new();
+            thumbnail.insert("type", jfxx_thumbnail.name().into());
+
+            match jfxx_thumbnail {
+                Thumbnail::Jpeg(_) => {}
+                Thumbnail::Palette { width, height, .. } | Thumbnail::Rgb { width, height, .. } => {
+                    thumbnail.insert("width", (*width).into());
+                    thumbnail.insert("height", (*height).into());
+                }
+            }
+
+            thumbnail.insert("size", jfxx_thumbnail.size().into());
+            thumbnail.insert("valid", jfxx_thumbnail.is_valid().into());
+
+            value.insert("thumbnail", thumbnail.into());
+        }
+
+        if self.verbose {
+            value.insert("data", jfxx.data.clone().into());
+        }
+
+        self.add(value);
+    }
+
+    fn handle_app1_exif(&mut self, position: usize, length: usize, exif: &App1Exif) {
+        let mut value = Object::new();
+        value.insert("position", position.into());
+        value.insert("length", length.into());
+        value.insert("marker", "App(0x1):Exif".into());
+        value.insert("byte_order", exif.byte_order.name().into());
+
+        let ifds: Vec<JsonValue> = exif.ifds.iter().map(|ifd| self.ifd_to_json(ifd)).collect();
+        value.insert("ifds", ifds.into());
+
+        self.add(value);
+    }
+
+    fn handle_app1_xmp(&mut self, position: usize, length: usize, xmp: &App1Xmp) {
+        let mut value = Object::new();
+        value.insert("position", position.into());
+        value.insert("length", length.into());
+        value.insert("marker", "App(0x1):XMP".into());
+
+        if let Some(guid) = xmp.extended_guid() {
+            value.insert("extended_guid", guid.into());
+        }
+
+        value.insert("size", xmp.packet.len().into());
+
+        if self.verbose {
+            value.insert("packet", xmp.packet.as_str().into());
+        }
+
+        self.add(value);
+    }
+
+    fn handle_app1_xmp_extension(
+        &mut self,
+        position: usize,
+        length: usize,
+        extension: &App1XmpExtension,
+    ) {
+        let mut value = Object::new();
+        value.insert("position", position.into());
+        value.insert("length", length.into());
+        value.insert("marker", "App(0x1):ExtendedXMP".into());
+        value.insert("guid", extension.guid.as_str().into());
+        value.insert("full_length", extension.full_length.into());
+        value.insert("offset", extension.offset.into());
+        value.insert("size", extension.data.len().into());
+
+        if self.verbose {
+            value.insert("data", extension.data.clone().into());
+        }
+
+        self.add(value);
+    }
+
+    fn handle_extended_xmp(&mut self, xmp: &ExtendedXmp) {
+        let mut value = Object::new();
+        value.insert("marker", "ExtendedXMP".into());
+        value.insert("guid", xmp.guid.as_str().into());
+        value.insert("full_length", xmp.full_length.into());
+        value.insert("size", xmp.data.len().into());
+        value.insert("complete", xmp.complete.into());
+        value.insert("checksum_valid", xmp.checksum_valid.into());
+
+        if self.verbose {
+            match xmp.document() {
+                Some(document) => value.insert("document", document.into()),
+                None => value.insert("raw", xmp.data.clone().into()),
+            }
+        }
+
+        self.add(value);
+    }
+
+    fn handle_app2_icc(&mut self, position: usize, length: usize, chunk: &App2Icc) {
+        let mut value = Object::new();
+        value.insert("position", position.into());
+        value.insert("length", length.into());
+        value.insert("marker", "App(0x2):ICC".into());
+        value.insert("sequence", chunk.sequence.into());
+        value.insert("count", chunk.count.into());
+        value.insert("size", chunk.data.len().into());
+
+        self.add(value);
+    }
+
+    fn handle_icc_profile(&mut self, profile: &IccProfile) {
+        let mut value = Object::new();
+        value.insert("marker", "ICCProfile".into());
+        value.insert("size", profile.data.len().into());
+
+        if let Some(header) = &profile.header {
+            value.insert(
+                "version",
+                format!(
+                    "{}.{}.{}",
+                    header.version_major, header.version_minor, header.version_bugfix
+                )
+                .into(),
+            );
+            value.insert("cmm", signature_string(&header.cmm).into());
+            value.insert("class", signature_string(&header.class).into());
+            value.insert("color_space", signature_string(&header.color_space).into());
+            value.insert("pcs", signature_string(&header.pcs).into());
+            value.insert("rendering_intent", header.rendering_intent_name().into());
+
+            if let Some(description) = &header.description {
+                value.insert("description", description.as_str().into());
+            }
+        }
+
+        let issues: Vec<JsonValue> = profile
+            .issues
+            .iter()
+            .map(|issue| issue.to_string().into())
+            .collect();
+        value.insert("issues", issues.into());
+
+        if self.verbose {
+            value.insert("data", profile.data.clone().into());
+        }
+
+        self.add(value);
+    }
+
+    fn handle_app2_mpf(&mut self, position: usize, length: usize, mpf: &App2Mpf) {
+        let mut value = Object::new();
+        value.insert("position", position.into());
+        value.insert("length", length.into());
+        value.insert("marker", "App(0x2):MPF".into());
+        value.insert("byte_order", mpf.byte_order.name().into());
+
+        let images: Vec<JsonValue> = mpf.images.iter().map(mp_entry_to_json).collect();
+        value.insert("images", images.into());
+
+        if self.verbose {
+            let ifds: Vec<JsonValue> = mpf.ifds.iter().map(|ifd| self.ifd_to_json(ifd)).collect();
+            value.insert("ifds", ifds.into());
+        }
+
+        self.add(value);
+    }
+
+    fn handle_app13_photoshop(
+        &mut self,
+        position: usize,
+        length: usize,
+        photoshop: &App13Photoshop,
+    ) {
+        let mut value = Object::new();
+        value.insert("position", position.into());
+        value.insert("length", length.into());
+        value.insert("marker", "App(0xD):Photoshop".into());
+
+        let resources: Vec<JsonValue> = photoshop
+            .resources
+            .iter()
+            .map(|resource| {
+                let mut r_value = Object::new();
+                r_value.insert("signature", signature_string(&resource.signature).into());
+                r_value.insert("id", resource.id.into());
+                r_value.insert("type", resource.id_name().into());
+                r_value.insert("name", resource.name_string().into());
+                r_value.insert("size", resource.data.len().into());
+
+                if let Some(iptc) = &resource.iptc {
+                    let datasets: Vec<JsonValue> = iptc
+                        .datasets
+                        .iter()
+                        .map(|dataset| {
+                            let mut d_value = Object::new();
+                            d_value.insert("record", dataset.record.into());
+                            d_value.insert("dataset", dataset.dataset.into());
+                            d_value.insert("name", dataset.name().into());
+
+                            if let Some(number) = dataset.number() {
+                                d_value.insert("value", number.into());
+                            } else if dataset.is_binary() {
+                                if self.verbose {
+                                    d_value.insert("raw", dataset.data.as_slice().into());
+                                }
+                            } else {
+                                d_value.insert("value", iptc.text(dataset).into());
+                            }
+
+                            JsonValue::Object(d_value)
+                        })
+                        .collect();
+                    r_value.insert("iptc", datasets.into());
+                } else if self.verbose {
+                    r_value.insert("raw", resource.data.as_slice().into());
+                }
+
+                JsonValue::Object(r_value)
+            })
+            .collect();
+        value.insert("resources", resources.into());
+
+        self.add(value);
+    }
+
+    fn handle_app14_adobe(&mut self, position: usize, length: usize, adobe: &App14Adobe) {
+        let mut value = Object::new();
+        value.insert("position", position.into());
+        value.insert("length", length.into());
+        value.insert("marker", "App(0xE):Adobe".into());
+        value.insert("version", adobe.version.into());
+        value.insert("flags0", adobe.flags0.into());
+        value.insert("flags1", adobe.flags1.into());
+        value.insert("transform", adobe.transform.into());
+
+        self.adobe = Some(adobe.clone());
+
+        self.add(value);
+    }
+
     fn handle_dqt(&mut self, position: usize, length: usize, tables: &[Dqt]) {
+        update_tables(&mut self.quantization_tables, tables);
+
         let mut value = Object::new();
         value.insert("position", position.into());
         value.insert("length", length.into());
@@ -103,6 +446,10 @@ impl Handler for JsonFormat {
                 t_value.insert("dest", table.dest.into());
                 t_value.insert("precision", table.precision.into());
 
+                let quality = estimate_table_quality(table);
+                t_value.insert("quality", quality.quality.into());
+                t_value.insert("ijg_exact", quality.exact.into());
+
                 if self.verbose {
                     t_value.insert("data", table.values.to_vec().into());
                 }
@@ -134,6 +481,13 @@ impl Handler for JsonFormat {
                     t_value.insert("values", table.values.to_vec().into());
                 }
 
+                let issues: Vec<JsonValue> = table
+                    .issues(self.frame.as_ref())
+                    .iter()
+                    .map(|issue| issue.to_string().into())
+                    .collect();
+                t_value.insert("issues", issues.into());
+
                 JsonValue::Object(t_value)
             })
             .collect();
@@ -173,7 +527,21 @@ impl Handler for JsonFormat {
         value.insert("marker", "SOF".into());
         value.insert("type", frame.get_sof_name().into());
 
+        let color_space = frame.color_space(self.jfif, self.adobe.as_ref());
+
         value.insert("precision", frame.precision.into());
+        value.insert("color_space", color_space.name().into());
+
+        if let Some(quality) = estimate_quality(&frame_tables(&self.quantization_tables, frame)) {
+            value.insert(
+                "quality",
+                object! {
+                    quality: quality.quality,
+                    ijg_exact: quality.exact,
+                },
+            );
+        }
+
         value.insert(
             "dimension",
             object! {
@@ -202,6 +570,8 @@ impl Handler for JsonFormat {
         );
 
         self.add(value);
+
+        self.frame = Some(frame.clone());
     }
 
     fn handle_scan(&mut self, position: usize, length: usize, scan: &Scan) {
@@ -241,7 +611,7 @@ impl Handler for JsonFormat {
             },
         );
 
-        value.insert("size", scan.data.len().into());
+        value.insert("size", scan.data_length.into());
 
         if self.verbose {
             value.insert("data", scan.data.clone().into());
@@ -266,7 +636,7 @@ impl Handler for JsonFormat {
         value.insert("length", length.into());
         value.insert("marker", format!("RST({})", restart.nr).into());
 
-        value.insert("size", restart.data.len().into());
+        value.insert("size", restart.data_length.into());
 
         if self.verbose {
             value.insert("data", restart.data.clone().into());
@@ -320,4 +690,55 @@ impl Handler for JsonFormat {
         value.insert("marker", "SOI".into());
         self.add(value);
     }
+
+    fn handle_embedded_image(&mut self, index: usize, position: usize, entry: Option<&MpEntry>) {
+        self.jfif = false;
+        self.adobe = None;
+        self.quantization_tables.clear();
+        self.frame = None;
+
+        let mut value = Object::new();
+        value.insert("position", position.into());
+        value.insert("marker", "EmbeddedImage".into());
+        value.insert("index", index.into());
+
+        if let Some(entry) = entry {
+            value.insert("mp_entry", mp_entry_to_json(entry));
+        }
+
+        self.add(value);
+    }
+
+    fn handle_warning(&mut self, warning: &Warning) {
+        let mut value = Object::new();
+        value.insert("position", warning.position.into());
+        value.insert("marker", "Warning".into());
+        value.insert("kind", warning.kind.name().into());
+        value.insert("message", warning.kind.to_string().into());
+
+        self.add(value);
+    }
+
+    fn handle_encoder(&mut self, matches: &[EncoderMatch]) {
+        let candidates: Vec<JsonValue> = matches
+            .iter()
+            .map(|m| {
+                let evidence: Vec<JsonValue> =
+                    m.evidence.iter().map(|e| e.to_string().into()).collect();
+
+                object! {
+                    name: m.encoder.name(),
+                    score: m.score,
+                    max_score: m.max_score,
+                    evidence: evidence,
+                }
+            })
+            .collect();
+
+        let mut value = Object::new();
+        value.insert("marker", "Encoder".into());
+        value.insert("candidates", candidates.into());
+
+        self.add(value);
+    }
 }
@@ -1,6 +1,14 @@
 //! # JFIF Dump
 //!
-//! A crate for reading the content of a JFIF file without decoding JPEG image data.
+//! A crate for reading the content of a JFIF file.
+//!
+//! [SliceReader] reads files in memory without copying raw segment and entropy-coded data.
+//!
+//! [PushParser] parses input that is fed in chunks of any size.
+//!
+//! [JfifDocument] collects all segments into an owned model with queries for common content.
+//!
+//! Image data can optionally be decoded with [decode_coefficients], [decode_image] or [decode_lossless].
 //!
 //! ## Example: Print image dimensions
 //!
@@ -37,71 +45,171 @@
 
 use std::io::{ErrorKind, Read};
 
+pub use decoder::{decode_coefficients, CoefficientDecoder, ComponentCoefficients, DecoderOptions};
+pub use document::JfifDocument;
 pub use error::JfifError;
+pub use exif::{App1Exif, ByteOrder, ExifValue, Ifd, IfdEntry, IfdKind};
+pub use fingerprint::{identify_encoder, Encoder, EncoderFeatures, EncoderMatch, Evidence};
 pub use handler::Handler;
+pub use huffman::{HuffmanCode, HuffmanTableIssue};
+pub use icc::{signature_string, App2Icc, IccChunkIssue, IccHeader, IccProfile};
+pub use image::{decode_image, Image, ImageDecoder, PixelFormat};
+pub use jfxx::{App0Jfxx, Thumbnail};
+pub use lossless::{decode_lossless, ComponentSamples, LosslessDecoder};
+pub use mpf::{App2Mpf, MpEntry};
+pub use photoshop::{App13Photoshop, ImageResource, Iptc, IptcDataset};
+pub use push::PushParser;
+pub use quality::{estimate_quality, estimate_table_quality, QualityEstimate};
 pub use reader::{
-    App0Jfif, Dac, Dht, Dqt, Frame, FrameComponent, Reader, Rst, Scan, ScanComponent, Segment,
-    SegmentKind,
+    App0Jfif, App14Adobe, ColorSpace, Dac, Dht, Dqt, Frame, FrameComponent, Reader, ReaderOptions,
+    Rst, Scan, ScanComponent, Segment, SegmentKind,
+};
+pub use slice::{
+    Destuffed, EntropyData, SliceReader, SliceRst, SliceScan, SliceSegment, SliceSegmentKind,
 };
 pub use text::TextFormat;
+pub use validate::{validate, Finding, Rule, Severity, Validator};
+pub use warning::{Warning, WarningKind};
+pub use writer::Writer;
+pub use xmp::{App1Xmp, App1XmpExtension, ExtendedXmp};
 
 #[cfg(feature = "json")]
 pub use crate::json::JsonFormat;
 
+#[cfg(feature = "tokio")]
+pub use crate::async_reader::{read_async, AsyncReader};
+
+mod arithmetic;
+#[cfg(feature = "tokio")]
+mod async_reader;
+mod decoder;
+mod document;
 mod error;
+mod exif;
+mod fingerprint;
 mod handler;
+mod huffman;
+mod icc;
+mod idct;
+mod image;
+mod input;
+mod jfxx;
 #[cfg(feature = "json")]
 mod json;
+mod lossless;
+mod md5;
+mod mpf;
+mod photoshop;
+mod push;
+mod quality;
 mod reader;
+mod slice;
 mod text;
+mod validate;
+mod warning;
+mod writer;
+mod xmp;
 
 /// Read JFIF input and call handler for all segments
 pub fn read<H: Handler, R: Read>(input: R, handler: &mut H) -> Result<(), JfifError> {
     let mut reader = Reader::new(input)?;
+    let mut handled_warnings = 0;
+    let mut features = EncoderFeatures::new();
 
     loop {
         let segment = match reader.next_segment() {
             Ok(segment) => segment,
             Err(JfifError::IoError(ioerror)) => {
-                return if ioerror.kind() == ErrorKind::UnexpectedEof {
-                    Ok(())
+                if ioerror.kind() == ErrorKind::UnexpectedEof {
+                    break;
                 } else {
-                    Err(JfifError::IoError(ioerror))
+                    return Err(JfifError::IoError(ioerror));
                 }
             }
             Err(err) => return Err(err),
         };
 
-        match segment.kind {
-            SegmentKind::Soi => handler.handle_soi(segment.position, segment.length),
-            SegmentKind::Eoi => handler.handle_eoi(segment.position, segment.length),
-            SegmentKind::App { nr, data } => {
-                handler.handle_app(segment.position, segment.length, nr, &data)
-            }
-            SegmentKind::App0Jfif(jfif) => {
-                handler.handle_app0_jfif(segment.position, segment.length, &jfif)
-            }
-            SegmentKind::Dqt(tables) => {
-                handler.handle_dqt(segment.position, segment.length, &tables)
-            }
-            SegmentKind::Dht(tables) => {
-                handler.handle_dht(segment.position, segment.length, &tables)
-            }
-            SegmentKind::Dac(dac) => handler.handle_dac(segment.position, segment.length, &dac),
-            SegmentKind::Frame(frame) => {
-                handler.handle_frame(segment.position, segment.length, &frame)
-            }
-            SegmentKind::Scan(scan) => handler.handle_scan(segment.position, segment.length, &scan),
-            SegmentKind::Dri(restart) => {
-                handler.handle_dri(segment.position, segment.length, restart)
-            }
-            SegmentKind::Rst(rst) => handler.handle_rst(segment.position, segment.length, &rst),
-            SegmentKind::Comment(data) => {
-                handler.handle_comment(segment.position, segment.length, &data)
-            }
-            SegmentKind::Unknown { marker, data } => {
-                handler.handle_unknown(segment.position, segment.length, marker, &data)
+        features.process(&segment.kind);
+
+        let mp_entry = reader.mp_entry(segment.position);
+        handle_segment(handler, segment, reader.image_index(), mp_entry);
+
+        for warning in &reader.warnings()[handled_warnings..] {
+            handler.handle_warning(warning);
+        }
+        handled_warnings = reader.warnings().len();
+    }
+
+    for xmp in reader.extended_xmp() {
+        handler.handle_extended_xmp(&xmp);
+    }
+
+    if let Some(profile) = reader.icc_profile() {
+        handler.handle_icc_profile(&profile);
+    }
+
+    handler.handle_encoder(&features.identify());
+
+    Ok(())
+}
+
+/// Call the handler method for a segment
+pub(crate) fn handle_segment<H: Handler>(
+    handler: &mut H,
+    segment: Segment,
+    image_index: usize,
+    mp_entry: Option<&MpEntry>,
+) {
+    match segment.kind {
+        SegmentKind::Soi => {
+            if image_index > 0 {
+                handler.handle_embedded_image(image_index, segment.position, mp_entry);
             }
-        };
+            handler.handle_soi(segment.position, segment.length)
+        }
+        SegmentKind::Eoi => handler.handle_eoi(segment.position, segment.length),
+        SegmentKind::App { nr, data } => {
+            handler.handle_app(segment.position, segment.length, nr, &data)
+        }
+        SegmentKind::App0Jfif(jfif) => {
+            handler.handle_app0_jfif(segment.position, segment.length, &jfif)
+        }
+        SegmentKind::App0Jfxx(jfxx) => {
+            handler.handle_app0_jfxx(segment.position, segment.length, &jfxx)
+        }
+        SegmentKind::App1Exif(exif) => {
+            handler.handle_app1_exif(segment.position, segment.length, &exif)
+        }
+        SegmentKind::App1Xmp(xmp) => {
+            handler.handle_app1_xmp(segment.position, segment.length, &xmp)
+        }
+        SegmentKind::App1XmpExtension(extension) => {
+            handler.handle_app1_xmp_extension(segment.position, segment.length, &extension)
+        }
+        SegmentKind::App2Icc(chunk) => {
+            handler.handle_app2_icc(segment.position, segment.length, &chunk)
+        }
+        SegmentKind::App2Mpf(mpf) => {
+            handler.handle_app2_mpf(segment.position, segment.length, &mpf)
+        }
+        SegmentKind::App13Photoshop(photoshop) => {
+            handler.handle_app13_photoshop(segment.position, segment.length, &photoshop)
+        }
+        SegmentKind::App14Adobe(adobe) => {
+            handler.handle_app14_adobe(segment.position, segment.length, &adobe)
+        }
+        SegmentKind::Dqt(tables) => handler.handle_dqt(segment.position, segment.length, &tables),
+        SegmentKind::Dht(tables) => handler.handle_dht(segment.position, segment.length, &tables),
+        SegmentKind::Dac(dac) => handler.handle_dac(segment.position, segment.length, &dac),
+        SegmentKind::Frame(frame) => handler.handle_frame(segment.position, segment.length, &frame),
+        SegmentKind::Scan(scan) => handler.handle_scan(segment.position, segment.length, &scan),
+        SegmentKind::Dri(restart) => handler.handle_dri(segment.position, segment.length, restart),
+        SegmentKind::Rst(rst) => handler.handle_rst(segment.position, segment.length, &rst),
+        SegmentKind::Comment(data) => {
+            handler.handle_comment(segment.position, segment.length, &data)
+        }
+        SegmentKind::Unknown { marker, data } => {
+            handler.handle_unknown(segment.position, segment.length, marker, &data)
+        }
     }
 }
@@ -0,0 +1,461 @@
+use std::fmt::Write;
+use std::io::Read;
+
+use crate::decoder::{huffman_table, DecoderOptions};
+use crate::huffman::{BitReader, HuffmanTable};
+use crate::{Frame, JfifError, Reader, Scan, SegmentKind};
+
+/// Reconstructed samples of a frame component of a lossless image
+#[derive(Debug)]
+pub struct ComponentSamples {
+    pub id: u8,
+    pub horizontal_sampling_factor: u8,
+    pub vertical_sampling_factor: u8,
+    /// Number of samples per line of the component
+    pub width: usize,
+    /// Number of lines of the component
+    pub height: usize,
+    /// Number of samples per line including samples padding the last MCU
+    pub samples_per_line: usize,
+    /// Number of lines including lines padding the last MCU
+    pub lines: usize,
+    /// Samples line by line shifted left by the point transform of their scan
+    pub samples: Vec<u16>,
+}
+
+impl ComponentSamples {
+    pub fn sample(&self, x: usize, y: usize) -> u16 {
+        self.samples[y * self.samples_per_line + x]
+    }
+}
+
+struct ScanComponentState {
+    /// Index into the frame components
+    index: usize,
+    table: u8,
+    /// Position of the first sample of the current restart interval
+    interval_start: (usize, usize),
+}
+
+struct ScanState {
+    components: Vec<ScanComponentState>,
+    /// Index of the next MCU to decode
+    mcu: usize,
+    mcu_count: usize,
+    predictor: u8,
+    point_transform: u8,
+}
+
+/// Decodes samples of lossless frames (Annex H of ITU T.81) segment by segment
+///
+/// All segments of an image have to be passed to [LosslessDecoder::process] in order.
+/// Supported are Huffman-coded frames with a precision of 2 to 16 bits.
+#[derive(Default)]
+pub struct LosslessDecoder {
+    options: DecoderOptions,
+    frame: Option<Frame>,
+    components: Vec<ComponentSamples>,
+    mcus_per_line: usize,
+    mcus_per_column: usize,
+    tables: [Option<HuffmanTable>; 4],
+    restart_interval: u16,
+    scan: Option<ScanState>,
+}
+
+impl LosslessDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new decoder with options
+    pub fn with_options(options: DecoderOptions) -> Self {
+        Self {
+            options,
+            ..Self::default()
+        }
+    }
+
+    /// Frame of the decoded image
+    pub fn frame(&self) -> Option<&Frame> {
+        self.frame.as_ref()
+    }
+
+    /// Samples of all frame components
+    pub fn components(&self) -> &[ComponentSamples] {
+        &self.components
+    }
+
+    /// Update the decoder state with the next segment of the image
+    pub fn process(&mut self, kind: &SegmentKind) -> Result<(), JfifError> {
+        match kind {
+            SegmentKind::Frame(frame) => self.start_frame(frame),
+            SegmentKind::Dht(tables) => {
+                for table in tables {
+                    match (table.class, table.dest) {
+                        (0, dest @ 0..=3) => {
+                            self.tables[dest as usize] = Some(HuffmanTable::new(table)?)
+                        }
+                        _ => return Err(JfifError::InvalidHuffmanTable(table.class, table.dest)),
+                    }
+                }
+                Ok(())
+            }
+            SegmentKind::Dri(restart_interval) => {
+                self.restart_interval = *restart_interval;
+                Ok(())
+            }
+            SegmentKind::Scan(scan) => {
+                self.start_scan(scan)?;
+                self.decode_interval(&scan.data)
+            }
+            SegmentKind::Rst(rst) => self.decode_interval(&rst.data),
+            SegmentKind::Eoi => {
+                self.scan = None;
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Content of a binary PGM, PPM or PAM file depending on the number of components
+    ///
+    /// Samples with more than 8 bits are written with two bytes.
+    /// All components must have the same size.
+    pub fn to_pnm(&self) -> Result<Vec<u8>, JfifError> {
+        let frame = self.frame.as_ref().ok_or(JfifError::MissingFrame)?;
+        let width = frame.dimension_x as usize;
+        let height = frame.dimension_y as usize;
+
+        if self
+            .components
+            .iter()
+            .any(|c| c.width != width || c.height != height)
+        {
+            return Err(JfifError::UnsupportedSampling);
+        }
+
+        let max_value = (1u32 << frame.precision) - 1;
+
+        let mut header = String::new();
+
+        match self.components.len() {
+            1 => write!(header, "P5\n{} {}\n{}\n", width, height, max_value),
+            3 => write!(header, "P6\n{} {}\n{}\n", width, height, max_value),
+            depth => write!(
+                header,
+                "P7\nWIDTH {}\nHEIGHT {}\nDEPTH {}\nMAXVAL {}\nENDHDR\n",
+                width, height, depth, max_value
+            ),
+        }
+        .unwrap();
+
+        let mut file = header.into_bytes();
+
+        for y in 0..height {
+            for x in 0..width {
+                for component in &self.components {
+                    let sample = component.sample(x, y);
+
+                    if max_value > 255 {
+                        file.extend_from_slice(&sample.to_be_bytes());
+                    } else {
+                        file.push(sample as u8);
+                    }
+                }
+            }
+        }
+
+        Ok(file)
+    }
+
+    fn start_frame(&mut self, frame: &Frame) -> Result<(), JfifError> {
+        if frame.sof != 0xC3 {
+            return Err(JfifError::UnsupportedFrame(frame.sof));
+        }
+
+        if !(2..=16).contains(&frame.precision) {
+            return Err(JfifError::UnsupportedPrecision(frame.precision));
+        }
+
+        if frame.dimension_x == 0 || frame.dimension_y == 0 || frame.components.is_empty() {
+            return Err(JfifError::InvalidFrameDimensions(
+                frame.dimension_x,
+                frame.dimension_y,
+            ));
+        }
+
+        let max_h = frame
+            .components
+            .iter()
+            .map(|c| c.horizontal_sampling_factor.max(1) as usize)
+            .max()
+            .unwrap_or(1);
+        let max_v = frame
+            .components
+            .iter()
+            .map(|c| c.vertical_sampling_factor.max(1) as usize)
+            .max()
+            .unwrap_or(1);
+
+        let width = frame.dimension_x as usize;
+        let height = frame.dimension_y as usize;
+
+        self.mcus_per_line = width.div_ceil(max_h);
+        self.mcus_per_column = height.div_ceil(max_v);
+
+        let samples: usize = frame
+            .components
+            .iter()
+            .map(|c| {
+                let h = c.horizontal_sampling_factor.max(1) as usize;
+                let v = c.vertical_sampling_factor.max(1) as usize;
+                (self.mcus_per_line * h).saturating_mul(self.mcus_per_column * v)
+            })
+            .fold(0, usize::saturating_add);
+
+        self.options
+            .check_memory(samples.saturating_mul(std::mem::size_of::<u16>()))?;
+
+        self.components = frame
+            .components
+            .iter()
+            .map(|c| {
+                let h = c.horizontal_sampling_factor.max(1) as usize;
+                let v = c.vertical_sampling_factor.max(1) as usize;
+
+                let samples_per_line = self.mcus_per_line * h;
+                let lines = self.mcus_per_column * v;
+
+                ComponentSamples {
+                    id: c.id,
+                    horizontal_sampling_factor: h as u8,
+                    vertical_sampling_factor: v as u8,
+                    width: (width * h).div_ceil(max_h),
+                    height: (height * v).div_ceil(max_v),
+                    samples_per_line,
+                    lines,
+                    samples: vec![0; samples_per_line * lines],
+                }
+            })
+            .collect();
+
+        self.frame = Some(frame.clone());
+        self.scan = None;
+
+        Ok(())
+    }
+
+    fn start_scan(&mut self, scan: &Scan) -> Result<(), JfifError> {
+        let precision = match &self.frame {
+            Some(frame) => frame.precision,
+            None => return Err(JfifError::MissingFrame),
+        };
+
+        // The predictor is stored in the start of spectral selection and the point
+        // transform in the low nibble, which is read into `approximation_high`
+        let predictor = scan.selection_start;
+        let point_transform = scan.approximation_high;
+
+        if !(1..=7).contains(&predictor) || point_transform >= precision {
+            return Err(JfifError::InvalidScanParameters);
+        }
+
+        let components = scan
+            .components
+            .iter()
+            .map(|sc| {
+                let index = self
+                    .components
+                    .iter()
+                    .position(|c| c.id == sc.id)
+                    .ok_or(JfifError::InvalidScanComponent(sc.id))?;
+
+                Ok(ScanComponentState {
+                    index,
+                    table: sc.dc_table,
+                    interval_start: (0, 0),
+                })
+            })
+            .collect::<Result<Vec<_>, JfifError>>()?;
+
+        let mcu_count = match components.as_slice() {
+            [component] => {
+                let component = &self.components[component.index];
+                component.width * component.height
+            }
+            _ => self.mcus_per_line * self.mcus_per_column,
+        };
+
+        self.scan = Some(ScanState {
+            components,
+            mcu: 0,
+            mcu_count,
+            predictor,
+            point_transform,
+        });
+
+        Ok(())
+    }
+
+    /// Decode the MCUs of a single restart interval or of the whole scan without restart interval
+    fn decode_interval(&mut self, data: &[u8]) -> Result<(), JfifError> {
+        let mut scan = match self.scan.take() {
+            Some(scan) => scan,
+            None => return Ok(()),
+        };
+
+        let remaining = scan.mcu_count - scan.mcu;
+
+        let count = if self.restart_interval > 0 {
+            remaining.min(self.restart_interval as usize)
+        } else {
+            remaining
+        };
+
+        let result = self.decode_mcus(&mut scan, &mut BitReader::new(data), count);
+
+        self.scan = Some(scan);
+
+        result
+    }
+
+    fn decode_mcus(
+        &mut self,
+        scan: &mut ScanState,
+        reader: &mut BitReader,
+        count: usize,
+    ) -> Result<(), JfifError> {
+        let interleaved = scan.components.len() > 1;
+        let precision = self.frame.as_ref().map_or(8, |frame| frame.precision);
+        let mcus_per_line = self.mcus_per_line;
+
+        // Position of the first sample of every component in the MCU
+        let mcu_position = |component: &ComponentSamples, mcu: usize| {
+            if interleaved {
+                let h = component.horizontal_sampling_factor as usize;
+                let v = component.vertical_sampling_factor as usize;
+                (mcu % mcus_per_line * h, mcu / mcus_per_line * v)
+            } else {
+                (mcu % component.width, mcu / component.width)
+            }
+        };
+
+        for sc in &mut scan.components {
+            sc.interval_start = mcu_position(&self.components[sc.index], scan.mcu);
+        }
+
+        let predictions = Predictions {
+            predictor: scan.predictor,
+            point_transform: scan.point_transform,
+            initial: 1 << (precision - scan.point_transform - 1),
+        };
+
+        for mcu in scan.mcu..scan.mcu + count {
+            for sc in &scan.components {
+                let table = huffman_table(&self.tables, 0, sc.table)?;
+                let component = &mut self.components[sc.index];
+                let (x, y) = mcu_position(component, mcu);
+
+                let (h, v) = if interleaved {
+                    (
+                        component.horizontal_sampling_factor as usize,
+                        component.vertical_sampling_factor as usize,
+                    )
+                } else {
+                    (1, 1)
+                };
+
+                for y in y..y + v {
+                    for x in x..x + h {
+                        let difference = decode_difference(reader, table)?;
+                        let prediction = predictions.predict(component, x, y, sc.interval_start);
+
+                        let sample = (prediction + difference) as u16;
+                        component.samples[y * component.samples_per_line + x] =
+                            sample << scan.point_transform;
+                    }
+                }
+            }
+        }
+
+        scan.mcu += count;
+
+        Ok(())
+    }
+}
+
+/// Prediction of samples (H.1.2.1 of ITU T.81)
+struct Predictions {
+    predictor: u8,
+    point_transform: u8,
+    /// Prediction of the first sample of a scan or restart interval
+    initial: i32,
+}
+
+impl Predictions {
+    fn predict(
+        &self,
+        component: &ComponentSamples,
+        x: usize,
+        y: usize,
+        interval_start: (usize, usize),
+    ) -> i32 {
+        let sample = |x: usize, y: usize| {
+            (component.samples[y * component.samples_per_line + x] >> self.point_transform) as i32
+        };
+
+        if (x, y) == interval_start {
+            return self.initial;
+        }
+
+        // The first line of a restart interval uses the sample to the left
+        if y == interval_start.1 {
+            return sample(x - 1, y);
+        }
+
+        // The first sample of every other line uses the sample above
+        if x == 0 {
+            return sample(x, y - 1);
+        }
+
+        let a = sample(x - 1, y);
+        let b = sample(x, y - 1);
+        let c = sample(x - 1, y - 1);
+
+        match self.predictor {
+            1 => a,
+            2 => b,
+            3 => c,
+            4 => a + b - c,
+            5 => a + ((b - c) >> 1),
+            6 => b + ((a - c) >> 1),
+            _ => (a + b) >> 1,
+        }
+    }
+}
+
+fn decode_difference(reader: &mut BitReader, table: &HuffmanTable) -> Result<i32, JfifError> {
+    match table.decode(reader)? {
+        16 => Ok(32768),
+        length @ 0..=15 => Ok(reader.receive_extend(length as u32)),
+        _ => Err(JfifError::InvalidScanData),
+    }
+}
+
+/// Decode the samples of the primary image of a lossless file
+pub fn decode_lossless<R: Read>(input: R) -> Result<LosslessDecoder, JfifError> {
+    let mut reader = Reader::new(input)?;
+    let mut decoder = LosslessDecoder::new();
+
+    loop {
+        let segment = reader.next_segment()?;
+
+        if let SegmentKind::Eoi = segment.kind {
+            break;
+        }
+
+        decoder.process(&segment.kind)?;
+    }
+
+    Ok(decoder)
+}
@@ -0,0 +1,213 @@
+use crate::exif::{ByteOrder, ExifValue, Ifd, IfdKind, Tiff};
+use crate::JfifError;
+
+pub(crate) const MPF_SIGNATURE: &[u8] = b"MPF\0";
+
+const TAG_MP_ENTRY: u16 = 0xB002;
+
+/// Content of an APP2 segment with `MPF\0` signature (CIPA DC-007 Multi-Picture Format)
+///
+/// The raw data is borrowed from the input if read by [SliceReader](crate::SliceReader).
+#[derive(Debug)]
+pub struct App2Mpf<D = Vec<u8>> {
+    pub byte_order: ByteOrder,
+    /// MP Index IFD (only in the first image) and MP Attribute IFD
+    pub ifds: Vec<Ifd>,
+    /// Entries of the MP Entry table
+    pub images: Vec<MpEntry>,
+    /// Position of the MP header in the file. Offsets of MP entries are relative to it.
+    pub header_position: usize,
+    /// Raw TIFF data without the `MPF\0` signature
+    pub data: D,
+}
+
+impl App2Mpf<&[u8]> {
+    /// Copy the borrowed raw data
+    pub fn into_owned(self) -> App2Mpf {
+        App2Mpf {
+            byte_order: self.byte_order,
+            ifds: self.ifds,
+            images: self.images,
+            header_position: self.header_position,
+            data: self.data.to_vec(),
+        }
+    }
+}
+
+impl App2Mpf {
+    /// Change the size of the image at `index` of the MP Entry table
+    ///
+    /// Updates both `images` and the raw `data`. Fails if `index` is not
+    /// part of the table.
+    pub fn set_image_size(&mut self, index: usize, size: u32) -> Result<(), JfifError> {
+        self.write_entry_value(index, 4, size)?;
+
+        if let Some(image) = self.images.get_mut(index) {
+            image.size = size;
+        }
+
+        Ok(())
+    }
+
+    /// Change the offset of the image at `index` of the MP Entry table
+    ///
+    /// Updates both `images` and the raw `data`. Needed if the distance
+    /// between the MP header and an image changes while writing a file.
+    pub fn set_image_offset(&mut self, index: usize, offset: u32) -> Result<(), JfifError> {
+        self.write_entry_value(index, 8, offset)?;
+
+        if let Some(image) = self.images.get_mut(index) {
+            image.offset = offset;
+        }
+
+        Ok(())
+    }
+
+    fn write_entry_value(
+        &mut self,
+        index: usize,
+        field: usize,
+        value: u32,
+    ) -> Result<(), JfifError> {
+        let (tiff, first_ifd) = Tiff::new(&self.data)?;
+
+        if index >= self.images.len() {
+            return Err(JfifError::InvalidTiffOffset(first_ifd as usize));
+        }
+
+        let position = tiff
+            .value_offset(first_ifd, TAG_MP_ENTRY)?
+            .and_then(|table| table.checked_add(index * 16 + field))
+            .ok_or(JfifError::InvalidTiffOffset(first_ifd as usize))?;
+
+        let bytes = match self.byte_order {
+            ByteOrder::LittleEndian => value.to_le_bytes(),
+            ByteOrder::BigEndian => value.to_be_bytes(),
+        };
+
+        self.data
+            .get_mut(position..position + 4)
+            .ok_or(JfifError::InvalidTiffOffset(position))?
+            .copy_from_slice(&bytes);
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MpEntry {
+    /// Individual image attribute containing flags, format and type
+    pub attribute: u32,
+    pub size: u32,
+    /// Offset relative to the MP header. Always 0 for the first image.
+    pub offset: u32,
+    pub dependent_image_1: u16,
+    pub dependent_image_2: u16,
+}
+
+impl MpEntry {
+    pub fn is_dependent_parent(&self) -> bool {
+        self.attribute & 0x8000_0000 != 0
+    }
+
+    pub fn is_dependent_child(&self) -> bool {
+        self.attribute & 0x4000_0000 != 0
+    }
+
+    pub fn is_representative(&self) -> bool {
+        self.attribute & 0x2000_0000 != 0
+    }
+
+    /// Image data format. 0 is JPEG.
+    pub fn format(&self) -> u8 {
+        ((self.attribute >> 24) & 0x07) as u8
+    }
+
+    pub fn image_type(&self) -> u32 {
+        self.attribute & 0x00FF_FFFF
+    }
+
+    pub fn image_type_name(&self) -> &'static str {
+        match self.image_type() {
+            0x030000 => "Baseline MP Primary Image",
+            0x010001 => "Large Thumbnail (VGA)",
+            0x010002 => "Large Thumbnail (Full HD)",
+            0x020001 => "Multi-Frame Panorama",
+            0x020002 => "Multi-Frame Disparity",
+            0x020003 => "Multi-Frame Multi-Angle",
+            0x000000 => "Undefined",
+            _ => "Unknown",
+        }
+    }
+
+    /// Absolute position of the image in the file given the position of the MP header
+    pub fn position(&self, header_position: usize) -> Option<usize> {
+        if self.offset == 0 {
+            None
+        } else {
+            Some(header_position + self.offset as usize)
+        }
+    }
+}
+
+/// Parse the content of an APP2 segment after the `MPF\0` signature
+///
+/// `header_position` is the absolute position of the TIFF header in the file.
+pub(crate) fn parse_mpf(data: &[u8], header_position: usize) -> Result<App2Mpf<&[u8]>, JfifError> {
+    let (tiff, first_ifd) = Tiff::new(data)?;
+    let byte_order = tiff.byte_order();
+
+    let mut ifds = vec![];
+    let mut images = vec![];
+
+    let (entries, next) = tiff.read_ifd(first_ifd)?;
+
+    let mp_entries = entries.iter().find(|e| e.tag == TAG_MP_ENTRY);
+
+    if let Some(mp_entries) = mp_entries {
+        if let ExifValue::Undefined(table) = &mp_entries.value {
+            for entry in table.chunks_exact(16) {
+                images.push(MpEntry {
+                    attribute: tiff.u32_from(&entry[0..4]),
+                    size: tiff.u32_from(&entry[4..8]),
+                    offset: tiff.u32_from(&entry[8..12]),
+                    dependent_image_1: tiff.u16_from(&entry[12..14]),
+                    dependent_image_2: tiff.u16_from(&entry[14..16]),
+                });
+            }
+        }
+    }
+
+    // Only the first image has an MP Index IFD which is followed by the MP Attribute IFD
+    let kind = if mp_entries.is_some() {
+        IfdKind::MpIndex
+    } else {
+        IfdKind::MpAttribute
+    };
+
+    ifds.push(Ifd {
+        kind,
+        offset: first_ifd,
+        entries,
+        sub_ifds: vec![],
+    });
+
+    if kind == IfdKind::MpIndex && next != 0 && next != first_ifd {
+        let (entries, _) = tiff.read_ifd(next)?;
+
+        ifds.push(Ifd {
+            kind: IfdKind::MpAttribute,
+            offset: next,
+            entries,
+            sub_ifds: vec![],
+        });
+    }
+
+    Ok(App2Mpf {
+        byte_order,
+        ifds,
+        images,
+        header_position,
+        data,
+    })
+}
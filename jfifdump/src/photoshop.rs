@@ -0,0 +1,357 @@
+pub(crate) const PHOTOSHOP_SIGNATURE: &[u8] = b"Photoshop 3.0\0";
+
+const RESOURCE_IPTC: u16 = 0x0404;
+
+/// Content of an APP13 segment with `Photoshop 3.0\0` signature
+///
+/// The raw data is borrowed from the input if read by [SliceReader](crate::SliceReader).
+#[derive(Debug)]
+pub struct App13Photoshop<D = Vec<u8>> {
+    /// Image resource blocks. Parsing stops at the first malformed block.
+    pub resources: Vec<ImageResource>,
+    /// Raw resource data without the signature
+    pub data: D,
+}
+
+impl App13Photoshop<&[u8]> {
+    /// Copy the borrowed raw data
+    pub fn into_owned(self) -> App13Photoshop {
+        App13Photoshop {
+            resources: self.resources,
+            data: self.data.to_vec(),
+        }
+    }
+}
+
+/// A Photoshop image resource block
+#[derive(Debug)]
+pub struct ImageResource {
+    /// Signature of the block. Usually `8BIM`.
+    pub signature: [u8; 4],
+    pub id: u16,
+    /// Raw content of the Pascal string name. Mostly empty.
+    pub name: Vec<u8>,
+    pub data: Vec<u8>,
+    /// Decoded IPTC-IIM datasets if this is an IPTC-NAA resource
+    pub iptc: Option<Iptc>,
+}
+
+impl ImageResource {
+    pub fn name_string(&self) -> String {
+        String::from_utf8_lossy(&self.name).into_owned()
+    }
+
+    pub fn id_name(&self) -> &'static str {
+        match self.id {
+            0x03E8 => "Channels, rows, columns, depth and mode",
+            0x03E9 => "Print manager info",
+            0x03ED => "Resolution info",
+            0x03EE => "Alpha channel names",
+            0x03F0 => "Caption",
+            0x03F1 => "Border info",
+            0x03F2 => "Background color",
+            0x03F3 => "Print flags",
+            0x03F4 => "Grayscale halftoning info",
+            0x03F5 => "Color halftoning info",
+            0x03F6 => "Duotone halftoning info",
+            0x03F7 => "Grayscale transfer function",
+            0x03F8 => "Color transfer functions",
+            0x03F9 => "Duotone transfer functions",
+            0x03FA => "Duotone image info",
+            0x03FB => "Effective black and white values",
+            0x03FD => "EPS options",
+            0x03FE => "Quick mask info",
+            0x0400 => "Layer state info",
+            0x0401 => "Working path",
+            0x0402 => "Layers group info",
+            0x0404 => "IPTC-NAA",
+            0x0405 => "Image mode for raw format",
+            0x0406 => "JPEG quality",
+            0x0408 => "Grid and guides info",
+            0x0409 => "Thumbnail (Photoshop 4.0)",
+            0x040A => "Copyright flag",
+            0x040B => "URL",
+            0x040C => "Thumbnail",
+            0x040D => "Global angle",
+            0x040E => "Color samplers",
+            0x040F => "ICC profile",
+            0x0410 => "Watermark",
+            0x0411 => "ICC untagged profile",
+            0x0412 => "Effects visible",
+            0x0413 => "Spot halftone",
+            0x0414 => "Document IDs seed",
+            0x0415 => "Unicode alpha names",
+            0x0416 => "Indexed color table count",
+            0x0417 => "Transparency index",
+            0x0419 => "Global altitude",
+            0x041A => "Slices",
+            0x041B => "Workflow URL",
+            0x041C => "Jump to XPEP",
+            0x041D => "Alpha identifiers",
+            0x041E => "URL list",
+            0x0421 => "Version info",
+            0x0422 => "EXIF data 1",
+            0x0423 => "EXIF data 3",
+            0x0424 => "XMP metadata",
+            0x0425 => "Caption digest",
+            0x0426 => "Print scale",
+            0x0428 => "Pixel aspect ratio",
+            0x0429 => "Layer comps",
+            0x042A => "Alternate duotone colors",
+            0x042B => "Alternate spot colors",
+            0x042D => "Layer selection IDs",
+            0x042E => "HDR toning info",
+            0x042F => "Print info",
+            0x0430 => "Layer groups enabled",
+            0x0431 => "Color samplers",
+            0x0432 => "Measurement scale",
+            0x0433 => "Timeline info",
+            0x0434 => "Sheet disclosure",
+            0x0435 => "Display info",
+            0x0436 => "Onion skins",
+            0x0438 => "Count info",
+            0x043A => "Print info",
+            0x043B => "Print style",
+            0x043C => "Mac NSPrintInfo",
+            0x043D => "Windows DEVMODE",
+            0x043E => "Auto save file path",
+            0x043F => "Auto save format",
+            0x0440 => "Path selection state",
+            0x07D0..=0x0BB6 => "Path info",
+            0x0BB7 => "Clipping path name",
+            0x0BB8 => "Origin path info",
+            0x1B58 => "Image ready variables",
+            0x1B59 => "Image ready data sets",
+            0x1F40 => "Lightroom workflow",
+            0x2710 => "Print flags info",
+            _ => "Unknown",
+        }
+    }
+}
+
+/// IPTC-IIM datasets of an IPTC-NAA resource
+#[derive(Debug)]
+pub struct Iptc {
+    pub datasets: Vec<IptcDataset>,
+    /// True if dataset 1:90 declares UTF-8 as coded character set
+    pub utf8: bool,
+}
+
+impl Iptc {
+    /// Decode the value of a dataset as text
+    ///
+    /// Values are decoded as UTF-8 if 1:90 says so and as ISO 8859-1 otherwise.
+    pub fn text(&self, dataset: &IptcDataset) -> String {
+        if self.utf8 {
+            String::from_utf8_lossy(&dataset.data).into_owned()
+        } else {
+            dataset.data.iter().map(|&v| v as char).collect()
+        }
+    }
+}
+
+/// A single IPTC-IIM dataset
+#[derive(Debug)]
+pub struct IptcDataset {
+    pub record: u8,
+    pub dataset: u8,
+    pub data: Vec<u8>,
+}
+
+impl IptcDataset {
+    /// Numeric value of datasets stored as binary 16 bit integer
+    pub fn number(&self) -> Option<u16> {
+        match (self.record, self.dataset) {
+            (1, 0) | (1, 20) | (1, 22) | (1, 120) | (2, 0) | (2, 200) | (2, 201) => {
+                match self.data[..] {
+                    [a, b] => Some(u16::from_be_bytes([a, b])),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// True for datasets containing binary data that shouldn't be shown as text
+    pub fn is_binary(&self) -> bool {
+        matches!((self.record, self.dataset), (1, 90) | (2, 125) | (2, 202))
+            || self.record > 2
+            || self.number().is_some()
+    }
+
+    pub fn name(&self) -> &'static str {
+        match (self.record, self.dataset) {
+            (1, 0) => "EnvelopeRecordVersion",
+            (1, 5) => "Destination",
+            (1, 20) => "FileFormat",
+            (1, 22) => "FileFormatVersion",
+            (1, 30) => "ServiceIdentifier",
+            (1, 40) => "EnvelopeNumber",
+            (1, 50) => "ProductID",
+            (1, 60) => "EnvelopePriority",
+            (1, 70) => "DateSent",
+            (1, 80) => "TimeSent",
+            (1, 90) => "CodedCharacterSet",
+            (1, 100) => "UniqueObjectName",
+            (1, 120) => "ARMIdentifier",
+            (1, 122) => "ARMVersion",
+            (2, 0) => "ApplicationRecordVersion",
+            (2, 3) => "ObjectTypeReference",
+            (2, 4) => "ObjectAttributeReference",
+            (2, 5) => "ObjectName",
+            (2, 7) => "EditStatus",
+            (2, 8) => "EditorialUpdate",
+            (2, 10) => "Urgency",
+            (2, 12) => "SubjectReference",
+            (2, 15) => "Category",
+            (2, 20) => "SupplementalCategories",
+            (2, 22) => "FixtureIdentifier",
+            (2, 25) => "Keywords",
+            (2, 26) => "ContentLocationCode",
+            (2, 27) => "ContentLocationName",
+            (2, 30) => "ReleaseDate",
+            (2, 35) => "ReleaseTime",
+            (2, 37) => "ExpirationDate",
+            (2, 38) => "ExpirationTime",
+            (2, 40) => "SpecialInstructions",
+            (2, 42) => "ActionAdvised",
+            (2, 45) => "ReferenceService",
+            (2, 47) => "ReferenceDate",
+            (2, 50) => "ReferenceNumber",
+            (2, 55) => "DateCreated",
+            (2, 60) => "TimeCreated",
+            (2, 62) => "DigitalCreationDate",
+            (2, 63) => "DigitalCreationTime",
+            (2, 65) => "OriginatingProgram",
+            (2, 70) => "ProgramVersion",
+            (2, 75) => "ObjectCycle",
+            (2, 80) => "By-line",
+            (2, 85) => "By-lineTitle",
+            (2, 90) => "City",
+            (2, 92) => "Sub-location",
+            (2, 95) => "Province-State",
+            (2, 100) => "Country-PrimaryLocationCode",
+            (2, 101) => "Country-PrimaryLocationName",
+            (2, 103) => "OriginalTransmissionReference",
+            (2, 105) => "Headline",
+            (2, 110) => "Credit",
+            (2, 115) => "Source",
+            (2, 116) => "CopyrightNotice",
+            (2, 118) => "Contact",
+            (2, 120) => "Caption-Abstract",
+            (2, 121) => "LocalCaption",
+            (2, 122) => "Writer-Editor",
+            (2, 125) => "RasterizedCaption",
+            (2, 130) => "ImageType",
+            (2, 131) => "ImageOrientation",
+            (2, 135) => "LanguageIdentifier",
+            (2, 150) => "AudioType",
+            (2, 151) => "AudioSamplingRate",
+            (2, 152) => "AudioSamplingResolution",
+            (2, 153) => "AudioDuration",
+            (2, 154) => "AudioOutcue",
+            (2, 184) => "JobID",
+            (2, 200) => "ObjectPreviewFileFormat",
+            (2, 201) => "ObjectPreviewFileVersion",
+            (2, 202) => "ObjectPreviewData",
+            _ => "Unknown",
+        }
+    }
+}
+
+/// Parse the content of an APP13 segment after the `Photoshop 3.0\0` signature
+pub(crate) fn parse_photoshop(data: &[u8]) -> App13Photoshop<&[u8]> {
+    let mut resources = vec![];
+    let mut offset = 0;
+
+    while let Some(resource) = parse_resource(data, &mut offset) {
+        resources.push(resource);
+    }
+
+    App13Photoshop { resources, data }
+}
+
+fn parse_resource(data: &[u8], offset: &mut usize) -> Option<ImageResource> {
+    let mut pos = *offset;
+
+    let mut signature = [0u8; 4];
+    signature.copy_from_slice(data.get(pos..pos + 4)?);
+
+    if !matches!(&signature, b"8BIM" | b"PHUT" | b"AgHg" | b"DCSR") {
+        return None;
+    }
+
+    let id = u16::from_be_bytes([*data.get(pos + 4)?, *data.get(pos + 5)?]);
+    pos += 6;
+
+    // Pascal string padded to an even size including the length byte
+    let name_length = *data.get(pos)? as usize;
+    let name = data.get(pos + 1..pos + 1 + name_length)?.to_vec();
+    pos += (name_length + 2) & !1;
+
+    let size = data.get(pos..pos + 4)?;
+    let size = u32::from_be_bytes([size[0], size[1], size[2], size[3]]) as usize;
+    pos += 4;
+
+    let resource_data = data.get(pos..pos.checked_add(size)?)?.to_vec();
+    pos += size + (size & 1);
+
+    *offset = pos;
+
+    let iptc = if id == RESOURCE_IPTC {
+        Some(parse_iptc(&resource_data))
+    } else {
+        None
+    };
+
+    Some(ImageResource {
+        signature,
+        id,
+        name,
+        data: resource_data,
+        iptc,
+    })
+}
+
+/// Parse IPTC-IIM datasets. Parsing stops at the first malformed dataset.
+fn parse_iptc(data: &[u8]) -> Iptc {
+    let mut datasets = vec![];
+    let mut pos = 0;
+
+    while data.get(pos) == Some(&0x1C) && pos + 5 <= data.len() {
+        let record = data[pos + 1];
+        let dataset = data[pos + 2];
+        let mut length = u16::from_be_bytes([data[pos + 3], data[pos + 4]]) as usize;
+        pos += 5;
+
+        // Extended dataset with the size of the length field in the lower 15 bits
+        if length & 0x8000 != 0 {
+            let count = length & 0x7FFF;
+            let bytes = match data.get(pos..pos + count) {
+                Some(bytes) if count <= 4 => bytes,
+                _ => break,
+            };
+            length = bytes.iter().fold(0, |acc, &v| (acc << 8) | v as usize);
+            pos += count;
+        }
+
+        let value = match pos.checked_add(length).and_then(|end| data.get(pos..end)) {
+            Some(value) => value,
+            None => break,
+        };
+        pos += length;
+
+        datasets.push(IptcDataset {
+            record,
+            dataset,
+            data: value.to_vec(),
+        });
+    }
+
+    // ESC % G selects UTF-8
+    let utf8 = datasets
+        .iter()
+        .any(|d| d.record == 1 && d.dataset == 90 && d.data.starts_with(b"\x1B%G"));
+
+    Iptc { datasets, utf8 }
+}
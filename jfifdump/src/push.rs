@@ -0,0 +1,492 @@
+//! Parser for input that arrives in chunks
+
+use std::io::{Error as IoError, ErrorKind};
+
+use crate::reader::{check_length, find_ff, ParsedSegment, ReaderState};
+use crate::{
+    ExtendedXmp, IccProfile, JfifError, MpEntry, ReaderOptions, Rst, Scan, Segment, SegmentKind,
+    Warning,
+};
+
+/// A parser for JFIF files that is fed with chunks of input
+///
+/// Segments are returned by [PushParser::next_segment] as soon as all of their data was fed.
+/// Incomplete segments and entropy-coded data are kept until the next chunk arrives.
+/// After the last chunk [PushParser::finish] reports input that ends inside a segment.
+///
+/// ```no_run
+/// # use jfifdump::JfifError;
+/// # fn main() -> Result<(), JfifError> {
+/// # let chunks: Vec<Vec<u8>> = vec![];
+/// use jfifdump::{PushParser, SegmentKind};
+///
+/// let mut parser = PushParser::new();
+///
+/// for chunk in chunks {
+///     parser.feed(&chunk);
+///
+///     while let Some(segment) = parser.next_segment()? {
+///         if let SegmentKind::Frame(frame) = segment.kind {
+///             println!("{}x{}", frame.dimension_x, frame.dimension_y);
+///         }
+///     }
+/// }
+///
+/// while let Some(segment) = parser.finish()? {
+///     println!("{:?}", segment.kind);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct PushParser {
+    options: ReaderOptions,
+    buffer: Vec<u8>,
+    /// Start of the unconsumed input in `buffer`
+    offset: usize,
+    /// Position of the unconsumed input in the stream
+    position: usize,
+    step: Step,
+    current_marker: Option<u8>,
+    state: ReaderState,
+}
+
+/// Scan or RST marker waiting for the end of its entropy-coded data
+enum PendingData {
+    Scan(Scan),
+    Rst(u8),
+}
+
+enum Step {
+    /// Waiting for the SOI at the start of the input
+    Start,
+    /// Start searching the next marker unless it was read with entropy-coded data
+    Marker,
+    /// Skipping data between an EOI and the next image listed in the MPF segment
+    Skip { next: usize },
+    /// Searching the 0xFF of the next marker
+    Search { start: usize },
+    /// Skipping fill bytes before the marker
+    Fill,
+    /// Waiting for the content of a segment with a length field
+    Segment { marker: u8, position: usize },
+    /// Waiting for the end of entropy-coded data
+    Data {
+        pending: PendingData,
+        position: usize,
+        data_position: usize,
+        data: Vec<u8>,
+    },
+}
+
+/// Result of processing the available input for a step
+enum Progress {
+    Segment(Segment),
+    Continue,
+    Wait,
+}
+
+impl Default for PushParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PushParser {
+    /// Create a new parser
+    pub fn new() -> Self {
+        Self::with_options(ReaderOptions::default())
+    }
+
+    /// Create a new parser with options
+    pub fn with_options(options: ReaderOptions) -> Self {
+        Self {
+            options,
+            buffer: vec![],
+            offset: 0,
+            position: 0,
+            step: Step::Start,
+            current_marker: None,
+            state: ReaderState::default(),
+        }
+    }
+
+    /// Index of the image the last segment belongs to
+    ///
+    /// The primary image has index 0. Images embedded after its EOI like
+    /// the ones listed in an MPF segment are counted up from 1.
+    pub fn image_index(&self) -> usize {
+        self.state.image_index()
+    }
+
+    /// MP entry of the primary image's MPF segment for the image starting at `position`
+    pub fn mp_entry(&self, position: usize) -> Option<&MpEntry> {
+        self.state.mp_entry(position)
+    }
+
+    /// Extended XMP documents of the primary image reassembled from all chunks read so far
+    pub fn extended_xmp(&self) -> Vec<ExtendedXmp> {
+        self.state.xmp_assembler.finish()
+    }
+
+    /// ICC profile of the primary image reassembled from all chunks read so far
+    pub fn icc_profile(&self) -> Option<IccProfile> {
+        self.state.icc_assembler.finish()
+    }
+
+    /// Problems recovered from in all segments read so far
+    pub fn warnings(&self) -> &[Warning] {
+        &self.state.warnings
+    }
+
+    /// Number of bytes held for segments that weren't returned yet
+    ///
+    /// This includes retained entropy-coded data of a scan waiting for its end.
+    pub fn buffered(&self) -> usize {
+        let pending = match &self.step {
+            Step::Data { data, .. } => data.len(),
+            _ => 0,
+        };
+
+        self.buffer.len() - self.offset + pending
+    }
+
+    /// Append the next chunk of the input
+    pub fn feed(&mut self, chunk: &[u8]) {
+        // Drop consumed input before the buffer grows
+        if self.offset > 0 {
+            self.buffer.drain(..self.offset);
+            self.offset = 0;
+        }
+
+        self.buffer.extend_from_slice(chunk);
+    }
+
+    fn available(&self) -> &[u8] {
+        &self.buffer[self.offset..]
+    }
+
+    fn consume(&mut self, length: usize) {
+        self.offset += length;
+        self.position += length;
+    }
+
+    /// Return the next complete segment or None if more input is needed
+    pub fn next_segment(&mut self) -> Result<Option<Segment>, JfifError> {
+        loop {
+            let progress = match std::mem::replace(&mut self.step, Step::Marker) {
+                Step::Start => self.read_start()?,
+                Step::Marker => self.start_search()?,
+                Step::Skip { next } => self.skip(next),
+                Step::Search { start } => self.search(start),
+                Step::Fill => self.read_marker()?,
+                Step::Segment { marker, position } => self.read_segment(marker, position)?,
+                Step::Data {
+                    pending,
+                    position,
+                    data_position,
+                    data,
+                } => self.read_scan_data(pending, position, data_position, data)?,
+            };
+
+            match progress {
+                Progress::Segment(segment) => return Ok(Some(segment)),
+                Progress::Continue => {}
+                Progress::Wait => return Ok(None),
+            }
+        }
+    }
+
+    /// Return the next segment after all input was fed
+    ///
+    /// Call this until it returns None once the end of the input is reached.
+    /// Input that ends inside a segment or its entropy-coded data fails with
+    /// [ErrorKind::UnexpectedEof] like [crate::Reader] does.
+    pub fn finish(&mut self) -> Result<Option<Segment>, JfifError> {
+        if let Some(segment) = self.next_segment()? {
+            return Ok(Some(segment));
+        }
+
+        match self.step {
+            Step::Start => Err(JfifError::JfifMarkerNotFound),
+            Step::Segment { .. } | Step::Data { .. } => {
+                Err(IoError::from(ErrorKind::UnexpectedEof).into())
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn segment(&self, kind: SegmentKind, position: usize) -> Progress {
+        Progress::Segment(Segment {
+            kind,
+            position,
+            length: self.position - position,
+        })
+    }
+
+    fn read_start(&mut self) -> Result<Progress, JfifError> {
+        let available = self.available();
+
+        if available.len() < 2 && [0xFF, 0xD8].starts_with(available) {
+            self.step = Step::Start;
+            return Ok(Progress::Wait);
+        }
+
+        if !available.starts_with(&[0xFF, 0xD8]) {
+            self.step = Step::Start;
+            return Err(JfifError::JfifMarkerNotFound);
+        }
+
+        self.consume(2);
+        self.start_segment(0xD8)
+    }
+
+    fn start_search(&mut self) -> Result<Progress, JfifError> {
+        if let Some(marker) = self.current_marker.take() {
+            return self.start_segment(marker);
+        }
+
+        self.step = if let Some(next) = self.state.next_image(self.position) {
+            Step::Skip { next }
+        } else {
+            Step::Search {
+                start: self.position,
+            }
+        };
+
+        Ok(Progress::Continue)
+    }
+
+    fn skip(&mut self, next: usize) -> Progress {
+        let count = (next - self.position).min(self.available().len());
+        self.consume(count);
+
+        if self.position < next {
+            self.step = Step::Skip { next };
+            return Progress::Wait;
+        }
+
+        self.step = Step::Search {
+            start: self.position,
+        };
+
+        Progress::Continue
+    }
+
+    fn search(&mut self, start: usize) -> Progress {
+        match find_ff(self.available()) {
+            Some(count) => {
+                self.consume(count + 1);
+                self.state.garbage(start, self.position - 1 - start);
+                self.step = Step::Fill;
+                Progress::Continue
+            }
+            None => {
+                self.consume(self.available().len());
+                self.step = Step::Search { start };
+                Progress::Wait
+            }
+        }
+    }
+
+    fn read_marker(&mut self) -> Result<Progress, JfifError> {
+        let available = self.available();
+
+        match available.iter().position(|&b| b != 0xFF) {
+            Some(count) => {
+                let marker = available[count];
+                self.consume(count + 1);
+                self.start_segment(marker)
+            }
+            None => {
+                self.consume(available.len());
+                self.step = Step::Fill;
+                Ok(Progress::Wait)
+            }
+        }
+    }
+
+    /// Handle a marker and return the segment if it doesn't have any content
+    fn start_segment(&mut self, marker: u8) -> Result<Progress, JfifError> {
+        let position = self.position - 2;
+
+        match marker {
+            0x00 => Err(JfifError::InvalidMarker(0x00)),
+            0xD8 => {
+                self.state.start_image();
+                Ok(self.segment(SegmentKind::Soi, position))
+            }
+            0xD9 => {
+                self.state.end_image();
+                Ok(self.segment(SegmentKind::Eoi, position))
+            }
+            0xD0..=0xD7 => {
+                self.step = Step::Data {
+                    pending: PendingData::Rst(marker - 0xD0),
+                    position,
+                    data_position: self.position,
+                    data: vec![],
+                };
+                Ok(Progress::Continue)
+            }
+            marker => {
+                self.step = Step::Segment { marker, position };
+                Ok(Progress::Continue)
+            }
+        }
+    }
+
+    /// Parse a segment with a length field if all of its content is available
+    fn read_segment(&mut self, marker: u8, position: usize) -> Result<Progress, JfifError> {
+        let available = &self.buffer[self.offset..];
+
+        let content = available.get(..2).and_then(|length| {
+            let length = u16::from_be_bytes([length[0], length[1]]) as usize;
+            Some((length, available.get(2..length.max(2))?))
+        });
+
+        let (length, data) = match content {
+            Some((length, data)) => (check_length(length)?, data),
+            None => {
+                self.step = Step::Segment { marker, position };
+                return Ok(Progress::Wait);
+            }
+        };
+
+        let data_position = self.position + 2;
+
+        let kind = match self.state.parse_segment(marker, data, data_position)? {
+            ParsedSegment::App(app) => app.into(),
+            ParsedSegment::Dqt(tables) => SegmentKind::Dqt(tables),
+            ParsedSegment::Dht(tables) => SegmentKind::Dht(tables),
+            ParsedSegment::Dac(dac) => SegmentKind::Dac(dac),
+            ParsedSegment::Frame(frame) => SegmentKind::Frame(frame),
+            ParsedSegment::Scan(scan) => {
+                self.consume(2 + length);
+                self.step = Step::Data {
+                    pending: PendingData::Scan(scan),
+                    position,
+                    data_position: self.position,
+                    data: vec![],
+                };
+                return Ok(Progress::Continue);
+            }
+            ParsedSegment::Dri(restart) => SegmentKind::Dri(restart),
+            ParsedSegment::Raw => match marker {
+                0xE0..=0xEF => SegmentKind::App {
+                    nr: marker - 0xE0,
+                    data: data.to_vec(),
+                },
+                0xFE => SegmentKind::Comment(data.to_vec()),
+                marker => SegmentKind::Unknown {
+                    marker,
+                    data: data.to_vec(),
+                },
+            },
+        };
+
+        self.consume(2 + length);
+
+        Ok(self.segment(kind, position))
+    }
+
+    /// Consume entropy-coded data and return the segment if the next marker is available
+    #[allow(clippy::same_item_push)]
+    fn read_scan_data(
+        &mut self,
+        pending: PendingData,
+        position: usize,
+        data_position: usize,
+        mut data: Vec<u8>,
+    ) -> Result<Progress, JfifError> {
+        let retain = self.options.retain_scan_data;
+
+        let end = loop {
+            let available = &self.buffer[self.offset..];
+
+            let start = match find_ff(available) {
+                Some(start) => start,
+                None => {
+                    if retain {
+                        data.extend_from_slice(available);
+                    }
+                    let count = available.len();
+                    self.consume(count);
+                    break None;
+                }
+            };
+
+            if retain {
+                data.extend_from_slice(&available[..start]);
+            }
+
+            // Multiple 0xFF are not standard compliant but supported by libjpeg
+            let ff_count = match available[start..].iter().position(|&b| b != 0xFF) {
+                Some(ff_count) => ff_count,
+                None => {
+                    self.consume(start);
+                    break None;
+                }
+            };
+
+            let byte = available[start + ff_count];
+
+            self.consume(start);
+            let ff_position = self.position;
+            self.consume(ff_count + 1);
+
+            if byte != 0x00 {
+                break Some((ff_position, byte));
+            }
+
+            self.state.fill_bytes(ff_position, ff_count - 1);
+
+            if retain {
+                for _ in 0..ff_count {
+                    data.push(0xFF);
+                }
+                data.push(byte);
+            }
+        };
+
+        let (end, marker) = match end {
+            Some(end) => end,
+            None => {
+                self.step = Step::Data {
+                    pending,
+                    position,
+                    data_position,
+                    data,
+                };
+                return Ok(Progress::Wait);
+            }
+        };
+
+        let data_length = end - data_position;
+
+        let kind = match pending {
+            PendingData::Scan(mut scan) => {
+                scan.data = data;
+                scan.data_position = data_position;
+                scan.data_length = data_length;
+                SegmentKind::Scan(scan)
+            }
+            PendingData::Rst(nr) => SegmentKind::Rst(Rst {
+                nr,
+                data,
+                data_position,
+                data_length,
+            }),
+        };
+
+        // The segment ends after the marker like it does for the other readers
+        let segment = Segment {
+            kind,
+            position,
+            length: self.position - position,
+        };
+
+        self.current_marker = Some(marker);
+
+        Ok(Progress::Segment(segment))
+    }
+}
@@ -0,0 +1,125 @@
+//! Estimation of the IJG (libjpeg) quality factor from quantization tables
+
+use std::fmt::Display;
+
+use crate::decoder::ZIGZAG;
+use crate::{Dqt, Frame};
+
+/// Luminance quantization table of Annex K.1 of ITU T.81 in natural order
+const LUMINANCE: [u16; 64] = [
+    16, 11, 10, 16, 24, 40, 51, 61, 12, 12, 14, 19, 26, 58, 60, 55, 14, 13, 16, 24, 40, 57, 69, 56,
+    14, 17, 22, 29, 51, 87, 80, 62, 18, 22, 37, 56, 68, 109, 103, 77, 24, 35, 55, 64, 81, 104, 113,
+    92, 49, 64, 78, 87, 103, 121, 120, 101, 72, 92, 95, 98, 112, 100, 103, 99,
+];
+
+/// Chrominance quantization table of Annex K.1 of ITU T.81 in natural order
+const CHROMINANCE: [u16; 64] = [
+    17, 18, 24, 47, 99, 99, 99, 99, 18, 21, 26, 66, 99, 99, 99, 99, 24, 26, 56, 99, 99, 99, 99, 99,
+    47, 66, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99,
+    99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99,
+];
+
+/// Estimated IJG quality of one or more quantization tables
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QualityEstimate {
+    /// Quality factor from 1 to 100 of the closest IJG tables
+    pub quality: u8,
+    /// Whether the tables are exactly the IJG tables scaled to this quality
+    pub exact: bool,
+}
+
+impl Display for QualityEstimate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.exact {
+            write!(f, "{} (exact)", self.quality)
+        } else {
+            write!(f, "{} (estimated)", self.quality)
+        }
+    }
+}
+
+/// Base tables for destination 0 and all other destinations in natural order
+pub(crate) type BaseTables = [&'static [u16; 64]; 2];
+
+/// Annex K tables used by IJG encoders
+const ANNEX_K: BaseTables = [&LUMINANCE, &CHROMINANCE];
+
+/// Sum of the differences between a table and a base table scaled to a quality (`jcparam.c`)
+fn difference(table: &Dqt, base: &BaseTables, quality: u32) -> u32 {
+    let scale = if quality < 50 {
+        5000 / quality
+    } else {
+        200 - quality * 2
+    };
+
+    let max = if table.precision == 0 { 255 } else { 32767 };
+
+    table
+        .values
+        .iter()
+        .zip(ZIGZAG.iter())
+        .map(|(&value, &index)| {
+            let reference = base[(table.dest != 0) as usize][index] as u32;
+            let expected = ((reference * scale + 50) / 100).clamp(1, max);
+            (value as u32).abs_diff(expected)
+        })
+        .sum()
+}
+
+/// Estimate the quality of a single table
+///
+/// Table 0 is compared with the luminance and all other tables with the chrominance table.
+pub fn estimate_table_quality(table: &Dqt) -> QualityEstimate {
+    estimate_quality(std::slice::from_ref(table)).unwrap()
+}
+
+/// Remember the tables of a DQT segment replacing earlier tables with the same destination
+pub(crate) fn update_tables(collected: &mut Vec<Dqt>, tables: &[Dqt]) {
+    for table in tables {
+        collected.retain(|t| t.dest != table.dest);
+        collected.push(table.clone());
+    }
+}
+
+/// Tables referenced by the components of a frame
+pub(crate) fn frame_tables(collected: &[Dqt], frame: &Frame) -> Vec<Dqt> {
+    collected
+        .iter()
+        .filter(|t| {
+            frame
+                .components
+                .iter()
+                .any(|c| c.quantization_table == t.dest)
+        })
+        .cloned()
+        .collect()
+}
+
+/// Estimate the quality shared by all tables of an image
+///
+/// Returns the quality with the smallest total difference or None without tables.
+pub fn estimate_quality(tables: &[Dqt]) -> Option<QualityEstimate> {
+    estimate_scaled_quality(tables, &ANNEX_K)
+}
+
+/// Estimate the quality of tables scaled like IJG does from other base tables
+pub(crate) fn estimate_scaled_quality(
+    tables: &[Dqt],
+    base: &BaseTables,
+) -> Option<QualityEstimate> {
+    if tables.is_empty() {
+        return None;
+    }
+
+    let (quality, difference) = (1..=100)
+        .map(|quality| {
+            let difference: u32 = tables.iter().map(|t| difference(t, base, quality)).sum();
+            (quality, difference)
+        })
+        .min_by_key(|&(_, difference)| difference)?;
+
+    Some(QualityEstimate {
+        quality: quality as u8,
+        exact: difference == 0,
+    })
+}
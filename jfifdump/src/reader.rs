@@ -1,33 +1,139 @@
-use std::io::{Error as IoError, Read};
-
+use std::convert::TryInto;
+use std::io::{BufRead, Error as IoError, ErrorKind, Read, Seek};
+
+use crate::exif::{parse_exif, App1Exif};
+use crate::icc::{parse_icc_chunk, App2Icc, IccAssembler, IccProfile};
+use crate::input::Input;
+use crate::jfxx::{parse_jfxx, App0Jfxx, Thumbnail, JFXX_SIGNATURE};
+use crate::mpf::{parse_mpf, App2Mpf, MpEntry, MPF_SIGNATURE};
+use crate::photoshop::{parse_photoshop, App13Photoshop, PHOTOSHOP_SIGNATURE};
+use crate::warning::{Warning, WarningKind};
+use crate::xmp::{
+    parse_xmp, parse_xmp_extension, App1Xmp, App1XmpExtension, ExtendedXmp, XmpAssembler,
+};
 pub use crate::JfifError;
 
+/// Options of a [Reader]
+#[derive(Debug, Clone, Copy)]
+pub struct ReaderOptions {
+    /// Keep the entropy-coded data of scans and RST markers
+    ///
+    /// If false [Scan::data] and [Rst::data] are empty and only the position and
+    /// length of the data are recorded. The data still has to be read to find the
+    /// next marker, but it isn't copied. Defaults to true.
+    pub retain_scan_data: bool,
+}
+
+impl Default for ReaderOptions {
+    fn default() -> Self {
+        Self {
+            retain_scan_data: true,
+        }
+    }
+}
+
 /// A reader for JFIF files
+///
+/// A reader created with [Reader::new] never reads past the last segment it
+/// returned, so the input can still be used afterwards. Entropy-coded data is
+/// then read one byte at a time, so unbuffered input should be wrapped in a
+/// [BufReader](std::io::BufReader) and passed to [Reader::with_buf_read], or passed to
+/// [Reader::with_seek]. Both scan entropy-coded data for markers in blocks.
 pub struct Reader<R: Read> {
-    reader: R,
+    input: Input<R>,
+    options: ReaderOptions,
     current_marker: Option<u8>,
     position: usize,
+    state: ReaderState,
 }
 
 impl<R: Read> Reader<R> {
     /// Create a new reader
-    pub fn new(mut reader: R) -> Result<Self, JfifError> {
+    pub fn new(reader: R) -> Result<Self, JfifError> {
+        Self::with_options(reader, ReaderOptions::default())
+    }
+
+    /// Create a new reader with options
+    pub fn with_options(reader: R, options: ReaderOptions) -> Result<Self, JfifError> {
+        Self::from_input(Input::new(reader), options)
+    }
+
+    /// Create a reader scanning the buffer of `reader` for markers
+    ///
+    /// Only the buffered bytes after the last returned segment are left unconsumed.
+    pub fn with_buf_read(reader: R, options: ReaderOptions) -> Result<Self, JfifError>
+    where
+        R: BufRead,
+    {
+        Self::from_input(Input::with_buf_read(reader), options)
+    }
+
+    /// Create a reader reading ahead in blocks and seeking over skipped data
+    ///
+    /// Data before embedded images is skipped by seeking. When the reader is
+    /// dropped, `reader` is seeked back to the end of the last returned segment.
+    pub fn with_seek(reader: R, options: ReaderOptions) -> Result<Self, JfifError>
+    where
+        R: Seek,
+    {
+        Self::from_input(Input::with_seek(reader), options)
+    }
+
+    fn from_input(mut input: Input<R>, options: ReaderOptions) -> Result<Self, JfifError> {
         let mut buf = [0u8; 2];
 
-        if reader.read(&mut buf)? != 2 || buf != [0xFF, 0xD8] {
+        match input.read_exact(&mut buf) {
+            Ok(_) => {}
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => {
+                return Err(JfifError::JfifMarkerNotFound)
+            }
+            Err(err) => return Err(err.into()),
+        }
+
+        if buf != [0xFF, 0xD8] {
             return Err(JfifError::JfifMarkerNotFound);
         }
 
         Ok(Self {
-            reader,
+            input,
+            options,
             current_marker: Some(0xD8),
             position: 2,
+            state: ReaderState::default(),
         })
     }
 
+    /// Index of the image the last segment belongs to
+    ///
+    /// The primary image has index 0. Images embedded after its EOI like
+    /// the ones listed in an MPF segment are counted up from 1.
+    pub fn image_index(&self) -> usize {
+        self.state.image_index()
+    }
+
+    /// MP entry of the primary image's MPF segment for the image starting at `position`
+    pub fn mp_entry(&self, position: usize) -> Option<&MpEntry> {
+        self.state.mp_entry(position)
+    }
+
+    /// Extended XMP documents of the primary image reassembled from all chunks read so far
+    pub fn extended_xmp(&self) -> Vec<ExtendedXmp> {
+        self.state.xmp_assembler.finish()
+    }
+
+    /// ICC profile of the primary image reassembled from all chunks read so far
+    pub fn icc_profile(&self) -> Option<IccProfile> {
+        self.state.icc_assembler.finish()
+    }
+
+    /// Problems recovered from in all segments read so far
+    pub fn warnings(&self) -> &[Warning] {
+        &self.state.warnings
+    }
+
     fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), IoError> {
         self.position += buf.len();
-        self.reader.read_exact(buf)
+        self.input.read_exact(buf)
     }
 
     fn read_u8(&mut self) -> Result<u8, IoError> {
@@ -36,11 +142,6 @@ impl<R: Read> Reader<R> {
         Ok(buf[0])
     }
 
-    fn read_u4_tuple(&mut self) -> Result<(u8, u8), IoError> {
-        let v = self.read_u8()?;
-        Ok((v >> 4, v & 0x0F))
-    }
-
     fn read_u16(&mut self) -> Result<u16, IoError> {
         let mut buf = [0u8; 2];
         self.read_exact(&mut buf)?;
@@ -54,21 +155,36 @@ impl<R: Read> Reader<R> {
     }
 
     fn skip(&mut self, length: usize) -> Result<(), IoError> {
-        for _ in 0..length {
-            self.read_u8()?;
-        }
-        Ok(())
+        self.position += length;
+        self.input.skip(length)
     }
 
-    fn read_length(&mut self) -> Result<usize, JfifError> {
-        let length = self.read_u16()? as usize;
+    /// Skip all bytes before the next 0xFF and append them to `data` if given
+    fn skip_to_ff(&mut self, mut data: Option<&mut Vec<u8>>) -> Result<(), IoError> {
+        loop {
+            let buf = self.input.fill_buf()?;
+
+            let (count, found) = match find_ff(buf) {
+                Some(count) => (count, true),
+                None => (buf.len(), false),
+            };
+
+            if let Some(data) = data.as_mut() {
+                data.extend_from_slice(&buf[..count]);
+            }
 
-        // lengths 0 and greater are allowed
-        if length < 2 {
-            return Err(JfifError::InvalidMarkerLength(length));
+            self.input.consume(count);
+            self.position += count;
+
+            if found {
+                return Ok(());
+            }
         }
+    }
 
-        Ok(length - 2)
+    fn read_length(&mut self) -> Result<usize, JfifError> {
+        let length = self.read_u16()? as usize;
+        check_length(length)
     }
 
     /// Read the next segment of the file
@@ -76,7 +192,17 @@ impl<R: Read> Reader<R> {
         let marker = if let Some(marker) = self.current_marker.take() {
             marker
         } else {
-            while self.read_u8()? != 0xFF {}
+            if let Some(next) = self.state.next_image(self.position) {
+                self.skip(next - self.position)?;
+            }
+
+            let start = self.position;
+
+            self.skip_to_ff(None)?;
+
+            self.state.garbage(start, self.position - start);
+
+            self.read_u8()?;
 
             let mut byte = self.read_u8()?;
 
@@ -90,23 +216,16 @@ impl<R: Read> Reader<R> {
 
         match marker {
             0x00 => Err(JfifError::InvalidMarker(0x00)),
-            0xD8 => Ok(SegmentKind::Soi),
-            0xD9 => Ok(SegmentKind::Eoi),
-            0xE0..=0xEF => Ok(self.read_app_segment(marker - 0xE0)?),
-            0xDB => Ok(SegmentKind::Dqt(self.read_dqt()?)),
-            0xC4 => Ok(SegmentKind::Dht(self.read_dht()?)),
-            0xCC => Ok(SegmentKind::Dac(self.read_dac()?)),
-            0xC0..=0xC3 | 0xC5..=0xC7 | 0xC9..=0xCB | 0xCD..=0xCF => {
-                Ok(SegmentKind::Frame(self.read_frame(marker)?))
+            0xD8 => {
+                self.state.start_image();
+                Ok(SegmentKind::Soi)
+            }
+            0xD9 => {
+                self.state.end_image();
+                Ok(SegmentKind::Eoi)
             }
-            0xDA => Ok(SegmentKind::Scan(self.read_scan()?)),
-            0xDD => Ok(SegmentKind::Dri(self.read_dri()?)),
             0xD0..=0xD7 => Ok(SegmentKind::Rst(self.read_rst(marker - 0xD0)?)),
-            0xFE => Ok(SegmentKind::Comment(self.read_segment()?)),
-            marker => Ok(SegmentKind::Unknown {
-                marker,
-                data: self.read_segment()?,
-            }),
+            marker => self.read_segment(marker),
         }
         .map(|kind| Segment {
             kind,
@@ -115,14 +234,302 @@ impl<R: Read> Reader<R> {
         })
     }
 
-    fn read_segment(&mut self) -> Result<Vec<u8>, JfifError> {
+    fn read_segment(&mut self, marker: u8) -> Result<SegmentKind, JfifError> {
         let length = self.read_length()?;
-        Ok(self.read_to_vec(length)?)
+        let data = self.read_to_vec(length)?;
+        let position = self.position - length;
+
+        Ok(match self.state.parse_segment(marker, &data, position)? {
+            ParsedSegment::App(app) => app.into(),
+            ParsedSegment::Dqt(tables) => SegmentKind::Dqt(tables),
+            ParsedSegment::Dht(tables) => SegmentKind::Dht(tables),
+            ParsedSegment::Dac(dac) => SegmentKind::Dac(dac),
+            ParsedSegment::Frame(frame) => SegmentKind::Frame(frame),
+            ParsedSegment::Scan(mut scan) => {
+                let (data, data_position, data_length) = self.read_scan_data()?;
+                scan.data = data;
+                scan.data_position = data_position;
+                scan.data_length = data_length;
+                SegmentKind::Scan(scan)
+            }
+            ParsedSegment::Dri(restart) => SegmentKind::Dri(restart),
+            ParsedSegment::Raw => match marker {
+                0xE0..=0xEF => SegmentKind::App {
+                    nr: marker - 0xE0,
+                    data,
+                },
+                0xFE => SegmentKind::Comment(data),
+                marker => SegmentKind::Unknown { marker, data },
+            },
+        })
     }
 
-    fn read_app_segment(&mut self, nr: u8) -> Result<SegmentKind, JfifError> {
-        let data = self.read_segment()?;
+    /// Read entropy-coded data up to the next marker and return it with its position and length
+    ///
+    /// The data is empty if it isn't retained.
+    #[allow(clippy::same_item_push)]
+    fn read_scan_data(&mut self) -> Result<(Vec<u8>, usize, usize), JfifError> {
+        let start = self.position;
+        let retain = self.options.retain_scan_data;
+        let mut data = vec![];
 
+        let end = loop {
+            self.skip_to_ff(if retain { Some(&mut data) } else { None })?;
+
+            let position = self.position;
+            self.read_u8()?;
+
+            let mut byte = self.read_u8()?;
+            let mut ff_count = 1;
+            // Multiple 0xFF are not standard compliant but supported by libjpeg
+            while byte == 0xFF {
+                ff_count += 1;
+                byte = self.read_u8()?;
+            }
+
+            if byte != 0x00 {
+                self.current_marker = Some(byte);
+                break position;
+            }
+
+            self.state.fill_bytes(position, ff_count - 1);
+
+            if retain {
+                for _ in 0..ff_count {
+                    data.push(0xFF);
+                }
+                data.push(byte);
+            }
+        };
+
+        Ok((data, start, end - start))
+    }
+
+    fn read_rst(&mut self, nr: u8) -> Result<Rst, JfifError> {
+        let (data, data_position, data_length) = self.read_scan_data()?;
+
+        Ok(Rst {
+            nr,
+            data,
+            data_position,
+            data_length,
+        })
+    }
+}
+
+/// Position of the first 0xFF in `data` testing eight bytes at a time
+pub(crate) fn find_ff(data: &[u8]) -> Option<usize> {
+    const LOW: u64 = 0x0101_0101_0101_0101;
+    const HIGH: u64 = 0x8080_8080_8080_8080;
+
+    let mut offset = 0;
+
+    for chunk in data.chunks_exact(8) {
+        // Bytes that are 0xFF are zero after inverting the word
+        let word = !u64::from_le_bytes(chunk.try_into().unwrap());
+
+        if word.wrapping_sub(LOW) & !word & HIGH != 0 {
+            break;
+        }
+
+        offset += 8;
+    }
+
+    data[offset..]
+        .iter()
+        .position(|&b| b == 0xFF)
+        .map(|position| offset + position)
+}
+
+pub(crate) fn check_length(length: usize) -> Result<usize, JfifError> {
+    // lengths 0 and greater are allowed
+    if length < 2 {
+        return Err(JfifError::InvalidMarkerLength(length));
+    }
+
+    Ok(length - 2)
+}
+
+/// Cursor over the content of a segment
+struct Payload<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Payload<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, offset: 0 }
+    }
+
+    fn remaining(&self) -> usize {
+        self.data.len() - self.offset
+    }
+
+    fn bytes(&mut self, length: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.offset..self.offset + length)?;
+        self.offset += length;
+        Some(bytes)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        Some(self.bytes(1)?[0])
+    }
+
+    fn u4_tuple(&mut self) -> Option<(u8, u8)> {
+        let v = self.u8()?;
+        Some((v >> 4, v & 0x0F))
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        let bytes = self.bytes(2)?;
+        Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+}
+
+/// APP segments with a known signature borrowing their payload from the segment data
+pub(crate) enum AppSegment<'a> {
+    Jfif(App0Jfif),
+    Jfxx(App0Jfxx),
+    Exif(App1Exif<&'a [u8]>),
+    Xmp(App1Xmp<&'a str>),
+    XmpExtension(App1XmpExtension<&'a [u8]>),
+    Icc(App2Icc<&'a [u8]>),
+    Mpf(App2Mpf<&'a [u8]>),
+    Photoshop(App13Photoshop<&'a [u8]>),
+    Adobe(App14Adobe),
+}
+
+impl<'a> From<AppSegment<'a>> for SegmentKind {
+    fn from(app: AppSegment<'a>) -> Self {
+        match app {
+            AppSegment::Jfif(jfif) => SegmentKind::App0Jfif(jfif),
+            AppSegment::Jfxx(jfxx) => SegmentKind::App0Jfxx(jfxx),
+            AppSegment::Exif(exif) => SegmentKind::App1Exif(exif.into_owned()),
+            AppSegment::Xmp(xmp) => SegmentKind::App1Xmp(xmp.into_owned()),
+            AppSegment::XmpExtension(extension) => {
+                SegmentKind::App1XmpExtension(extension.into_owned())
+            }
+            AppSegment::Icc(chunk) => SegmentKind::App2Icc(chunk.into_owned()),
+            AppSegment::Mpf(mpf) => SegmentKind::App2Mpf(mpf.into_owned()),
+            AppSegment::Photoshop(photoshop) => SegmentKind::App13Photoshop(photoshop.into_owned()),
+            AppSegment::Adobe(adobe) => SegmentKind::App14Adobe(adobe),
+        }
+    }
+}
+
+/// Content of a segment with a length field
+pub(crate) enum ParsedSegment<'a> {
+    App(AppSegment<'a>),
+    Dqt(Vec<Dqt>),
+    Dht(Vec<Dht>),
+    Dac(Dac),
+    Frame(Frame),
+    /// Scan header without entropy-coded data
+    Scan(Scan),
+    Dri(u16),
+    /// Segment that is kept as raw data
+    Raw,
+}
+
+/// State and segment parsing shared by [Reader] and [crate::SliceReader]
+#[derive(Default)]
+pub(crate) struct ReaderState {
+    pub(crate) xmp_assembler: XmpAssembler,
+    pub(crate) icc_assembler: IccAssembler,
+    image_count: usize,
+    after_eoi: bool,
+    mp_images: Vec<(usize, MpEntry)>,
+    pub(crate) warnings: Vec<Warning>,
+}
+
+impl ReaderState {
+    pub(crate) fn image_index(&self) -> usize {
+        self.image_count.saturating_sub(1)
+    }
+
+    pub(crate) fn mp_entry(&self, position: usize) -> Option<&MpEntry> {
+        self.mp_images
+            .iter()
+            .find(|(image_position, _)| *image_position == position)
+            .map(|(_, entry)| entry)
+    }
+
+    pub(crate) fn start_image(&mut self) {
+        self.image_count += 1;
+    }
+
+    pub(crate) fn end_image(&mut self) {
+        self.after_eoi = true;
+    }
+
+    /// Position of the next image listed in the MPF segment when reading the first marker after an EOI
+    pub(crate) fn next_image(&mut self, position: usize) -> Option<usize> {
+        if !self.after_eoi {
+            return None;
+        }
+
+        self.after_eoi = false;
+
+        self.mp_images
+            .iter()
+            .map(|(image_position, _)| *image_position)
+            .filter(|&image_position| image_position >= position)
+            .min()
+    }
+
+    fn warn(&mut self, position: usize, kind: WarningKind) {
+        self.warnings.push(Warning { position, kind });
+    }
+
+    pub(crate) fn garbage(&mut self, position: usize, count: usize) {
+        if count > 0 {
+            self.warn(position, WarningKind::GarbageBeforeMarker { count });
+        }
+    }
+
+    pub(crate) fn fill_bytes(&mut self, position: usize, count: usize) {
+        if count > 0 {
+            self.warn(position, WarningKind::FillBytesInScanData { count });
+        }
+    }
+
+    /// Report bytes at the end of a segment that aren't part of its content
+    fn surplus(&mut self, marker: u8, payload: &Payload, position: usize) {
+        let count = payload.remaining();
+
+        if count > 0 {
+            self.warn(
+                position + payload.offset,
+                WarningKind::SurplusBytes { marker, count },
+            );
+        }
+    }
+
+    /// Parse the content of a segment starting at `position`
+    pub(crate) fn parse_segment<'a>(
+        &mut self,
+        marker: u8,
+        data: &'a [u8],
+        position: usize,
+    ) -> Result<ParsedSegment<'a>, JfifError> {
+        Ok(match marker {
+            0xE0..=0xEF => match self.parse_app(marker - 0xE0, data, position) {
+                Some(app) => ParsedSegment::App(app),
+                None => ParsedSegment::Raw,
+            },
+            0xDB => ParsedSegment::Dqt(self.parse_dqt(data, position)?),
+            0xC4 => ParsedSegment::Dht(self.parse_dht(data, position)?),
+            0xCC => ParsedSegment::Dac(self.parse_dac(data, position)),
+            0xC0..=0xC3 | 0xC5..=0xC7 | 0xC9..=0xCB | 0xCD..=0xCF => {
+                ParsedSegment::Frame(self.parse_frame(marker, data, position)?)
+            }
+            0xDA => ParsedSegment::Scan(self.parse_scan(data, position)?),
+            0xDD => ParsedSegment::Dri(self.parse_dri(data, position)?),
+            _ => ParsedSegment::Raw,
+        })
+    }
+
+    fn parse_app<'a>(&mut self, nr: u8, data: &'a [u8], position: usize) -> Option<AppSegment<'a>> {
         if nr == 0 && data.len() >= 14 && data.starts_with(b"JFIF\0") {
             let major = data[5];
             let minor = data[6];
@@ -134,13 +541,13 @@ impl<R: Read> Reader<R> {
             let x_thumbnail = data[12];
             let y_thumbnail = data[13];
 
-            let thumbnail = if x_thumbnail > 0 && y_thumbnail > 0 && data.len() > 14 {
-                Some(data[14..].to_vec())
+            let (thumbnail, trailing) = if x_thumbnail > 0 && y_thumbnail > 0 && data.len() > 14 {
+                (Some(data[14..].to_vec()), vec![])
             } else {
-                None
+                (None, data[14..].to_vec())
             };
 
-            return Ok(SegmentKind::App0Jfif(App0Jfif {
+            return Some(AppSegment::Jfif(App0Jfif {
                 major,
                 minor,
                 unit,
@@ -149,59 +556,152 @@ impl<R: Read> Reader<R> {
                 x_thumbnail,
                 y_thumbnail,
                 thumbnail,
+                trailing,
             }));
         }
 
-        Ok(SegmentKind::App { nr, data })
-    }
+        if nr == 0 && data.starts_with(JFXX_SIGNATURE) {
+            if let Some(jfxx) = parse_jfxx(&data[JFXX_SIGNATURE.len()..]) {
+                return Some(AppSegment::Jfxx(jfxx));
+            }
+        }
 
-    fn read_dqt(&mut self) -> Result<Vec<Dqt>, JfifError> {
-        let length = self.read_length()?;
+        let primary = self.image_index() == 0;
 
-        let num_tables = length / 65;
+        if nr == 1 {
+            if let Some(xmp) = parse_xmp(data) {
+                return Some(AppSegment::Xmp(xmp));
+            }
 
-        let remaining = match length.checked_sub(num_tables * 65) {
-            Some(length) => length,
-            None => return Err(JfifError::InvalidDqtSegmentLength(length)),
-        };
+            if let Some(extension) = parse_xmp_extension(data) {
+                if primary {
+                    self.xmp_assembler.add(&extension);
+                }
+                return Some(AppSegment::XmpExtension(extension));
+            }
+        }
 
+        if nr == 2 {
+            if let Some(chunk) = parse_icc_chunk(data) {
+                if primary {
+                    self.icc_assembler.add(&chunk);
+                }
+                return Some(AppSegment::Icc(chunk));
+            }
+
+            if data.starts_with(MPF_SIGNATURE) {
+                let header_position = position + MPF_SIGNATURE.len();
+
+                // Malformed TIFF data is reported as a raw segment
+                if let Ok(mpf) = parse_mpf(&data[MPF_SIGNATURE.len()..], header_position) {
+                    if primary && self.mp_images.is_empty() {
+                        self.mp_images = mpf
+                            .images
+                            .iter()
+                            .filter_map(|entry| Some((entry.position(header_position)?, *entry)))
+                            .collect();
+                    }
+                    return Some(AppSegment::Mpf(mpf));
+                }
+            }
+        }
+
+        if nr == 13 && data.starts_with(PHOTOSHOP_SIGNATURE) {
+            return Some(AppSegment::Photoshop(parse_photoshop(
+                &data[PHOTOSHOP_SIGNATURE.len()..],
+            )));
+        }
+
+        if nr == 14 && data.len() >= 12 && data.starts_with(b"Adobe") {
+            return Some(AppSegment::Adobe(App14Adobe {
+                version: u16::from_be_bytes([data[5], data[6]]),
+                flags0: u16::from_be_bytes([data[7], data[8]]),
+                flags1: u16::from_be_bytes([data[9], data[10]]),
+                transform: data[11],
+                trailing: data[12..].to_vec(),
+            }));
+        }
+
+        if nr == 1 && data.starts_with(b"Exif\0\0") {
+            // Malformed TIFF data is reported as a raw segment
+            if let Ok(exif) = parse_exif(&data[6..]) {
+                return Some(AppSegment::Exif(exif));
+            }
+        }
+
+        None
+    }
+
+    fn parse_dqt(&mut self, data: &[u8], position: usize) -> Result<Vec<Dqt>, JfifError> {
         let mut tables = vec![];
+        let mut offset = 0;
 
-        for _ in 0..num_tables {
-            let (precision, dest) = self.read_u4_tuple()?;
+        while let Some(&value) = data.get(offset) {
+            let precision = value >> 4;
+            let dest = value & 0x0F;
 
-            let mut values = [0u8; 64];
-            self.read_exact(&mut values)?;
+            let table_length = match precision {
+                0 => 64,
+                1 => 128,
+                _ => return Err(JfifError::InvalidDqtPrecision(precision)),
+            };
+
+            // Surplus bytes that can't hold a complete table are ignored
+            let table = match data.get(offset + 1..offset + 1 + table_length) {
+                Some(table) => table,
+                None => {
+                    self.warn(
+                        position + offset,
+                        WarningKind::SurplusBytes {
+                            marker: 0xDB,
+                            count: data.len() - offset,
+                        },
+                    );
+                    break;
+                }
+            };
+
+            let mut values = [0u16; 64];
+
+            if precision == 0 {
+                for (value, &v) in values.iter_mut().zip(table) {
+                    *value = v as u16;
+                }
+            } else {
+                for (value, v) in values.iter_mut().zip(table.chunks_exact(2)) {
+                    *value = u16::from_be_bytes([v[0], v[1]]);
+                }
+            }
 
             tables.push(Dqt {
                 precision,
                 dest,
                 values: Box::new(values),
             });
-        }
 
-        if remaining > 0 {
-            self.skip(remaining)?;
+            offset += 1 + table_length;
         }
 
         Ok(tables)
     }
 
-    fn read_dht(&mut self) -> Result<Vec<Dht>, JfifError> {
-        let length = self.read_length()?;
+    fn parse_dht(&mut self, data: &[u8], position: usize) -> Result<Vec<Dht>, JfifError> {
+        let length = data.len();
+        let mut payload = Payload::new(data);
 
         let mut tables = vec![];
 
-        let mut remaining = length;
-
-        while remaining > 17 {
-            let (class, destination) = self.read_u4_tuple()?;
+        while payload.remaining() > 17 {
+            let (class, destination) = payload.u4_tuple().unwrap();
             let mut code_lengths = [0u8; 16];
-            self.read_exact(&mut code_lengths)?;
+            code_lengths.copy_from_slice(payload.bytes(16).unwrap());
 
             let num_codes = code_lengths.iter().map(|v| *v as usize).sum();
 
-            let values = self.read_to_vec(num_codes)?;
+            let values = match payload.bytes(num_codes) {
+                Some(values) => values.to_vec(),
+                None => return Err(JfifError::InvalidDhtSegmentLength(length)),
+            };
 
             tables.push(Dht {
                 class,
@@ -209,44 +709,43 @@ impl<R: Read> Reader<R> {
                 code_lengths,
                 values,
             });
-
-            remaining = match remaining.checked_sub(17 + num_codes) {
-                Some(length) => length,
-                None => return Err(JfifError::InvalidDhtSegmentLength(length)),
-            }
         }
 
-        if remaining > 0 {
-            self.skip(remaining)?;
-        }
+        self.surplus(0xC4, &payload, position);
 
         Ok(tables)
     }
 
-    fn read_dac(&mut self) -> Result<Dac, JfifError> {
-        let length = self.read_length()?;
+    fn parse_dac(&mut self, data: &[u8], position: usize) -> Dac {
+        let mut payload = Payload::new(data);
 
         let mut params = vec![];
 
-        for _ in 0..(length / 2) {
-            let (class, dest) = self.read_u4_tuple()?;
-            let value = self.read_u8()?;
+        while payload.remaining() >= 2 {
+            let (class, dest) = payload.u4_tuple().unwrap();
+            let value = payload.u8().unwrap();
 
             params.push(DacParam { class, dest, value })
         }
 
-        Ok(Dac { params })
+        self.surplus(0xCC, &payload, position);
+
+        Dac { params }
     }
 
-    fn read_scan(&mut self) -> Result<Scan, JfifError> {
-        let length = self.read_length()?;
-        let num_components = self.read_u8()?;
+    fn parse_scan(&mut self, data: &[u8], position: usize) -> Result<Scan, JfifError> {
+        let length = data.len();
+        let mut payload = Payload::new(data);
+
+        let invalid = || JfifError::InvalidScanHeaderLength(length);
+
+        let num_components = payload.u8().ok_or_else(invalid)?;
 
         let mut components = vec![];
 
         for _ in 0..num_components {
-            let id = self.read_u8()?;
-            let (dc_table, ac_table) = self.read_u4_tuple()?;
+            let id = payload.u8().ok_or_else(invalid)?;
+            let (dc_table, ac_table) = payload.u4_tuple().ok_or_else(invalid)?;
 
             components.push(ScanComponent {
                 id,
@@ -255,20 +754,11 @@ impl<R: Read> Reader<R> {
             })
         }
 
-        let selection_start = self.read_u8()?;
-        let selection_end = self.read_u8()?;
-        let (approximation_low, approximation_high) = self.read_u4_tuple()?;
-
-        let remaining = match length.checked_sub(1 + num_components as usize * 2 + 3) {
-            Some(length) => length,
-            None => return Err(JfifError::InvalidScanHeaderLength(length)),
-        };
+        let selection_start = payload.u8().ok_or_else(invalid)?;
+        let selection_end = payload.u8().ok_or_else(invalid)?;
+        let (approximation_low, approximation_high) = payload.u4_tuple().ok_or_else(invalid)?;
 
-        if remaining > 0 {
-            self.skip(remaining)?;
-        }
-
-        let data = self.read_scan_data()?;
+        self.surplus(0xDA, &payload, position);
 
         Ok(Scan {
             components,
@@ -276,77 +766,43 @@ impl<R: Read> Reader<R> {
             selection_end,
             approximation_low,
             approximation_high,
-            data,
+            data: vec![],
+            data_position: 0,
+            data_length: 0,
         })
     }
 
-    #[allow(clippy::same_item_push)]
-    fn read_scan_data(&mut self) -> Result<Vec<u8>, JfifError> {
-        let mut data = vec![];
-
-        loop {
-            let byte = self.read_u8()?;
-            if byte == 0xFF {
-                let mut byte = self.read_u8()?;
-                let mut ff_count = 1;
-                // Multiple 0xFF are not standard compliant but supported by libjpeg
-                while byte == 0xFF {
-                    ff_count += 1;
-                    byte = self.read_u8()?;
-                }
-
-                if byte != 0x00 {
-                    self.current_marker = Some(byte);
-                    break;
-                } else {
-                    for _ in 0..ff_count {
-                        data.push(0xFF);
-                    }
-                    data.push(byte);
-                }
-            } else {
-                data.push(byte);
-            }
-        }
-        Ok(data)
-    }
-
-    fn read_rst(&mut self, nr: u8) -> Result<Rst, JfifError> {
-        let data = self.read_scan_data()?;
-        Ok(Rst { nr, data })
-    }
-
-    fn read_dri(&mut self) -> Result<u16, JfifError> {
-        let length = self.read_length()?;
-        let restart = self.read_u16()?;
+    fn parse_dri(&mut self, data: &[u8], position: usize) -> Result<u16, JfifError> {
+        let mut payload = Payload::new(data);
 
-        let remaining = match length.checked_sub(2) {
-            Some(length) => length,
-            None => return Err(JfifError::InvalidDriLength(length)),
-        };
+        let restart = payload
+            .u16()
+            .ok_or(JfifError::InvalidDriLength(data.len()))?;
 
-        if remaining > 0 {
-            self.skip(remaining)?;
-        }
+        self.surplus(0xDD, &payload, position);
 
         Ok(restart)
     }
 
-    fn read_frame(&mut self, sof: u8) -> Result<Frame, JfifError> {
-        let length = self.read_length()?;
+    fn parse_frame(&mut self, sof: u8, data: &[u8], position: usize) -> Result<Frame, JfifError> {
+        let length = data.len();
+        let mut payload = Payload::new(data);
 
-        let precision = self.read_u8()?;
-        let dimension_y = self.read_u16()?;
-        let dimension_x = self.read_u16()?;
+        let invalid = || JfifError::InvalidFrameSegmentLength(length);
 
-        let num_components = self.read_u8()?;
+        let precision = payload.u8().ok_or_else(invalid)?;
+        let dimension_y = payload.u16().ok_or_else(invalid)?;
+        let dimension_x = payload.u16().ok_or_else(invalid)?;
+
+        let num_components = payload.u8().ok_or_else(invalid)?;
 
         let mut components = vec![];
 
         for _ in 0..num_components {
-            let id = self.read_u8()?;
-            let (horizontal_sampling_factor, vertical_sampling_factor) = self.read_u4_tuple()?;
-            let quantization_table = self.read_u8()?;
+            let id = payload.u8().ok_or_else(invalid)?;
+            let (horizontal_sampling_factor, vertical_sampling_factor) =
+                payload.u4_tuple().ok_or_else(invalid)?;
+            let quantization_table = payload.u8().ok_or_else(invalid)?;
 
             components.push(FrameComponent {
                 id,
@@ -356,14 +812,7 @@ impl<R: Read> Reader<R> {
             })
         }
 
-        let remaining = match length.checked_sub(6 + num_components as usize * 3) {
-            Some(length) => length,
-            None => return Err(JfifError::InvalidFrameSegmentLength(length)),
-        };
-
-        if remaining > 0 {
-            self.skip(remaining)?;
-        }
+        self.surplus(sof, &payload, position);
 
         Ok(Frame {
             sof,
@@ -381,6 +830,14 @@ pub enum SegmentKind {
     Eoi,
     App { nr: u8, data: Vec<u8> },
     App0Jfif(App0Jfif),
+    App0Jfxx(App0Jfxx),
+    App1Exif(App1Exif),
+    App1Xmp(App1Xmp),
+    App1XmpExtension(App1XmpExtension),
+    App2Icc(App2Icc),
+    App2Mpf(App2Mpf),
+    App13Photoshop(App13Photoshop),
+    App14Adobe(App14Adobe),
     Dqt(Vec<Dqt>),
     Dht(Vec<Dht>),
     Dac(Dac),
@@ -392,6 +849,7 @@ pub enum SegmentKind {
     Unknown { marker: u8, data: Vec<u8> },
 }
 
+#[derive(Debug)]
 pub struct Segment {
     pub kind: SegmentKind,
 
@@ -412,13 +870,42 @@ pub struct App0Jfif {
     pub x_thumbnail: u8,
     pub y_thumbnail: u8,
     pub thumbnail: Option<Vec<u8>>,
+    /// Bytes after the header that don't belong to a thumbnail
+    pub trailing: Vec<u8>,
 }
 
-#[derive(Debug)]
+impl App0Jfif {
+    /// Uncompressed RGB thumbnail if the dimensions are not zero
+    pub fn rgb_thumbnail(&self) -> Option<Thumbnail> {
+        if self.x_thumbnail > 0 && self.y_thumbnail > 0 {
+            Some(Thumbnail::Rgb {
+                width: self.x_thumbnail,
+                height: self.y_thumbnail,
+                pixels: self.thumbnail.clone().unwrap_or_default(),
+            })
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct App14Adobe {
+    pub version: u16,
+    pub flags0: u16,
+    pub flags1: u16,
+    /// 0: RGB or CMYK, 1: YCbCr, 2: YCCK
+    pub transform: u8,
+    /// Bytes after the transform flag
+    pub trailing: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
 pub struct Dqt {
+    /// 0 for 8 bit and 1 for 16 bit values
     pub precision: u8,
     pub dest: u8,
-    pub values: Box<[u8; 64]>,
+    pub values: Box<[u16; 64]>,
 }
 
 #[derive(Debug)]
@@ -456,15 +943,23 @@ pub struct Scan {
     pub approximation_low: u8,
     pub approximation_high: u8,
     pub data: Vec<u8>,
+    /// Position of the entropy-coded data
+    pub data_position: usize,
+    /// Length of the entropy-coded data including stuffed zero bytes
+    pub data_length: usize,
 }
 
 #[derive(Debug)]
 pub struct Rst {
     pub nr: u8,
     pub data: Vec<u8>,
+    /// Position of the entropy-coded data
+    pub data_position: usize,
+    /// Length of the entropy-coded data including stuffed zero bytes
+    pub data_length: usize,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct FrameComponent {
     pub id: u8,
     pub horizontal_sampling_factor: u8,
@@ -472,7 +967,7 @@ pub struct FrameComponent {
     pub quantization_table: u8,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Frame {
     pub sof: u8,
     pub precision: u8,
@@ -500,4 +995,62 @@ impl Frame {
             _ => "Unknown",
         }
     }
+
+    /// Determine the color space of the encoded image like libjpeg does
+    ///
+    /// `jfif` has to be true if the file contains an APP0 JFIF segment.
+    pub fn color_space(&self, jfif: bool, adobe: Option<&App14Adobe>) -> ColorSpace {
+        match self.components.len() {
+            1 => ColorSpace::Grayscale,
+            3 => {
+                if jfif {
+                    return ColorSpace::YCbCr;
+                }
+
+                if let Some(adobe) = adobe {
+                    return match adobe.transform {
+                        0 => ColorSpace::Rgb,
+                        _ => ColorSpace::YCbCr,
+                    };
+                }
+
+                let ids: Vec<u8> = self.components.iter().map(|c| c.id).collect();
+
+                if ids == b"RGB" {
+                    ColorSpace::Rgb
+                } else {
+                    ColorSpace::YCbCr
+                }
+            }
+            4 => match adobe {
+                Some(App14Adobe { transform: 2, .. }) => ColorSpace::Ycck,
+                Some(App14Adobe { transform: 0, .. }) | None => ColorSpace::Cmyk,
+                Some(_) => ColorSpace::Ycck,
+            },
+            _ => ColorSpace::Unknown,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorSpace {
+    Grayscale,
+    YCbCr,
+    Rgb,
+    Cmyk,
+    Ycck,
+    Unknown,
+}
+
+impl ColorSpace {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ColorSpace::Grayscale => "Grayscale",
+            ColorSpace::YCbCr => "YCbCr",
+            ColorSpace::Rgb => "RGB",
+            ColorSpace::Cmyk => "CMYK",
+            ColorSpace::Ycck => "YCCK",
+            ColorSpace::Unknown => "Unknown",
+        }
+    }
 }
@@ -0,0 +1,335 @@
+//! Reader borrowing segment data from a byte slice
+
+use std::io::{Error as IoError, ErrorKind};
+use std::ops::Range;
+
+use crate::reader::{check_length, find_ff, AppSegment, ParsedSegment, ReaderState};
+use crate::{
+    App0Jfif, App0Jfxx, App13Photoshop, App14Adobe, App1Exif, App1Xmp, App1XmpExtension, App2Icc,
+    App2Mpf, Dac, Dht, Dqt, ExtendedXmp, Frame, IccProfile, JfifError, MpEntry, ScanComponent,
+    Warning,
+};
+
+/// A reader for JFIF files in memory
+///
+/// Unlike [crate::Reader] raw segment data, payloads of APP segments like EXIF, XMP
+/// or ICC chunks and entropy-coded data aren't copied but borrowed from the input.
+/// Segments with a known structure are parsed like the streaming reader does.
+pub struct SliceReader<'a> {
+    data: &'a [u8],
+    current_marker: Option<u8>,
+    position: usize,
+    state: ReaderState,
+}
+
+#[derive(Debug)]
+pub enum SliceSegmentKind<'a> {
+    Soi,
+    Eoi,
+    App { nr: u8, data: &'a [u8] },
+    App0Jfif(App0Jfif),
+    App0Jfxx(App0Jfxx),
+    App1Exif(App1Exif<&'a [u8]>),
+    App1Xmp(App1Xmp<&'a str>),
+    App1XmpExtension(App1XmpExtension<&'a [u8]>),
+    App2Icc(App2Icc<&'a [u8]>),
+    App2Mpf(App2Mpf<&'a [u8]>),
+    App13Photoshop(App13Photoshop<&'a [u8]>),
+    App14Adobe(App14Adobe),
+    Dqt(Vec<Dqt>),
+    Dht(Vec<Dht>),
+    Dac(Dac),
+    Frame(Frame),
+    Scan(SliceScan<'a>),
+    Dri(u16),
+    Rst(SliceRst<'a>),
+    Comment(&'a [u8]),
+    Unknown { marker: u8, data: &'a [u8] },
+}
+
+#[derive(Debug)]
+pub struct SliceSegment<'a> {
+    pub kind: SliceSegmentKind<'a>,
+
+    // Position of the segment including trailing marker
+    pub position: usize,
+
+    // Length of the segment including trailing marker and without padding bytes
+    pub length: usize,
+}
+
+#[derive(Debug)]
+pub struct SliceScan<'a> {
+    pub components: Vec<ScanComponent>,
+    pub selection_start: u8,
+    pub selection_end: u8,
+    pub approximation_low: u8,
+    pub approximation_high: u8,
+    pub data: EntropyData<'a>,
+}
+
+#[derive(Debug)]
+pub struct SliceRst<'a> {
+    pub nr: u8,
+    pub data: EntropyData<'a>,
+}
+
+/// Entropy-coded data following a scan header or RST marker
+#[derive(Debug, Clone)]
+pub struct EntropyData<'a> {
+    input: &'a [u8],
+    range: Range<usize>,
+}
+
+impl<'a> EntropyData<'a> {
+    /// Position of the data in the input
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+
+    /// Data including stuffed zero bytes like [crate::Scan::data]
+    pub fn raw(&self) -> &'a [u8] {
+        &self.input[self.range.clone()]
+    }
+
+    /// Data without stuffed zero bytes and fill bytes
+    pub fn bytes(&self) -> Destuffed<'a> {
+        Destuffed {
+            data: self.raw(),
+            offset: 0,
+        }
+    }
+}
+
+/// Iterator over entropy-coded data removing the zero byte after every 0xFF
+#[derive(Debug, Clone)]
+pub struct Destuffed<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Iterator for Destuffed<'a> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        let byte = *self.data.get(self.offset)?;
+        self.offset += 1;
+
+        if byte == 0xFF {
+            // Fill bytes and the stuffed zero byte
+            while let Some(0xFF | 0x00) = self.data.get(self.offset) {
+                let next = self.data[self.offset];
+                self.offset += 1;
+
+                if next == 0x00 {
+                    break;
+                }
+            }
+        }
+
+        Some(byte)
+    }
+}
+
+impl<'a> SliceReader<'a> {
+    /// Create a new reader
+    pub fn new(data: &'a [u8]) -> Result<Self, JfifError> {
+        if !data.starts_with(&[0xFF, 0xD8]) {
+            return Err(JfifError::JfifMarkerNotFound);
+        }
+
+        Ok(Self {
+            data,
+            current_marker: Some(0xD8),
+            position: 2,
+            state: ReaderState::default(),
+        })
+    }
+
+    /// Index of the image the last segment belongs to
+    ///
+    /// The primary image has index 0. Images embedded after its EOI like
+    /// the ones listed in an MPF segment are counted up from 1.
+    pub fn image_index(&self) -> usize {
+        self.state.image_index()
+    }
+
+    /// MP entry of the primary image's MPF segment for the image starting at `position`
+    pub fn mp_entry(&self, position: usize) -> Option<&MpEntry> {
+        self.state.mp_entry(position)
+    }
+
+    /// Extended XMP documents of the primary image reassembled from all chunks read so far
+    pub fn extended_xmp(&self) -> Vec<ExtendedXmp> {
+        self.state.xmp_assembler.finish()
+    }
+
+    /// ICC profile of the primary image reassembled from all chunks read so far
+    pub fn icc_profile(&self) -> Option<IccProfile> {
+        self.state.icc_assembler.finish()
+    }
+
+    /// Problems recovered from in all segments read so far
+    pub fn warnings(&self) -> &[Warning] {
+        &self.state.warnings
+    }
+
+    fn read_bytes(&mut self, length: usize) -> Result<&'a [u8], IoError> {
+        // Like the streaming reader the position is advanced past the end of the input
+        let start = self.position;
+        self.position += length;
+
+        let data: &'a [u8] = self.data;
+
+        data.get(start..self.position)
+            .ok_or_else(|| IoError::from(ErrorKind::UnexpectedEof))
+    }
+
+    fn read_u8(&mut self) -> Result<u8, IoError> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    fn read_u16(&mut self) -> Result<u16, IoError> {
+        let bytes = self.read_bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    /// Skip to the byte after the next 0xFF
+    fn skip_to_ff(&mut self) -> Result<(), IoError> {
+        let rest = self.data.get(self.position..).unwrap_or_default();
+
+        match find_ff(rest) {
+            Some(offset) => {
+                self.position += offset + 1;
+                Ok(())
+            }
+            None => {
+                self.position = self.data.len();
+                Err(IoError::from(ErrorKind::UnexpectedEof))
+            }
+        }
+    }
+
+    /// Read the next segment of the input
+    pub fn next_segment(&mut self) -> Result<SliceSegment<'a>, JfifError> {
+        let marker = if let Some(marker) = self.current_marker.take() {
+            marker
+        } else {
+            if let Some(next) = self.state.next_image(self.position) {
+                self.position = next;
+            }
+
+            let start = self.position;
+
+            self.skip_to_ff()?;
+
+            self.state.garbage(start, self.position - start - 1);
+
+            let mut byte = self.read_u8()?;
+
+            while byte == 0xFF {
+                byte = self.read_u8()?;
+            }
+            byte
+        };
+
+        let position = self.position - 2;
+
+        match marker {
+            0x00 => Err(JfifError::InvalidMarker(0x00)),
+            0xD8 => {
+                self.state.start_image();
+                Ok(SliceSegmentKind::Soi)
+            }
+            0xD9 => {
+                self.state.end_image();
+                Ok(SliceSegmentKind::Eoi)
+            }
+            0xD0..=0xD7 => Ok(SliceSegmentKind::Rst(SliceRst {
+                nr: marker - 0xD0,
+                data: self.read_scan_data()?,
+            })),
+            marker => self.read_segment(marker),
+        }
+        .map(|kind| SliceSegment {
+            kind,
+            position,
+            length: self.position - position,
+        })
+    }
+
+    fn read_segment(&mut self, marker: u8) -> Result<SliceSegmentKind<'a>, JfifError> {
+        let length = check_length(self.read_u16()? as usize)?;
+        let data = self.read_bytes(length)?;
+        let position = self.position - length;
+
+        Ok(match self.state.parse_segment(marker, data, position)? {
+            ParsedSegment::App(app) => app.into(),
+            ParsedSegment::Dqt(tables) => SliceSegmentKind::Dqt(tables),
+            ParsedSegment::Dht(tables) => SliceSegmentKind::Dht(tables),
+            ParsedSegment::Dac(dac) => SliceSegmentKind::Dac(dac),
+            ParsedSegment::Frame(frame) => SliceSegmentKind::Frame(frame),
+            ParsedSegment::Scan(scan) => SliceSegmentKind::Scan(SliceScan {
+                components: scan.components,
+                selection_start: scan.selection_start,
+                selection_end: scan.selection_end,
+                approximation_low: scan.approximation_low,
+                approximation_high: scan.approximation_high,
+                data: self.read_scan_data()?,
+            }),
+            ParsedSegment::Dri(restart) => SliceSegmentKind::Dri(restart),
+            ParsedSegment::Raw => match marker {
+                0xE0..=0xEF => SliceSegmentKind::App {
+                    nr: marker - 0xE0,
+                    data,
+                },
+                0xFE => SliceSegmentKind::Comment(data),
+                marker => SliceSegmentKind::Unknown { marker, data },
+            },
+        })
+    }
+
+    fn read_scan_data(&mut self) -> Result<EntropyData<'a>, JfifError> {
+        let start = self.position;
+
+        loop {
+            self.skip_to_ff()?;
+
+            let end = self.position - 1;
+            let mut byte = self.read_u8()?;
+            let mut ff_count = 1;
+            // Multiple 0xFF are not standard compliant but supported by libjpeg
+            while byte == 0xFF {
+                ff_count += 1;
+                byte = self.read_u8()?;
+            }
+
+            if byte != 0x00 {
+                self.current_marker = Some(byte);
+
+                return Ok(EntropyData {
+                    input: self.data,
+                    range: start..end,
+                });
+            }
+
+            self.state.fill_bytes(end, ff_count - 1);
+        }
+    }
+}
+
+impl<'a> From<AppSegment<'a>> for SliceSegmentKind<'a> {
+    fn from(app: AppSegment<'a>) -> Self {
+        match app {
+            AppSegment::Jfif(jfif) => SliceSegmentKind::App0Jfif(jfif),
+            AppSegment::Jfxx(jfxx) => SliceSegmentKind::App0Jfxx(jfxx),
+            AppSegment::Exif(exif) => SliceSegmentKind::App1Exif(exif),
+            AppSegment::Xmp(xmp) => SliceSegmentKind::App1Xmp(xmp),
+            AppSegment::XmpExtension(extension) => SliceSegmentKind::App1XmpExtension(extension),
+            AppSegment::Icc(chunk) => SliceSegmentKind::App2Icc(chunk),
+            AppSegment::Mpf(mpf) => SliceSegmentKind::App2Mpf(mpf),
+            AppSegment::Photoshop(photoshop) => SliceSegmentKind::App13Photoshop(photoshop),
+            AppSegment::Adobe(adobe) => SliceSegmentKind::App14Adobe(adobe),
+        }
+    }
+}
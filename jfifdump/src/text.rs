@@ -1,12 +1,39 @@
-use crate::{App0Jfif, Dac, Dht, Dqt, Frame, Handler, Rst, Scan};
+use crate::quality::{frame_tables, update_tables};
+use crate::{
+    estimate_quality, estimate_table_quality, signature_string, App0Jfif, App0Jfxx, App13Photoshop,
+    App14Adobe, App1Exif, App1Xmp, App1XmpExtension, App2Icc, App2Mpf, Dac, Dht, Dqt, EncoderMatch,
+    ExtendedXmp, Frame, Handler, IccProfile, Ifd, MpEntry, Rst, Scan, Thumbnail, Warning,
+};
 
 pub struct TextFormat {
     verbose: bool,
+    jfif: bool,
+    adobe: Option<App14Adobe>,
+    quantization_tables: Vec<Dqt>,
+    frame: Option<Frame>,
 }
 
 impl TextFormat {
     pub fn new(verbose: bool) -> TextFormat {
-        TextFormat { verbose }
+        TextFormat {
+            verbose,
+            jfif: false,
+            adobe: None,
+            quantization_tables: vec![],
+            frame: None,
+        }
+    }
+}
+
+fn print_thumbnail_size(thumbnail: &Thumbnail) {
+    if let Some(expected) = thumbnail.expected_size() {
+        if !thumbnail.is_valid() {
+            println!(
+                "  Invalid thumbnail size: {} bytes, expected {}",
+                thumbnail.size(),
+                expected
+            );
+        }
     }
 }
 
@@ -18,6 +45,30 @@ fn print_ascii_value(v: u8) {
     }
 }
 
+impl TextFormat {
+    fn print_ifd(&self, ifd: &Ifd, indent: usize) {
+        println!("{:indent$}{}:", "", ifd.kind.name(), indent = indent);
+
+        let max = if self.verbose { usize::MAX } else { 8 };
+
+        for entry in &ifd.entries {
+            println!(
+                "{:indent$}  0x{:04X} {} ({}): {}",
+                "",
+                entry.tag,
+                entry.tag_name(ifd.kind).unwrap_or("Unknown"),
+                entry.value.type_name(),
+                entry.value.format(max),
+                indent = indent
+            );
+        }
+
+        for sub_ifd in &ifd.sub_ifds {
+            self.print_ifd(sub_ifd, indent + 2);
+        }
+    }
+}
+
 impl Handler for TextFormat {
     fn handle_app(&mut self, position: usize, length: usize, nr: u8, data: &[u8]) {
         if self.verbose {
@@ -38,6 +89,8 @@ impl Handler for TextFormat {
         }
         println!("App(0x0): JFIF");
 
+        self.jfif = true;
+
         println!("  Version: {}.{:02}", jfif.major, jfif.minor);
 
         let unit = match jfif.unit {
@@ -49,6 +102,239 @@ impl Handler for TextFormat {
 
         println!("  Density: {}x{} {}", jfif.x_density, jfif.y_density, unit);
         println!("  Thumbnail: {}x{}", jfif.x_thumbnail, jfif.y_thumbnail);
+
+        if let Some(thumbnail) = jfif.rgb_thumbnail() {
+            print_thumbnail_size(&thumbnail);
+        }
+    }
+
+    fn handle_app0_jfxx(&mut self, position: usize, length: usize, jfxx: &App0Jfxx) {
+        if self.verbose {
+            print!("0x{:X}/0x{:X}: ", position, length);
+        }
+        println!("App(0x0): JFXX");
+        println!("  Extension code: 0x{:X}", jfxx.extension_code);
+
+        match &jfxx.thumbnail {
+            Some(Thumbnail::Jpeg(data)) => println!("  Thumbnail: JPEG {} bytes", data.len()),
+            Some(
+                thumbnail @ Thumbnail::Palette { width, height, .. }
+                | thumbnail @ Thumbnail::Rgb { width, height, .. },
+            ) => {
+                println!("  Thumbnail: {} {}x{}", thumbnail.name(), width, height);
+                print_thumbnail_size(thumbnail);
+            }
+            None => println!("  Unknown extension: {} bytes", jfxx.data.len()),
+        }
+    }
+
+    fn handle_app1_exif(&mut self, position: usize, length: usize, exif: &App1Exif) {
+        if self.verbose {
+            print!("0x{:X}/0x{:X}: ", position, length);
+        }
+        println!("App(0x1): Exif");
+        println!("  Byte order: {}", exif.byte_order.name());
+
+        for ifd in &exif.ifds {
+            self.print_ifd(ifd, 2);
+        }
+    }
+
+    fn handle_app1_xmp(&mut self, position: usize, length: usize, xmp: &App1Xmp) {
+        if self.verbose {
+            print!("0x{:X}/0x{:X}: ", position, length);
+        }
+        println!("App(0x1): XMP");
+        println!("  Packet: {} bytes", xmp.packet.len());
+
+        if let Some(guid) = xmp.extended_guid() {
+            println!("  Extended XMP: {}", guid);
+        }
+
+        if self.verbose {
+            for line in xmp.packet.lines() {
+                println!("    {}", line);
+            }
+        }
+    }
+
+    fn handle_app1_xmp_extension(
+        &mut self,
+        position: usize,
+        length: usize,
+        extension: &App1XmpExtension,
+    ) {
+        if self.verbose {
+            print!("0x{:X}/0x{:X}: ", position, length);
+        }
+        println!("App(0x1): Extended XMP");
+        println!("  GUID: {}", extension.guid);
+        println!(
+            "  Chunk: {} bytes at offset {} of {}",
+            extension.data.len(),
+            extension.offset,
+            extension.full_length
+        );
+    }
+
+    fn handle_extended_xmp(&mut self, xmp: &ExtendedXmp) {
+        println!("Extended XMP: {}", xmp.guid);
+        println!("  Length: {} of {} bytes", xmp.data.len(), xmp.full_length);
+        println!("  Complete: {}", xmp.complete);
+        println!("  Checksum valid: {}", xmp.checksum_valid);
+
+        if self.verbose {
+            match xmp.document() {
+                Some(document) => {
+                    for line in document.lines() {
+                        println!("    {}", line);
+                    }
+                }
+                None => println!("  BAD STRING WITH LENGTH {}", xmp.data.len()),
+            }
+        }
+    }
+
+    fn handle_app2_icc(&mut self, position: usize, length: usize, chunk: &App2Icc) {
+        if self.verbose {
+            print!("0x{:X}/0x{:X}: ", position, length);
+        }
+        println!(
+            "App(0x2): ICC Profile chunk {} of {} ({} bytes)",
+            chunk.sequence,
+            chunk.count,
+            chunk.data.len()
+        );
+    }
+
+    fn handle_icc_profile(&mut self, profile: &IccProfile) {
+        println!("ICC Profile: {} bytes", profile.data.len());
+
+        if let Some(header) = &profile.header {
+            println!(
+                "  Version: {}.{}.{}",
+                header.version_major, header.version_minor, header.version_bugfix
+            );
+            println!(
+                "  Class: {} ({})",
+                header.class_name(),
+                signature_string(&header.class)
+            );
+            println!("  Color space: {}", signature_string(&header.color_space));
+            println!("  PCS: {}", signature_string(&header.pcs));
+            println!("  Rendering intent: {}", header.rendering_intent_name());
+
+            if let Some(description) = &header.description {
+                println!("  Description: {}", description);
+            }
+
+            if self.verbose {
+                println!("  CMM: {}", signature_string(&header.cmm));
+                println!("  Header size: {}", header.size);
+            }
+        }
+
+        for issue in &profile.issues {
+            println!("  Issue: {}", issue);
+        }
+    }
+
+    fn handle_app2_mpf(&mut self, position: usize, length: usize, mpf: &App2Mpf) {
+        if self.verbose {
+            print!("0x{:X}/0x{:X}: ", position, length);
+        }
+        println!("App(0x2): MPF");
+        println!("  Byte order: {}", mpf.byte_order.name());
+
+        for (i, image) in mpf.images.iter().enumerate() {
+            println!(
+                "  Image {}: {} Size: {} Offset: 0x{:X}",
+                i,
+                image.image_type_name(),
+                image.size,
+                image.offset
+            );
+        }
+
+        if self.verbose {
+            for ifd in &mpf.ifds {
+                self.print_ifd(ifd, 2);
+            }
+        }
+    }
+
+    fn handle_app13_photoshop(
+        &mut self,
+        position: usize,
+        length: usize,
+        photoshop: &App13Photoshop,
+    ) {
+        if self.verbose {
+            print!("0x{:X}/0x{:X}: ", position, length);
+        }
+        println!("App(0xD): Photoshop");
+
+        for resource in &photoshop.resources {
+            print!(
+                "  {} 0x{:04X} {}",
+                signature_string(&resource.signature),
+                resource.id,
+                resource.id_name()
+            );
+            if !resource.name.is_empty() {
+                print!(" \"{}\"", resource.name_string());
+            }
+            println!(": {} bytes", resource.data.len());
+
+            if let Some(iptc) = &resource.iptc {
+                for dataset in &iptc.datasets {
+                    print!(
+                        "    {}:{:03} {}: ",
+                        dataset.record,
+                        dataset.dataset,
+                        dataset.name()
+                    );
+                    if let Some(number) = dataset.number() {
+                        println!("{}", number);
+                    } else if dataset.is_binary() {
+                        println!("{} bytes", dataset.data.len());
+                    } else {
+                        let text: String = iptc
+                            .text(dataset)
+                            .chars()
+                            .flat_map(|c| {
+                                if c.is_control() {
+                                    c.escape_default().collect::<Vec<_>>()
+                                } else {
+                                    vec![c]
+                                }
+                            })
+                            .collect();
+                        println!("{}", text);
+                    }
+                }
+            }
+        }
+    }
+
+    fn handle_app14_adobe(&mut self, position: usize, length: usize, adobe: &App14Adobe) {
+        if self.verbose {
+            print!("0x{:X}/0x{:X}: ", position, length);
+        }
+        println!("App(0xE): Adobe");
+        println!("  Version: {}", adobe.version);
+        println!("  Flags: 0x{:04X} 0x{:04X}", adobe.flags0, adobe.flags1);
+
+        let transform = match adobe.transform {
+            0 => "RGB/CMYK",
+            1 => "YCbCr",
+            2 => "YCCK",
+            _ => "Unknown",
+        };
+
+        println!("  Transform: {} ({})", adobe.transform, transform);
+
+        self.adobe = Some(adobe.clone());
     }
 
     fn handle_dqt(&mut self, position: usize, length: usize, tables: &[Dqt]) {
@@ -58,23 +344,26 @@ impl Handler for TextFormat {
         println!("DQT:");
 
         for table in tables {
-            print!("  {}: Precision {}", table.dest, table.precision);
+            print!(
+                "  {}: Precision {} Quality: {}",
+                table.dest,
+                table.precision,
+                estimate_table_quality(table)
+            );
             if self.verbose {
+                let width = if table.precision == 0 { 3 } else { 5 };
+
                 for (i, &v) in table.values.iter().enumerate() {
                     if i % 8 == 0 {
                         print!("\n    ");
                     }
-                    if v < 10 {
-                        print!(" ");
-                    }
-                    if v < 100 {
-                        print!(" ");
-                    }
-                    print!("{}, ", v)
+                    print!("{:>width$}, ", v, width = width)
                 }
             }
             println!();
         }
+
+        update_tables(&mut self.quantization_tables, tables);
     }
 
     fn handle_dht(&mut self, position: usize, length: usize, tables: &[Dht]) {
@@ -94,6 +383,15 @@ impl Handler for TextFormat {
                     print!("{}", v)
                 }
                 println!();
+
+                println!("    Codes:");
+                for code in table.codes() {
+                    println!("      0x{:02X}: {}", code.value, code.bits());
+                }
+            }
+
+            for issue in table.issues(self.frame.as_ref()) {
+                println!("    Issue: {}", issue);
             }
         }
     }
@@ -119,6 +417,14 @@ impl Handler for TextFormat {
         println!("Frame: {}", frame.get_sof_name());
         println!("  Precision: {}", frame.precision);
         println!("  Dimension: {}x{}", frame.dimension_x, frame.dimension_y);
+        println!(
+            "  Color space: {}",
+            frame.color_space(self.jfif, self.adobe.as_ref()).name()
+        );
+
+        if let Some(quality) = estimate_quality(&frame_tables(&self.quantization_tables, frame)) {
+            println!("  Quality: {}", quality);
+        }
 
         for component in &frame.components {
             println!(
@@ -129,6 +435,8 @@ impl Handler for TextFormat {
                 component.quantization_table
             );
         }
+
+        self.frame = Some(frame.clone());
     }
 
     fn handle_scan(&mut self, position: usize, length: usize, scan: &Scan) {
@@ -152,7 +460,7 @@ impl Handler for TextFormat {
             "  Approximation: {} to {}",
             scan.approximation_low, scan.approximation_high
         );
-        println!("  Data: {} bytes", scan.data.len());
+        println!("  Data: {} bytes", scan.data_length);
     }
 
     fn handle_dri(&mut self, position: usize, length: usize, restart: u16) {
@@ -166,7 +474,7 @@ impl Handler for TextFormat {
         if self.verbose {
             print!("0x{:X}/0x{:X}: ", position, length);
         }
-        println!("RST({}): Data: {} bytes", restart.nr, restart.data.len());
+        println!("RST({}): Data: {} bytes", restart.nr, restart.data_length);
     }
 
     fn handle_comment(&mut self, position: usize, length: usize, data: &[u8]) {
@@ -200,4 +508,43 @@ impl Handler for TextFormat {
         }
         println!("SOI");
     }
+
+    fn handle_embedded_image(&mut self, index: usize, position: usize, entry: Option<&MpEntry>) {
+        self.jfif = false;
+        self.adobe = None;
+        self.quantization_tables.clear();
+        self.frame = None;
+
+        print!("Embedded image {} at 0x{:X}", index, position);
+
+        if let Some(entry) = entry {
+            print!(": {} Size: {}", entry.image_type_name(), entry.size);
+        }
+
+        println!();
+    }
+
+    fn handle_warning(&mut self, warning: &Warning) {
+        println!("Warning at 0x{:X}: {}", warning.position, warning.kind);
+    }
+
+    fn handle_encoder(&mut self, matches: &[EncoderMatch]) {
+        if matches.is_empty() {
+            println!("Encoder: Unknown");
+        } else {
+            println!("Encoder:");
+        }
+
+        for m in matches {
+            let evidence: Vec<String> = m.evidence.iter().map(|e| e.to_string()).collect();
+
+            println!(
+                "  {} ({}/{}): {}",
+                m.encoder.name(),
+                m.score,
+                m.max_score,
+                evidence.join(", ")
+            );
+        }
+    }
 }
@@ -0,0 +1,545 @@
+//! Structural validation of JPEG streams against ITU T.81
+
+use std::fmt::Display;
+use std::io::{ErrorKind, Read};
+
+use crate::{Frame, JfifError, Reader, ReaderOptions, Scan, Segment, SegmentKind, Warning};
+
+/// Rules checked by the [Validator]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rule {
+    /// The stream starts with SOI (B.2.1)
+    SoiFirst,
+    /// SOI only starts a new image after EOI (B.2.1)
+    SoiUnique,
+    /// Every image ends with EOI (B.2.1)
+    EoiPresent,
+    /// Segments after EOI that don't start a new image
+    SegmentAfterEoi,
+    /// A segment can't be parsed
+    SegmentSyntax,
+    /// A non-hierarchical image has a single frame header (B.2.1)
+    SofUnique,
+    /// The frame header precedes all scans (B.2.1)
+    SofBeforeSos,
+    /// Sampling factors are between 1 and 4 (B.2.2)
+    SamplingFactor,
+    /// Scan components are defined in the frame header (B.2.3)
+    ScanComponent,
+    /// Interleaved MCUs contain at most 10 blocks (B.2.3)
+    McuBlocks,
+    /// Quantization tables are defined before the first scan using them (B.2.4.1)
+    DqtDefined,
+    /// Huffman tables are defined before the first scan using them (B.2.4.2)
+    DhtDefined,
+    /// Huffman tables have a valid code tree and values (Annex C)
+    DhtTable,
+    /// RST markers are only used with a restart interval (B.2.4.4)
+    RstWithoutDri,
+    /// RST markers are numbered from 0 to 7 in sequence (B.2.1)
+    RstSequence,
+    /// Every restart interval except the last one ends with an RST marker (B.2.1)
+    RstCount,
+    /// The reader skipped bytes that don't belong to the stream syntax
+    Recovered,
+}
+
+impl Rule {
+    /// Stable identifier of the rule
+    pub fn id(&self) -> &'static str {
+        match self {
+            Rule::SoiFirst => "soi-first",
+            Rule::SoiUnique => "soi-unique",
+            Rule::EoiPresent => "eoi-present",
+            Rule::SegmentAfterEoi => "segment-after-eoi",
+            Rule::SegmentSyntax => "segment-syntax",
+            Rule::SofUnique => "sof-unique",
+            Rule::SofBeforeSos => "sof-before-sos",
+            Rule::SamplingFactor => "sampling-factor",
+            Rule::ScanComponent => "scan-component",
+            Rule::McuBlocks => "mcu-blocks",
+            Rule::DqtDefined => "dqt-defined",
+            Rule::DhtDefined => "dht-defined",
+            Rule::DhtTable => "dht-table",
+            Rule::RstWithoutDri => "rst-without-dri",
+            Rule::RstSequence => "rst-sequence",
+            Rule::RstCount => "rst-count",
+            Rule::Recovered => "recovered",
+        }
+    }
+
+    pub fn severity(&self) -> Severity {
+        match self {
+            Rule::SegmentAfterEoi | Rule::RstCount | Rule::Recovered => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+/// Violation of a rule
+#[derive(Debug, Clone)]
+pub struct Finding {
+    pub rule: Rule,
+    pub severity: Severity,
+    /// Position of the segment the finding refers to
+    pub position: usize,
+    pub message: String,
+}
+
+impl Display for Finding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "0x{:X}: {} [{}] {}",
+            self.position,
+            self.severity.name(),
+            self.rule.id(),
+            self.message
+        )
+    }
+}
+
+/// Restart markers of the current scan
+#[derive(Default)]
+struct Restarts {
+    /// Number of the next expected RST marker
+    next: u8,
+    count: usize,
+    /// Number of RST markers the scan should contain if known
+    expected: Option<usize>,
+    /// Position of the scan
+    position: usize,
+}
+
+/// Checks the structure of a stream segment by segment
+///
+/// All segments have to be passed to [Validator::process] in order followed by a call to
+/// [Validator::finish] at the end of the stream.
+#[derive(Default)]
+pub struct Validator {
+    findings: Vec<Finding>,
+    /// Whether an image was started with SOI and not yet ended with EOI
+    in_image: bool,
+    /// Whether segments are ignored until the next SOI
+    ignore: bool,
+    frame: Option<Frame>,
+    quantization_tables: [bool; 4],
+    /// DC and AC Huffman tables
+    huffman_tables: [[bool; 4]; 2],
+    restart_interval: u16,
+    restarts: Option<Restarts>,
+    /// End of the last segment
+    end: usize,
+}
+
+impl Validator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Findings of all segments processed so far
+    pub fn findings(&self) -> &[Finding] {
+        &self.findings
+    }
+
+    /// Check the next segment of the stream
+    pub fn process(&mut self, segment: &Segment) {
+        let position = segment.position;
+        self.end = position + segment.length;
+
+        if !matches!(segment.kind, SegmentKind::Rst(_)) {
+            self.finish_scan();
+        }
+
+        if !self.in_image {
+            if let SegmentKind::Soi = segment.kind {
+                self.ignore = false;
+            } else {
+                // Everything up to the next SOI is probably not part of the stream
+                if !self.ignore {
+                    self.add(
+                        Rule::SegmentAfterEoi,
+                        position,
+                        "Segment after EOI".to_owned(),
+                    );
+                    self.ignore = true;
+                }
+                return;
+            }
+        }
+
+        match &segment.kind {
+            SegmentKind::Soi => {
+                if self.in_image {
+                    self.add(Rule::SoiUnique, position, "SOI before EOI".to_owned());
+                }
+                self.start_image();
+            }
+            SegmentKind::Eoi => {
+                self.in_image = false;
+                self.frame = None;
+            }
+            SegmentKind::Dqt(tables) => {
+                for table in tables {
+                    if let Some(defined) = self.quantization_tables.get_mut(table.dest as usize) {
+                        *defined = true;
+                    }
+                }
+            }
+            SegmentKind::Dht(tables) => {
+                for table in tables {
+                    for issue in table.issues(self.frame.as_ref()) {
+                        self.add(
+                            Rule::DhtTable,
+                            position,
+                            format!("Table {} of class {}: {}", table.dest, table.class, issue),
+                        );
+                    }
+
+                    let class = self.huffman_tables.get_mut(table.class as usize);
+                    if let Some(defined) = class.and_then(|c| c.get_mut(table.dest as usize)) {
+                        *defined = true;
+                    }
+                }
+            }
+            SegmentKind::Dri(restart_interval) => self.restart_interval = *restart_interval,
+            SegmentKind::Frame(frame) => self.check_frame(frame, position),
+            SegmentKind::Scan(scan) => self.check_scan(scan, position),
+            SegmentKind::Rst(rst) => self.check_rst(rst.nr, position),
+            _ => {}
+        }
+    }
+
+    /// Report a segment that couldn't be read and stop validating the current image
+    pub fn process_error(&mut self, error: &JfifError) {
+        // Data after the last image isn't required to contain valid segments
+        if self.in_image {
+            self.add(Rule::SegmentSyntax, self.end, error.to_string());
+            self.in_image = false;
+            self.ignore = true;
+        }
+    }
+
+    /// Report a problem the reader recovered from
+    pub fn process_warning(&mut self, warning: &Warning) {
+        self.add(Rule::Recovered, warning.position, warning.kind.to_string());
+    }
+
+    /// Check the end of the stream and return all findings
+    pub fn finish(mut self) -> Vec<Finding> {
+        self.finish_scan();
+
+        if self.in_image {
+            self.add(Rule::EoiPresent, self.end, "Missing EOI".to_owned());
+        }
+
+        self.findings
+    }
+
+    fn add(&mut self, rule: Rule, position: usize, message: String) {
+        self.findings.push(Finding {
+            rule,
+            severity: rule.severity(),
+            position,
+            message,
+        });
+    }
+
+    fn start_image(&mut self) {
+        self.in_image = true;
+        self.frame = None;
+        self.quantization_tables = [false; 4];
+        self.huffman_tables = [[false; 4]; 2];
+        self.restart_interval = 0;
+    }
+
+    fn check_frame(&mut self, frame: &Frame, position: usize) {
+        let hierarchical = matches!(frame.sof, 0xC5..=0xC7 | 0xCD..=0xCF);
+
+        if self.frame.is_some() && !hierarchical {
+            self.add(
+                Rule::SofUnique,
+                position,
+                "Multiple frame headers".to_owned(),
+            );
+        }
+
+        for component in &frame.components {
+            let h = component.horizontal_sampling_factor;
+            let v = component.vertical_sampling_factor;
+
+            if !(1..=4).contains(&h) || !(1..=4).contains(&v) {
+                self.add(
+                    Rule::SamplingFactor,
+                    position,
+                    format!(
+                        "Component {} has sampling factors {}x{}",
+                        component.id, h, v
+                    ),
+                );
+            }
+        }
+
+        self.frame = Some(frame.clone());
+    }
+
+    fn check_scan(&mut self, scan: &Scan, position: usize) {
+        let frame = match self.frame.take() {
+            Some(frame) => frame,
+            None => {
+                self.add(
+                    Rule::SofBeforeSos,
+                    position,
+                    "Scan without frame".to_owned(),
+                );
+                return;
+            }
+        };
+
+        let lossless = matches!(frame.sof, 0xC3 | 0xC7 | 0xCB | 0xCF);
+        let arithmetic = frame.sof >= 0xC8;
+        let progressive = matches!(frame.sof, 0xC2 | 0xC6 | 0xCA | 0xCE);
+
+        // The high nibble of the successive approximation is read into `approximation_low`
+        let dc_table_used = if progressive {
+            scan.selection_start == 0 && scan.approximation_low == 0
+        } else {
+            true
+        };
+        let ac_table_used = !lossless && scan.selection_end > 0;
+
+        let mut blocks = 0;
+
+        for sc in &scan.components {
+            let component = match frame.components.iter().find(|c| c.id == sc.id) {
+                Some(component) => component,
+                None => {
+                    self.add(
+                        Rule::ScanComponent,
+                        position,
+                        format!("Component {} is not part of the frame", sc.id),
+                    );
+                    continue;
+                }
+            };
+
+            blocks += component.horizontal_sampling_factor as usize
+                * component.vertical_sampling_factor as usize;
+
+            let dest = component.quantization_table;
+            if !lossless
+                && !self
+                    .quantization_tables
+                    .get(dest as usize)
+                    .copied()
+                    .unwrap_or(false)
+            {
+                self.add(
+                    Rule::DqtDefined,
+                    position,
+                    format!(
+                        "Quantization table {} of component {} is not defined",
+                        dest, sc.id
+                    ),
+                );
+            }
+
+            if !arithmetic {
+                let tables = [
+                    (0, sc.dc_table, dc_table_used),
+                    (1, sc.ac_table, ac_table_used),
+                ];
+
+                for (class, dest, used) in tables {
+                    let defined = self.huffman_tables[class]
+                        .get(dest as usize)
+                        .copied()
+                        .unwrap_or(false);
+
+                    if used && !defined {
+                        let name = if class == 0 { "DC" } else { "AC" };
+                        self.add(
+                            Rule::DhtDefined,
+                            position,
+                            format!(
+                                "{} table {} of component {} is not defined",
+                                name, dest, sc.id
+                            ),
+                        );
+                    }
+                }
+            }
+        }
+
+        if scan.components.len() > 1 && blocks > 10 {
+            self.add(
+                Rule::McuBlocks,
+                position,
+                format!("MCU contains {} blocks", blocks),
+            );
+        }
+
+        let expected = if self.restart_interval > 0 {
+            mcu_count(&frame, scan, lossless).map(|count| {
+                count
+                    .div_ceil(self.restart_interval as usize)
+                    .saturating_sub(1)
+            })
+        } else {
+            None
+        };
+
+        self.restarts = Some(Restarts {
+            expected,
+            position,
+            ..Default::default()
+        });
+
+        self.frame = Some(frame);
+    }
+
+    fn check_rst(&mut self, nr: u8, position: usize) {
+        let mut restarts = match self.restarts.take() {
+            Some(restarts) => restarts,
+            None => return,
+        };
+
+        if self.restart_interval == 0 {
+            // Only the first marker of a scan is reported
+            if restarts.count == 0 {
+                self.add(
+                    Rule::RstWithoutDri,
+                    position,
+                    format!("RST{} without restart interval", nr),
+                );
+            }
+        } else if nr != restarts.next {
+            self.add(
+                Rule::RstSequence,
+                position,
+                format!("RST{} instead of RST{}", nr, restarts.next),
+            );
+        }
+
+        restarts.next = (nr + 1) % 8;
+        restarts.count += 1;
+
+        self.restarts = Some(restarts);
+    }
+
+    fn finish_scan(&mut self) {
+        if let Some(restarts) = self.restarts.take() {
+            if let Some(expected) = restarts.expected {
+                if restarts.count != expected {
+                    self.add(
+                        Rule::RstCount,
+                        restarts.position,
+                        format!(
+                            "Scan contains {} RST markers instead of {}",
+                            restarts.count, expected
+                        ),
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Number of MCUs of a scan or None if the frame has no height yet
+fn mcu_count(frame: &Frame, scan: &Scan, lossless: bool) -> Option<usize> {
+    let unit = if lossless { 1 } else { 8 };
+
+    let width = frame.dimension_x as usize;
+    let height = frame.dimension_y as usize;
+
+    if width == 0 || height == 0 {
+        return None;
+    }
+
+    let max_h = frame
+        .components
+        .iter()
+        .map(|c| c.horizontal_sampling_factor.max(1) as usize)
+        .max()?;
+    let max_v = frame
+        .components
+        .iter()
+        .map(|c| c.vertical_sampling_factor.max(1) as usize)
+        .max()?;
+
+    match scan.components.as_slice() {
+        [sc] => {
+            let component = frame.components.iter().find(|c| c.id == sc.id)?;
+            let h = component.horizontal_sampling_factor.max(1) as usize;
+            let v = component.vertical_sampling_factor.max(1) as usize;
+
+            let columns = (width * h).div_ceil(max_h).div_ceil(unit);
+            let lines = (height * v).div_ceil(max_v).div_ceil(unit);
+
+            Some(columns * lines)
+        }
+        _ => Some(width.div_ceil(unit * max_h) * height.div_ceil(unit * max_v)),
+    }
+}
+
+/// Validate the structure of all images of a stream
+///
+/// Errors reading the input are returned while malformed segments are reported as findings.
+pub fn validate<R: Read>(input: R) -> Result<Vec<Finding>, JfifError> {
+    let mut validator = Validator::new();
+
+    let options = ReaderOptions {
+        retain_scan_data: false,
+    };
+
+    let mut reader = match Reader::with_options(input, options) {
+        Ok(reader) => reader,
+        Err(JfifError::JfifMarkerNotFound) => {
+            validator.add(
+                Rule::SoiFirst,
+                0,
+                "Stream doesn't start with SOI".to_owned(),
+            );
+            return Ok(validator.finish());
+        }
+        Err(err) => return Err(err),
+    };
+
+    let mut processed_warnings = 0;
+
+    loop {
+        let result = reader.next_segment();
+
+        for warning in &reader.warnings()[processed_warnings..] {
+            validator.process_warning(warning);
+        }
+        processed_warnings = reader.warnings().len();
+
+        match result {
+            Ok(segment) => validator.process(&segment),
+            Err(JfifError::IoError(err)) if err.kind() == ErrorKind::UnexpectedEof => break,
+            Err(JfifError::IoError(err)) => return Err(JfifError::IoError(err)),
+            Err(err) => {
+                validator.process_error(&err);
+                break;
+            }
+        }
+    }
+
+    Ok(validator.finish())
+}
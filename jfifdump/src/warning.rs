@@ -0,0 +1,43 @@
+use std::fmt::Display;
+
+/// Problem the reader recovered from without failing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WarningKind {
+    /// Bytes at the end of a segment that aren't part of its content
+    SurplusBytes { marker: u8, count: usize },
+    /// Bytes before a marker that don't belong to any segment
+    GarbageBeforeMarker { count: usize },
+    /// Multiple 0xFF bytes before a stuffed zero byte in entropy-coded data
+    FillBytesInScanData { count: usize },
+}
+
+/// Recoverable problem found at a position of the input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Warning {
+    pub position: usize,
+    pub kind: WarningKind,
+}
+
+impl WarningKind {
+    /// Short identifier of the kind
+    pub fn name(&self) -> &'static str {
+        match self {
+            WarningKind::SurplusBytes { .. } => "surplus-bytes",
+            WarningKind::GarbageBeforeMarker { .. } => "garbage-before-marker",
+            WarningKind::FillBytesInScanData { .. } => "fill-bytes-in-scan-data",
+        }
+    }
+}
+
+impl Display for WarningKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use WarningKind::*;
+        match self {
+            SurplusBytes { marker, count } => {
+                write!(f, "{} surplus bytes in segment 0x{:X}", count, marker)
+            }
+            GarbageBeforeMarker { count } => write!(f, "{} bytes before marker", count),
+            FillBytesInScanData { count } => write!(f, "{} fill bytes in scan data", count),
+        }
+    }
+}
@@ -0,0 +1,254 @@
+use std::io::Write;
+
+use crate::icc::ICC_SIGNATURE;
+use crate::jfxx::JFXX_SIGNATURE;
+use crate::mpf::MPF_SIGNATURE;
+use crate::photoshop::PHOTOSHOP_SIGNATURE;
+use crate::xmp::{XMP_EXTENSION_SIGNATURE, XMP_SIGNATURE};
+use crate::{JfifError, SegmentKind};
+
+/// A writer for JFIF files
+///
+/// Segments are written as they would be read by [Reader](crate::Reader).
+/// Writing all segments of a well-formed file results in a byte-identical copy.
+/// Scans read without [ReaderOptions::retain_scan_data](crate::ReaderOptions::retain_scan_data)
+/// can't be written and fail with [JfifError::MissingScanData].
+///
+/// Parsed APP segments are written from their raw data if they contain any.
+/// Changes to derived fields like [App0Jfxx::thumbnail](crate::App0Jfxx::thumbnail)
+/// or [App1Exif::ifds](crate::App1Exif::ifds) are not written.
+///
+/// ```no_run
+/// # use jfifdump::JfifError;
+/// # fn main() -> Result<(), JfifError> {
+/// use jfifdump::{Reader, SegmentKind, Writer};
+/// use std::fs::File;
+/// use std::io::{BufReader, BufWriter};
+///
+/// let mut reader = Reader::new(BufReader::new(File::open("some.jpeg")?))?;
+/// let mut writer = Writer::new(BufWriter::new(File::create("copy.jpeg")?));
+///
+/// loop {
+///     let segment = reader.next_segment()?;
+///     writer.write_segment(&segment.kind)?;
+///
+///     if let SegmentKind::Eoi = segment.kind {
+///         break;
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct Writer<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> Writer<W> {
+    /// Create a new writer
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    /// Return the underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Write a single segment including its marker
+    pub fn write_segment(&mut self, kind: &SegmentKind) -> Result<(), JfifError> {
+        match kind {
+            SegmentKind::Soi => self.write_marker(0xD8),
+            SegmentKind::Eoi => self.write_marker(0xD9),
+            SegmentKind::App { nr, data } => self.write_app(*nr, &[data]),
+            SegmentKind::App0Jfif(jfif) => {
+                let density_x = jfif.x_density.to_be_bytes();
+                let density_y = jfif.y_density.to_be_bytes();
+                self.write_app(
+                    0,
+                    &[
+                        b"JFIF\0",
+                        &[jfif.major, jfif.minor, jfif.unit],
+                        &density_x,
+                        &density_y,
+                        &[jfif.x_thumbnail, jfif.y_thumbnail],
+                        jfif.thumbnail.as_deref().unwrap_or_default(),
+                        &jfif.trailing,
+                    ],
+                )
+            }
+            SegmentKind::App0Jfxx(jfxx) => {
+                self.write_app(0, &[JFXX_SIGNATURE, &[jfxx.extension_code], &jfxx.data])
+            }
+            SegmentKind::App1Exif(exif) => self.write_app(1, &[b"Exif\0\0", &exif.data]),
+            SegmentKind::App1Xmp(xmp) => self.write_app(1, &[XMP_SIGNATURE, xmp.packet.as_bytes()]),
+            SegmentKind::App1XmpExtension(extension) => self.write_app(
+                1,
+                &[
+                    XMP_EXTENSION_SIGNATURE,
+                    extension.guid.as_bytes(),
+                    &extension.full_length.to_be_bytes(),
+                    &extension.offset.to_be_bytes(),
+                    &extension.data,
+                ],
+            ),
+            SegmentKind::App2Icc(chunk) => self.write_app(
+                2,
+                &[ICC_SIGNATURE, &[chunk.sequence, chunk.count], &chunk.data],
+            ),
+            SegmentKind::App2Mpf(mpf) => self.write_app(2, &[MPF_SIGNATURE, &mpf.data]),
+            SegmentKind::App13Photoshop(photoshop) => {
+                self.write_app(13, &[PHOTOSHOP_SIGNATURE, &photoshop.data])
+            }
+            SegmentKind::App14Adobe(adobe) => self.write_app(
+                14,
+                &[
+                    b"Adobe",
+                    &adobe.version.to_be_bytes(),
+                    &adobe.flags0.to_be_bytes(),
+                    &adobe.flags1.to_be_bytes(),
+                    &[adobe.transform],
+                    &adobe.trailing,
+                ],
+            ),
+            SegmentKind::Dqt(tables) => {
+                let mut data = vec![];
+
+                for table in tables {
+                    data.push(table.precision << 4 | table.dest);
+
+                    match table.precision {
+                        0 => data.extend(table.values.iter().map(|&v| v as u8)),
+                        1 => data.extend(table.values.iter().flat_map(|v| v.to_be_bytes())),
+                        precision => return Err(JfifError::InvalidDqtPrecision(precision)),
+                    }
+                }
+
+                self.write_marker_segment(0xDB, &[&data])
+            }
+            SegmentKind::Dht(tables) => {
+                let mut data = vec![];
+
+                for table in tables {
+                    data.push(table.class << 4 | table.dest);
+                    data.extend_from_slice(&table.code_lengths);
+                    data.extend_from_slice(&table.values);
+                }
+
+                self.write_marker_segment(0xC4, &[&data])
+            }
+            SegmentKind::Dac(dac) => {
+                let data: Vec<u8> = dac
+                    .params
+                    .iter()
+                    .flat_map(|param| [param.class << 4 | param.dest, param.value])
+                    .collect();
+
+                self.write_marker_segment(0xCC, &[&data])
+            }
+            SegmentKind::Frame(frame) => {
+                let mut data = vec![frame.precision];
+                data.extend_from_slice(&frame.dimension_y.to_be_bytes());
+                data.extend_from_slice(&frame.dimension_x.to_be_bytes());
+                data.push(frame.components.len() as u8);
+
+                for component in &frame.components {
+                    data.push(component.id);
+                    data.push(
+                        component.horizontal_sampling_factor << 4
+                            | component.vertical_sampling_factor,
+                    );
+                    data.push(component.quantization_table);
+                }
+
+                self.write_marker_segment(frame.sof, &[&data])
+            }
+            SegmentKind::Scan(scan) => {
+                let mut data = vec![scan.components.len() as u8];
+
+                for component in &scan.components {
+                    data.push(component.id);
+                    data.push(component.dc_table << 4 | component.ac_table);
+                }
+
+                data.push(scan.selection_start);
+                data.push(scan.selection_end);
+                data.push(scan.approximation_low << 4 | scan.approximation_high);
+
+                if scan.data.is_empty() && scan.data_length > 0 {
+                    return Err(JfifError::MissingScanData);
+                }
+
+                self.write_marker_segment(0xDA, &[&data])?;
+                self.write_entropy_coded_data(&scan.data)
+            }
+            SegmentKind::Dri(restart) => self.write_marker_segment(0xDD, &[&restart.to_be_bytes()]),
+            SegmentKind::Rst(rst) => {
+                if rst.nr > 7 {
+                    return Err(JfifError::InvalidMarker(rst.nr));
+                }
+                if rst.data.is_empty() && rst.data_length > 0 {
+                    return Err(JfifError::MissingScanData);
+                }
+                self.write_marker(0xD0 + rst.nr)?;
+                self.write_entropy_coded_data(&rst.data)
+            }
+            SegmentKind::Comment(data) => self.write_marker_segment(0xFE, &[data]),
+            SegmentKind::Unknown { marker, data } => {
+                if *marker == 0x00 || *marker == 0xFF {
+                    return Err(JfifError::InvalidMarker(*marker));
+                }
+                self.write_marker_segment(*marker, &[data])
+            }
+        }
+    }
+
+    fn write_marker(&mut self, marker: u8) -> Result<(), JfifError> {
+        self.writer.write_all(&[0xFF, marker])?;
+        Ok(())
+    }
+
+    fn write_app(&mut self, nr: u8, parts: &[&[u8]]) -> Result<(), JfifError> {
+        if nr > 15 {
+            return Err(JfifError::InvalidMarker(nr));
+        }
+        self.write_marker_segment(0xE0 + nr, parts)
+    }
+
+    /// Write marker and length followed by the concatenated parts
+    fn write_marker_segment(&mut self, marker: u8, parts: &[&[u8]]) -> Result<(), JfifError> {
+        let length = 2 + parts.iter().map(|part| part.len()).sum::<usize>();
+
+        if length > u16::MAX as usize {
+            return Err(JfifError::SegmentTooLong(length));
+        }
+
+        self.write_marker(marker)?;
+        self.writer.write_all(&(length as u16).to_be_bytes())?;
+
+        for part in parts {
+            self.writer.write_all(part)?;
+        }
+
+        Ok(())
+    }
+
+    /// Write entropy-coded data of a scan or restart interval
+    ///
+    /// A 0x00 is inserted after every run of 0xFF bytes that isn't already stuffed.
+    /// Data read by [Reader](crate::Reader) is always stuffed and written unchanged.
+    fn write_entropy_coded_data(&mut self, data: &[u8]) -> Result<(), JfifError> {
+        let mut result = Vec::with_capacity(data.len());
+        let mut iter = data.iter().copied().peekable();
+
+        while let Some(byte) = iter.next() {
+            result.push(byte);
+
+            if byte == 0xFF && iter.peek() != Some(&0xFF) && iter.peek() != Some(&0x00) {
+                result.push(0x00);
+            }
+        }
+
+        self.writer.write_all(&result)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,174 @@
+use std::collections::BTreeMap;
+
+use crate::md5::md5;
+
+pub(crate) const XMP_SIGNATURE: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+pub(crate) const XMP_EXTENSION_SIGNATURE: &[u8] = b"http://ns.adobe.com/xmp/extension/\0";
+
+/// Content of an APP1 segment with the standard XMP signature
+///
+/// The packet is borrowed from the input if read by [SliceReader](crate::SliceReader).
+#[derive(Debug)]
+pub struct App1Xmp<S = String> {
+    pub packet: S,
+}
+
+impl App1Xmp<&str> {
+    /// Copy the borrowed packet
+    pub fn into_owned(self) -> App1Xmp {
+        App1Xmp {
+            packet: self.packet.to_owned(),
+        }
+    }
+}
+
+impl<S: AsRef<str>> App1Xmp<S> {
+    /// GUID of the extended XMP document referenced by `xmpNote:HasExtendedXMP`
+    pub fn extended_guid(&self) -> Option<&str> {
+        let packet = self.packet.as_ref();
+        let start = packet.find("xmpNote:HasExtendedXMP")?;
+        let rest = &packet[start + "xmpNote:HasExtendedXMP".len()..];
+
+        // Attribute form: xmpNote:HasExtendedXMP="GUID"
+        // Element form: <xmpNote:HasExtendedXMP>GUID</xmpNote:HasExtendedXMP>
+        let rest = rest.trim_start().strip_prefix(['=', '>'])?.trim_start();
+        let rest = rest.strip_prefix(['"', '\'']).unwrap_or(rest);
+
+        let end = rest.find(|c: char| !c.is_ascii_hexdigit())?;
+
+        Some(&rest[..end])
+    }
+}
+
+/// A chunk of an extended XMP document stored in an APP1 segment
+///
+/// The chunk data is borrowed from the input if read by [SliceReader](crate::SliceReader).
+#[derive(Debug)]
+pub struct App1XmpExtension<D = Vec<u8>> {
+    /// Uppercase hex MD5 digest of the full extended XMP document
+    pub guid: String,
+    pub full_length: u32,
+    pub offset: u32,
+    pub data: D,
+}
+
+impl App1XmpExtension<&[u8]> {
+    /// Copy the borrowed chunk data
+    pub fn into_owned(self) -> App1XmpExtension {
+        App1XmpExtension {
+            guid: self.guid,
+            full_length: self.full_length,
+            offset: self.offset,
+            data: self.data.to_vec(),
+        }
+    }
+}
+
+/// Extended XMP document reassembled from all chunks with the same GUID
+#[derive(Debug)]
+pub struct ExtendedXmp {
+    pub guid: String,
+    pub full_length: u32,
+    /// Merged document. Missing ranges of incomplete documents are filled with zeros.
+    pub data: Vec<u8>,
+    /// True if all chunks of the document were found
+    pub complete: bool,
+    /// True if the MD5 digest of the merged document matches the GUID
+    pub checksum_valid: bool,
+}
+
+impl ExtendedXmp {
+    pub fn document(&self) -> Option<&str> {
+        std::str::from_utf8(&self.data).ok()
+    }
+}
+
+pub(crate) fn parse_xmp(data: &[u8]) -> Option<App1Xmp<&str>> {
+    let packet = std::str::from_utf8(data.strip_prefix(XMP_SIGNATURE)?).ok()?;
+
+    Some(App1Xmp { packet })
+}
+
+pub(crate) fn parse_xmp_extension(data: &[u8]) -> Option<App1XmpExtension<&[u8]>> {
+    let data = data.strip_prefix(XMP_EXTENSION_SIGNATURE)?;
+
+    if data.len() < 40 || !data[..32].iter().all(|v| v.is_ascii_alphanumeric()) {
+        return None;
+    }
+
+    Some(App1XmpExtension {
+        guid: String::from_utf8_lossy(&data[..32]).into_owned(),
+        full_length: u32::from_be_bytes([data[32], data[33], data[34], data[35]]),
+        offset: u32::from_be_bytes([data[36], data[37], data[38], data[39]]),
+        data: &data[40..],
+    })
+}
+
+/// Collects extended XMP chunks across segments
+#[derive(Default)]
+pub(crate) struct XmpAssembler {
+    documents: BTreeMap<String, (u32, BTreeMap<u32, Vec<u8>>)>,
+}
+
+impl XmpAssembler {
+    pub(crate) fn add(&mut self, chunk: &App1XmpExtension<&[u8]>) {
+        let (full_length, chunks) = self
+            .documents
+            .entry(chunk.guid.clone())
+            .or_insert_with(|| (chunk.full_length, BTreeMap::new()));
+
+        // Chunks contradicting the first announced length are ignored
+        if *full_length == chunk.full_length {
+            chunks.insert(chunk.offset, chunk.data.to_vec());
+        }
+    }
+
+    pub(crate) fn finish(&self) -> Vec<ExtendedXmp> {
+        self.documents
+            .iter()
+            .map(|(guid, (full_length, chunks))| {
+                let full_length = *full_length;
+                let mut data = vec![];
+                let mut complete = true;
+
+                // End of the covered range in document coordinates
+                let mut end = 0;
+
+                for (&offset, chunk) in chunks {
+                    let offset = offset as usize;
+
+                    // Zeros keep the following chunks at their offsets
+                    if offset > end {
+                        complete = false;
+                        data.resize(offset, 0);
+                        end = offset;
+                    }
+
+                    // Skip bytes already covered by an overlapping chunk
+                    if let Some(rest) = chunk.get(end - offset..) {
+                        data.extend_from_slice(rest);
+                        end += rest.len();
+                    }
+                }
+
+                if end != full_length as usize {
+                    complete = false;
+                }
+
+                let checksum_valid = complete && hex(&md5(&data)) == guid.to_ascii_uppercase();
+
+                ExtendedXmp {
+                    guid: guid.clone(),
+                    full_length,
+                    data,
+                    complete,
+                    checksum_valid,
+                }
+            })
+            .collect()
+    }
+}
+
+fn hex(data: &[u8]) -> String {
+    data.iter().map(|v| format!("{:02X}", v)).collect()
+}
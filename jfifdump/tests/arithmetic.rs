@@ -0,0 +1,48 @@
+use jfifdump::decode_coefficients;
+
+/// Huffman-coded baseline image the arithmetic-coded images were transcoded from
+const HUFFMAN: &[u8] = include_bytes!("images/baseline.jpg");
+
+/// Sequential with the default conditioning
+const SEQUENTIAL: &[u8] = include_bytes!("images/arithmetic.jpg");
+
+/// Sequential with a restart interval of one MCU
+const RESTART: &[u8] = include_bytes!("images/arithmetic_restart.jpg");
+
+/// Progressive with the default conditioning
+const PROGRESSIVE: &[u8] = include_bytes!("images/arithmetic_progressive.jpg");
+
+/// Progressive with a restart interval of two MCUs and DAC conditioning L=1, U=4, Kx=10
+const CONDITIONED: &[u8] = include_bytes!("images/arithmetic_dac.jpg");
+
+fn assert_same_coefficients(input: &[u8], sof: u8) {
+    let expected = decode_coefficients(HUFFMAN).unwrap();
+    let decoder = decode_coefficients(input).unwrap();
+
+    assert_eq!(decoder.frame().unwrap().sof, sof);
+    assert_eq!(decoder.components().len(), expected.components().len());
+
+    for (component, expected) in decoder.components().iter().zip(expected.components()) {
+        assert_eq!(component.blocks, expected.blocks);
+    }
+}
+
+#[test]
+fn sequential() {
+    assert_same_coefficients(SEQUENTIAL, 0xC9);
+}
+
+#[test]
+fn sequential_with_restarts() {
+    assert_same_coefficients(RESTART, 0xC9);
+}
+
+#[test]
+fn progressive() {
+    assert_same_coefficients(PROGRESSIVE, 0xCA);
+}
+
+#[test]
+fn progressive_with_conditioning_and_restarts() {
+    assert_same_coefficients(CONDITIONED, 0xCA);
+}
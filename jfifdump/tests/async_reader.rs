@@ -0,0 +1,62 @@
+use std::io::Result as IoResult;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tokio::io::{AsyncRead, ReadBuf};
+
+use jfifdump::{read, read_async, JsonFormat};
+
+/// Progressive 53x41 4:2:0 image with restart intervals
+const PROGRESSIVE: &[u8] = include_bytes!("images/progressive.jpg");
+
+/// Image with all parsed APP segments followed by an embedded image listed in an MPF segment
+const METADATA: &[u8] = include_bytes!("images/metadata.jpg");
+
+/// Input returning a single byte for every read
+struct OneByteReader {
+    data: &'static [u8],
+}
+
+impl AsyncRead for OneByteReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<IoResult<()>> {
+        if let Some((first, rest)) = self.data.split_first() {
+            if buf.remaining() > 0 {
+                buf.put_slice(&[*first]);
+                self.data = rest;
+            }
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+fn json(input: &[u8]) -> String {
+    let mut handler = JsonFormat::new(true);
+    read(input, &mut handler).unwrap();
+    handler.stringify()
+}
+
+async fn json_async<R: AsyncRead + Unpin>(input: R) -> String {
+    let mut handler = JsonFormat::new(true);
+    read_async(input, &mut handler).await.unwrap();
+    handler.stringify()
+}
+
+#[tokio::test]
+async fn same_output_as_read() {
+    for input in [PROGRESSIVE, METADATA] {
+        assert_eq!(json_async(input).await, json(input));
+    }
+}
+
+#[tokio::test]
+async fn same_output_for_single_byte_reads() {
+    for input in [PROGRESSIVE, METADATA] {
+        let reader = OneByteReader { data: input };
+        assert_eq!(json_async(reader).await, json(input));
+    }
+}
@@ -0,0 +1,139 @@
+use jfifdump::{decode_coefficients, JfifError};
+use std::io::ErrorKind;
+
+/// Baseline 44x30 4:2:0 image with a restart interval of two MCUs
+const BASELINE: &[u8] = include_bytes!("images/baseline.jpg");
+
+/// Progressive 53x41 4:2:0 image with restart intervals and successive approximation
+const PROGRESSIVE: &[u8] = include_bytes!("images/progressive.jpg");
+
+// Expected blocks as returned by libjpeg's jpeg_read_coefficients in natural order
+
+#[rustfmt::skip]
+const BASELINE_Y_0_0: [i16; 64] = [
+    19, -52, -19, 1, -3, 0, -1, 0,
+    -33, -18, 12, -6, 3, 0, 1, -1,
+    -7, 8, -6, 2, 0, 1, 0, 1,
+    -3, -3, 2, -1, 1, -1, 1, -1,
+    -4, 3, -1, 0, -1, 0, -1, 1,
+    1, -2, 1, -1, 0, 0, 0, 0,
+    -1, 2, -1, 0, 0, 0, 0, 0,
+    1, -1, 1, 0, 0, 0, 0, 1,
+];
+
+#[rustfmt::skip]
+const BASELINE_Y_0_2: [i16; 64] = [
+    -8, -51, 13, 2, 2, -1, 0, 0,
+    -26, 18, 13, 6, 3, 1, 0, 0,
+    10, 11, 11, 3, 1, 0, 0, 0,
+    5, 7, 6, 2, 0, 0, 0, -1,
+    3, 5, 1, 1, 0, 0, 0, 0,
+    2, 1, 1, 0, 0, 0, 0, 0,
+    1, 1, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0,
+];
+
+#[rustfmt::skip]
+const BASELINE_Y_5_3: [i16; 64] = [
+    17, 21, 21, 10, 5, 2, 1, 0,
+    91, -30, -22, -12, -5, -1, 0, 0,
+    -6, -7, -1, 2, 2, 1, 0, 0,
+    -29, 7, 5, 2, 0, 0, 0, 0,
+    3, 1, 0, -1, -1, 0, 0, 0,
+    12, -5, -2, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0,
+    -3, 2, 1, 0, -1, -1, 0, 0,
+];
+
+#[rustfmt::skip]
+const BASELINE_CB_2_1: [i16; 64] = [
+    -4, 0, -2, -1, 0, 0, 0, 0,
+    2, -4, -1, 1, 1, 0, 0, 0,
+    4, -2, 2, 0, 0, 0, 0, 0,
+    -1, 2, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0,
+    1, -1, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0,
+];
+
+#[rustfmt::skip]
+const PROGRESSIVE_Y_0_0: [i16; 64] = [
+    12, -40, -30, 3, -4, 0, -1, 0,
+    -22, -30, 21, -9, 3, 1, 0, 1,
+    -11, 11, -6, 2, 2, -2, 1, 0,
+    -2, -2, -1, 1, -1, 1, -1, 0,
+    -3, 3, -1, 1, 0, 0, 0, 1,
+    0, 0, 0, -1, 1, 0, 0, 0,
+    0, -1, 0, 0, 0, 0, 0, 0,
+    -1, 1, -1, 1, 0, 0, 0, 0,
+];
+
+#[rustfmt::skip]
+const PROGRESSIVE_Y_3_2: [i16; 64] = [
+    -16, -48, 16, 5, 4, 2, 1, 1,
+    -25, 17, 14, 10, 6, 2, 1, 1,
+    5, 10, 6, 4, 2, 1, 1, 0,
+    -2, 0, 0, 1, 0, 0, 0, 0,
+    -2, -1, -1, 0, 0, 0, 0, 0,
+    0, 1, 1, 1, 0, 0, 0, 0,
+    1, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 1, 0, 0, 0, 0,
+];
+
+#[rustfmt::skip]
+const PROGRESSIVE_CR_3_2: [i16; 64] = [
+    -3, 2, 1, 0, 0, 0, 0, 0,
+    10, -3, -2, 0, 0, 0, 0, 0,
+    5, -3, -1, 0, 0, 0, 0, 0,
+    2, -1, 0, 0, 0, 0, 0, 0,
+    1, 0, 0, 0, 0, 0, 0, 0,
+    1, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0,
+];
+
+#[test]
+fn baseline_with_restarts() {
+    let decoder = decode_coefficients(BASELINE).unwrap();
+    let components = decoder.components();
+
+    assert_eq!(components.len(), 3);
+
+    let luma = &components[0];
+    assert_eq!((luma.width, luma.height), (44, 30));
+    assert_eq!((luma.blocks_per_line, luma.blocks_per_column), (6, 4));
+
+    let chroma = &components[1];
+    assert_eq!((chroma.width, chroma.height), (22, 15));
+    assert_eq!((chroma.blocks_per_line, chroma.blocks_per_column), (3, 2));
+
+    assert_eq!(luma.block(0, 0), &BASELINE_Y_0_0);
+    // First block after RST1 with the DC prediction reset
+    assert_eq!(luma.block(0, 2), &BASELINE_Y_0_2);
+    // Last block of the last MCU
+    assert_eq!(luma.block(5, 3), &BASELINE_Y_5_3);
+    assert_eq!(chroma.block(2, 1), &BASELINE_CB_2_1);
+}
+
+#[test]
+fn progressive_with_restarts() {
+    let decoder = decode_coefficients(PROGRESSIVE).unwrap();
+    let components = decoder.components();
+
+    assert_eq!(components.len(), 3);
+    assert_eq!(components[0].block(0, 0), &PROGRESSIVE_Y_0_0);
+    assert_eq!(components[0].block(3, 2), &PROGRESSIVE_Y_3_2);
+    assert_eq!(components[2].block(3, 2), &PROGRESSIVE_CR_3_2);
+}
+
+#[test]
+fn truncated_scan() {
+    // Cut inside the entropy-coded data of the first restart interval
+    let result = decode_coefficients(&BASELINE[..0x300]);
+
+    assert!(matches!(
+        result,
+        Err(JfifError::IoError(err)) if err.kind() == ErrorKind::UnexpectedEof
+    ));
+}
@@ -0,0 +1,44 @@
+use jfifdump::{ExifValue, IfdKind, Reader, SegmentKind};
+
+/// JPEG with an EXIF segment whose IFD0 has a MakerNote pointing past the TIFF data
+fn image_with_broken_maker_note() -> Vec<u8> {
+    let mut tiff = vec![];
+    tiff.extend_from_slice(b"MM\x00\x2A\x00\x00\x00\x08");
+    tiff.extend_from_slice(&[0x00, 0x02]);
+    // Make: ASCII "Canon\0" at offset 38
+    tiff.extend_from_slice(&[0x01, 0x0F, 0x00, 0x02, 0x00, 0x00, 0x00, 0x06]);
+    tiff.extend_from_slice(&[0x00, 0x00, 0x00, 0x26]);
+    // MakerNote: 100 bytes at offset 5000
+    tiff.extend_from_slice(&[0x92, 0x7C, 0x00, 0x07, 0x00, 0x00, 0x00, 0x64]);
+    tiff.extend_from_slice(&[0x00, 0x00, 0x13, 0x88]);
+    tiff.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]);
+    tiff.extend_from_slice(b"Canon\0");
+
+    let length = 2 + 6 + tiff.len();
+
+    let mut image = vec![0xFF, 0xD8, 0xFF, 0xE1];
+    image.extend_from_slice(&(length as u16).to_be_bytes());
+    image.extend_from_slice(b"Exif\0\0");
+    image.extend_from_slice(&tiff);
+    image.extend_from_slice(&[0xFF, 0xD9]);
+    image
+}
+
+#[test]
+fn broken_entry_offset_keeps_other_entries() {
+    let image = image_with_broken_maker_note();
+    let mut reader = Reader::new(image.as_slice()).unwrap();
+
+    reader.next_segment().unwrap();
+
+    let exif = match reader.next_segment().unwrap().kind {
+        SegmentKind::App1Exif(exif) => exif,
+        kind => panic!("Expected EXIF segment, got {:?}", kind),
+    };
+
+    let make = exif.find(IfdKind::Ifd(0), 0x010F).unwrap();
+    assert_eq!(make.value.as_ascii().as_deref(), Some("Canon"));
+
+    let maker_note = exif.find(IfdKind::Ifd(0), 0x927C).unwrap();
+    assert_eq!(maker_note.value, ExifValue::InvalidOffset(5000));
+}
@@ -0,0 +1,93 @@
+use jfifdump::{identify_encoder, Encoder, EncoderMatch, Evidence};
+
+/// Baseline image written by libjpeg-turbo with the default Huffman tables
+const BASELINE: &[u8] = include_bytes!("images/baseline.jpg");
+
+/// Progressive image with IJG tables, optimized Huffman tables and a 72 dpi JFIF density
+const PROGRESSIVE: &[u8] = include_bytes!("images/progressive.jpg");
+
+fn segment(marker: u8, payload: &[u8]) -> Vec<u8> {
+    let mut segment = vec![0xFF, marker];
+    segment.extend_from_slice(&(payload.len() as u16 + 2).to_be_bytes());
+    segment.extend_from_slice(payload);
+    segment
+}
+
+/// EXIF segment with a single ASCII entry in IFD0
+fn exif(tag: u16, text: &str) -> Vec<u8> {
+    let mut payload = b"Exif\0\0MM\x00\x2A\x00\x00\x00\x08\x00\x01".to_vec();
+    payload.extend_from_slice(&tag.to_be_bytes());
+    payload.extend_from_slice(&[0x00, 0x02]);
+    payload.extend_from_slice(&(text.len() as u32 + 1).to_be_bytes());
+    payload.extend_from_slice(&[0, 0, 0, 26, 0, 0, 0, 0]);
+    payload.extend_from_slice(text.as_bytes());
+    payload.push(0);
+    segment(0xE1, &payload)
+}
+
+/// DQT segment with two tables that aren't scaled IJG or mozjpeg tables
+fn custom_tables() -> Vec<u8> {
+    let mut payload = vec![];
+
+    for table in 0..2 {
+        payload.push(table);
+        payload.extend((0..64).map(|i| 1 + table + i / 4));
+    }
+
+    segment(0xDB, &payload)
+}
+
+/// Encoders of the matches in order
+fn encoders(matches: &[EncoderMatch]) -> Vec<Encoder> {
+    matches.iter().map(|m| m.encoder).collect()
+}
+
+#[test]
+fn libjpeg() {
+    let matches = identify_encoder(BASELINE).unwrap();
+
+    assert_eq!(encoders(&matches), [Encoder::Libjpeg]);
+    assert_eq!((matches[0].score, matches[0].max_score), (8, 8));
+}
+
+#[test]
+fn libjpeg_with_optimized_huffman_tables() {
+    let matches = identify_encoder(PROGRESSIVE).unwrap();
+
+    // The density and optimized tables are what ImageMagick writes through libjpeg
+    assert_eq!(encoders(&matches), [Encoder::ImageMagick, Encoder::Libjpeg]);
+    assert!(matches[0].evidence.contains(&Evidence::Jfif72Dpi));
+    assert!(!matches[1].evidence.contains(&Evidence::StandardHuffman));
+}
+
+#[test]
+fn photoshop() {
+    let mut image = vec![0xFF, 0xD8];
+    image.extend(segment(0xEE, b"Adobe\x00\x64\x40\x00\x00\x00\x01"));
+    image.extend(exif(0x0131, "Adobe Photoshop 25.0 (Windows)"));
+    image.extend(segment(
+        0xED,
+        b"Photoshop 3.0\x008BIM\x04\x04\x00\x00\x00\x00\x00\x00",
+    ));
+    image.extend(custom_tables());
+    image.extend_from_slice(&[0xFF, 0xD9]);
+
+    let matches = identify_encoder(image.as_slice()).unwrap();
+
+    assert_eq!(encoders(&matches), [Encoder::Photoshop]);
+    assert_eq!((matches[0].score, matches[0].max_score), (8, 8));
+}
+
+#[test]
+fn android_camera() {
+    let mut image = vec![0xFF, 0xD8];
+    image.extend(exif(0x010F, "samsung"));
+    image.extend(custom_tables());
+    image.extend_from_slice(&[0xFF, 0xD9]);
+
+    let matches = identify_encoder(image.as_slice()).unwrap();
+
+    assert_eq!(encoders(&matches), [Encoder::AndroidCamera]);
+    assert_eq!((matches[0].score, matches[0].max_score), (7, 7));
+    assert!(matches[0].evidence.contains(&Evidence::CustomTables));
+}
@@ -0,0 +1,128 @@
+use jfifdump::{IccChunkIssue, IccProfile, Reader, SegmentKind};
+
+/// Display profile of ICC v4.3 with an English `desc` tag
+fn profile() -> Vec<u8> {
+    let text: Vec<u8> = "Test RGB"
+        .encode_utf16()
+        .flat_map(u16::to_be_bytes)
+        .collect();
+
+    let mut desc = b"mluc\0\0\0\0".to_vec();
+    desc.extend_from_slice(&1u32.to_be_bytes());
+    desc.extend_from_slice(&12u32.to_be_bytes());
+    desc.extend_from_slice(b"enUS");
+    desc.extend_from_slice(&(text.len() as u32).to_be_bytes());
+    desc.extend_from_slice(&28u32.to_be_bytes());
+    desc.extend_from_slice(&text);
+
+    let mut profile = vec![0u8; 128];
+    profile[4..8].copy_from_slice(b"lcms");
+    profile[8] = 4;
+    profile[9] = 0x30;
+    profile[12..16].copy_from_slice(b"mntr");
+    profile[16..20].copy_from_slice(b"RGB ");
+    profile[20..24].copy_from_slice(b"XYZ ");
+    profile[36..40].copy_from_slice(b"acsp");
+    profile[64..68].copy_from_slice(&1u32.to_be_bytes());
+
+    profile.extend_from_slice(&1u32.to_be_bytes());
+    profile.extend_from_slice(b"desc");
+    profile.extend_from_slice(&144u32.to_be_bytes());
+    profile.extend_from_slice(&(desc.len() as u32).to_be_bytes());
+    profile.extend_from_slice(&desc);
+
+    let size = profile.len() as u32;
+    profile[0..4].copy_from_slice(&size.to_be_bytes());
+    profile
+}
+
+/// Split the profile into `count` chunks with sequence numbers starting at 1
+fn chunks(profile: &[u8], count: u8) -> Vec<(u8, u8, &[u8])> {
+    let size = profile.len().div_ceil(count as usize);
+
+    profile
+        .chunks(size)
+        .enumerate()
+        .map(|(i, chunk)| (i as u8 + 1, count, chunk))
+        .collect()
+}
+
+/// Read an image with APP2 chunks in the given order and return the reassembled profile
+fn read_profile(chunks: &[(u8, u8, &[u8])]) -> IccProfile {
+    let mut image = vec![0xFF, 0xD8];
+
+    for (sequence, count, data) in chunks {
+        image.extend_from_slice(&[0xFF, 0xE2]);
+        image.extend_from_slice(&(data.len() as u16 + 16).to_be_bytes());
+        image.extend_from_slice(b"ICC_PROFILE\0");
+        image.extend_from_slice(&[*sequence, *count]);
+        image.extend_from_slice(data);
+    }
+
+    image.extend_from_slice(&[0xFF, 0xD9]);
+
+    let mut reader = Reader::new(image.as_slice()).unwrap();
+    while !matches!(reader.next_segment().unwrap().kind, SegmentKind::Eoi) {}
+
+    reader.icc_profile().unwrap()
+}
+
+#[test]
+fn header_and_description() {
+    let data = profile();
+    let profile = read_profile(&chunks(&data, 3));
+
+    assert_eq!(profile.data, data);
+    assert_eq!(profile.issues, []);
+
+    let header = profile.header.unwrap();
+    assert_eq!(header.size as usize, data.len());
+    assert_eq!(&header.cmm, b"lcms");
+    assert_eq!((header.version_major, header.version_minor), (4, 3));
+    assert_eq!(header.class_name(), "Display device");
+    assert_eq!(&header.color_space, b"RGB ");
+    assert_eq!(&header.pcs, b"XYZ ");
+    assert_eq!(
+        header.rendering_intent_name(),
+        "Media-relative colorimetric"
+    );
+    assert_eq!(header.description.as_deref(), Some("Test RGB"));
+}
+
+#[test]
+fn out_of_order_chunks() {
+    let data = profile();
+    let chunks = chunks(&data, 3);
+    let profile = read_profile(&[chunks[2], chunks[0], chunks[1]]);
+
+    // Chunks are still assembled by sequence number
+    assert_eq!(profile.data, data);
+    assert_eq!(profile.issues, [IccChunkIssue::OutOfOrder(1)]);
+}
+
+#[test]
+fn duplicate_chunk() {
+    let data = profile();
+    let chunks = chunks(&data, 3);
+    let duplicate = (2, 3, &b"duplicate"[..]);
+    let profile = read_profile(&[chunks[0], chunks[1], duplicate, chunks[2]]);
+
+    // Only the first chunk with a sequence number is used
+    assert_eq!(profile.data, data);
+    assert_eq!(profile.issues, [IccChunkIssue::Duplicate(2)]);
+}
+
+#[test]
+fn missing_chunk() {
+    let data = profile();
+    let chunks = chunks(&data, 3);
+    let profile = read_profile(&[chunks[0], chunks[2]]);
+
+    let mut expected = chunks[0].2.to_vec();
+    expected.extend_from_slice(chunks[2].2);
+
+    assert_eq!(profile.data, expected);
+    assert_eq!(profile.issues, [IccChunkIssue::Missing(2)]);
+    // The remaining chunks are shorter than the profile header
+    assert!(profile.header.is_none());
+}
@@ -0,0 +1,58 @@
+use jfifdump::{decode_image, PixelFormat};
+
+// Expected files were written by libjpeg's C implementation (no SIMD) with the
+// accurate integer IDCT and without fancy upsampling
+
+/// Decode an image from `tests/images` and compare it with the PNM file of the same name
+fn assert_decoded(name: &str, format: PixelFormat, size: (usize, usize)) {
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/images/");
+    let input = std::fs::read(format!("{}{}.jpg", dir, name)).unwrap();
+    let expected = std::fs::read(format!("{}{}.pnm", dir, name)).unwrap();
+
+    let image = decode_image(input.as_slice()).unwrap();
+
+    assert_eq!(image.format, format);
+    assert_eq!((image.width, image.height), size);
+    assert!(image.to_pnm() == expected, "{} differs from libjpeg", name);
+}
+
+#[test]
+fn ycbcr_420_with_restarts() {
+    assert_decoded("baseline", PixelFormat::Rgb, (44, 30));
+}
+
+#[test]
+fn ycbcr_420_progressive() {
+    assert_decoded("progressive", PixelFormat::Rgb, (53, 41));
+}
+
+#[test]
+fn ycbcr_422() {
+    assert_decoded("image_422", PixelFormat::Rgb, (21, 13));
+}
+
+#[test]
+fn grayscale() {
+    assert_decoded("image_gray", PixelFormat::Grayscale, (21, 13));
+}
+
+#[test]
+fn adobe_rgb() {
+    assert_decoded("image_rgb", PixelFormat::Rgb, (21, 13));
+}
+
+#[test]
+fn adobe_cmyk() {
+    assert_decoded("image_cmyk", PixelFormat::Cmyk, (21, 13));
+}
+
+#[test]
+fn adobe_ycck_with_subsampling() {
+    assert_decoded("image_ycck", PixelFormat::Cmyk, (21, 13));
+}
+
+#[test]
+fn idct_range_limit() {
+    // Blocks with coefficients far outside the sample range
+    assert_decoded("idct_overflow", PixelFormat::Grayscale, (16, 8));
+}
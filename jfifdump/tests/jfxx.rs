@@ -0,0 +1,81 @@
+use jfifdump::{App0Jfxx, Reader, SegmentKind, Thumbnail};
+
+/// Read an image with an APP0 segment containing a JFXX extension
+fn read_jfxx(extension_code: u8, data: &[u8]) -> App0Jfxx {
+    let mut image = vec![0xFF, 0xD8, 0xFF, 0xE0];
+    image.extend_from_slice(&(data.len() as u16 + 8).to_be_bytes());
+    image.extend_from_slice(b"JFXX\0");
+    image.push(extension_code);
+    image.extend_from_slice(data);
+    image.extend_from_slice(&[0xFF, 0xD9]);
+
+    let mut reader = Reader::new(image.as_slice()).unwrap();
+    reader.next_segment().unwrap();
+
+    match reader.next_segment().unwrap().kind {
+        SegmentKind::App0Jfxx(jfxx) => jfxx,
+        kind => panic!("Expected JFXX segment, got {:?}", kind),
+    }
+}
+
+#[test]
+fn palette_thumbnail() {
+    let mut palette = vec![0; 768];
+    palette[3..6].copy_from_slice(&[255, 0, 0]);
+    palette[765..].copy_from_slice(&[0, 0, 255]);
+
+    let mut data = vec![2, 2];
+    data.extend_from_slice(&palette);
+    data.extend_from_slice(&[0, 1, 255, 1]);
+
+    let jfxx = read_jfxx(0x11, &data);
+    let thumbnail = jfxx.thumbnail.unwrap();
+
+    assert_eq!(thumbnail.name(), "Palette");
+    assert!(thumbnail.is_valid());
+    assert_eq!(thumbnail.file_extension(), "ppm");
+
+    // Every index is replaced by its palette entry
+    let mut expected = b"P6\n2 2\n255\n".to_vec();
+    expected.extend_from_slice(&[0, 0, 0, 255, 0, 0, 0, 0, 255, 255, 0, 0]);
+    assert_eq!(thumbnail.to_file().unwrap(), expected);
+}
+
+#[test]
+fn rgb_thumbnail() {
+    let pixels = [10, 20, 30, 40, 50, 60, 70, 80, 90];
+    let mut data = vec![3, 1];
+    data.extend_from_slice(&pixels);
+
+    let jfxx = read_jfxx(0x13, &data);
+    let thumbnail = jfxx.thumbnail.unwrap();
+
+    assert_eq!(thumbnail.name(), "RGB");
+    assert!(thumbnail.is_valid());
+    assert_eq!(thumbnail.file_extension(), "ppm");
+
+    let mut expected = b"P6\n3 1\n255\n".to_vec();
+    expected.extend_from_slice(&pixels);
+    assert_eq!(thumbnail.to_file().unwrap(), expected);
+}
+
+#[test]
+fn thumbnails_with_wrong_size() {
+    let jfxx = read_jfxx(0x13, &[2, 2, 1, 2, 3]);
+    let thumbnail = jfxx.thumbnail.unwrap();
+
+    assert_eq!(thumbnail.expected_size(), Some(12));
+    assert!(!thumbnail.is_valid());
+    assert_eq!(thumbnail.to_file(), None);
+
+    let thumbnail = Thumbnail::Palette {
+        width: 1,
+        height: 1,
+        palette: vec![0; 3],
+        pixels: vec![0],
+    };
+
+    // The palette always has 256 entries
+    assert!(!thumbnail.is_valid());
+    assert_eq!(thumbnail.to_file(), None);
+}
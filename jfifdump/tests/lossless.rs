@@ -0,0 +1,94 @@
+use jfifdump::decode_lossless;
+
+// Lossless images named after their predictor with samples given by `expected_sample`
+
+/// 17x13 8 bits, three interleaved components with 4:2:0 sampling
+const PREDICTOR_1: &[u8] = include_bytes!("images/lossless_1.jpg");
+/// 16x8 10 bits
+const PREDICTOR_2: &[u8] = include_bytes!("images/lossless_2.jpg");
+/// 16x8 12 bits, restart interval of one line
+const PREDICTOR_3: &[u8] = include_bytes!("images/lossless_3.jpg");
+/// 16x8 16 bits
+const PREDICTOR_4: &[u8] = include_bytes!("images/lossless_4.jpg");
+/// 16x8 12 bits, point transform of 2
+const PREDICTOR_5: &[u8] = include_bytes!("images/lossless_5.jpg");
+/// 15x9 16 bits, two non-interleaved components with a restart interval of one line
+const PREDICTOR_6: &[u8] = include_bytes!("images/lossless_6.jpg");
+/// 16x8 14 bits, restart interval of two lines
+const PREDICTOR_7: &[u8] = include_bytes!("images/lossless_7.jpg");
+
+fn expected_sample(
+    x: usize,
+    y: usize,
+    component: usize,
+    precision: u8,
+    point_transform: u8,
+) -> u16 {
+    let value =
+        (x * 37 + y * 11 + component * 1000 + (x * y) % 97 + (x ^ y) * 13) % (1 << precision);
+    ((value >> point_transform) << point_transform) as u16
+}
+
+fn assert_samples(input: &[u8], precision: u8, point_transform: u8) {
+    let decoder = decode_lossless(input).unwrap();
+
+    assert_eq!(decoder.frame().unwrap().precision, precision);
+
+    for (index, component) in decoder.components().iter().enumerate() {
+        for y in 0..component.height {
+            for x in 0..component.width {
+                assert_eq!(
+                    component.sample(x, y),
+                    expected_sample(x, y, index, precision, point_transform),
+                    "component {} at {}x{}",
+                    index,
+                    x,
+                    y
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn predictor_1_with_subsampling() {
+    let decoder = decode_lossless(PREDICTOR_1).unwrap();
+    let sizes: Vec<_> = decoder
+        .components()
+        .iter()
+        .map(|c| (c.width, c.height))
+        .collect();
+
+    assert_eq!(sizes, [(17, 13), (9, 7), (9, 7)]);
+    assert_samples(PREDICTOR_1, 8, 0);
+}
+
+#[test]
+fn predictor_2() {
+    assert_samples(PREDICTOR_2, 10, 0);
+}
+
+#[test]
+fn predictor_3_with_restarts() {
+    assert_samples(PREDICTOR_3, 12, 0);
+}
+
+#[test]
+fn predictor_4() {
+    assert_samples(PREDICTOR_4, 16, 0);
+}
+
+#[test]
+fn predictor_5_with_point_transform() {
+    assert_samples(PREDICTOR_5, 12, 2);
+}
+
+#[test]
+fn predictor_6_non_interleaved_with_restarts() {
+    assert_samples(PREDICTOR_6, 16, 0);
+}
+
+#[test]
+fn predictor_7_with_restarts() {
+    assert_samples(PREDICTOR_7, 14, 0);
+}
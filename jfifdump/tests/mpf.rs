@@ -0,0 +1,108 @@
+use jfifdump::{App2Mpf, ByteOrder, IfdKind, Reader, SegmentKind, Writer};
+
+/// Little endian MPF segment with three images and an MP Attribute IFD
+fn mpf_segment() -> Vec<u8> {
+    let mut tiff = b"II\x2A\x00\x08\x00\x00\x00\x03\x00".to_vec();
+    // MPFVersion, NumberOfImages and MPEntry at offset 50
+    tiff.extend_from_slice(b"\x00\xB0\x07\x00\x04\x00\x00\x000100");
+    tiff.extend_from_slice(b"\x01\xB0\x04\x00\x01\x00\x00\x00\x03\x00\x00\x00");
+    tiff.extend_from_slice(b"\x02\xB0\x07\x00\x30\x00\x00\x00\x32\x00\x00\x00");
+    // MP Attribute IFD after the entries
+    tiff.extend_from_slice(&98u32.to_le_bytes());
+
+    for (attribute, size, offset, dependent_image_1) in [
+        (0xA003_0000u32, 1000u32, 0u32, 2u16),
+        (0x4001_0001, 200, 0x400, 0),
+        (0x0002_0002, 300, 0x600, 0),
+    ] {
+        tiff.extend_from_slice(&attribute.to_le_bytes());
+        tiff.extend_from_slice(&size.to_le_bytes());
+        tiff.extend_from_slice(&offset.to_le_bytes());
+        tiff.extend_from_slice(&dependent_image_1.to_le_bytes());
+        tiff.extend_from_slice(&[0, 0]);
+    }
+
+    // MPIndividualNum
+    tiff.extend_from_slice(b"\x01\x00\x01\xB1\x04\x00\x01\x00\x00\x00\x01\x00\x00\x00");
+    tiff.extend_from_slice(&[0, 0, 0, 0]);
+
+    let mut segment = vec![0xFF, 0xE2];
+    segment.extend_from_slice(&(tiff.len() as u16 + 6).to_be_bytes());
+    segment.extend_from_slice(b"MPF\0");
+    segment.extend_from_slice(&tiff);
+    segment
+}
+
+fn read_mpf(segment: &[u8]) -> App2Mpf {
+    let mut image = vec![0xFF, 0xD8];
+    image.extend_from_slice(segment);
+    image.extend_from_slice(&[0xFF, 0xD9]);
+
+    let mut reader = Reader::new(image.as_slice()).unwrap();
+    reader.next_segment().unwrap();
+
+    match reader.next_segment().unwrap().kind {
+        SegmentKind::App2Mpf(mpf) => mpf,
+        kind => panic!("Expected MPF segment, got {:?}", kind),
+    }
+}
+
+#[test]
+fn entry_table() {
+    let mpf = read_mpf(&mpf_segment());
+
+    assert_eq!(mpf.byte_order, ByteOrder::LittleEndian);
+    assert_eq!(mpf.ifds.len(), 2);
+    assert_eq!(mpf.ifds[0].kind, IfdKind::MpIndex);
+    assert_eq!(mpf.ifds[1].kind, IfdKind::MpAttribute);
+    assert_eq!(mpf.ifds[1].offset, 98);
+
+    // SOI, marker, length and signature are in front of the MP header
+    assert_eq!(mpf.header_position, 10);
+
+    let images = &mpf.images;
+    assert_eq!(images.len(), 3);
+
+    assert!(images[0].is_dependent_parent());
+    assert!(images[0].is_representative());
+    assert_eq!(images[0].format(), 0);
+    assert_eq!(images[0].image_type_name(), "Baseline MP Primary Image");
+    assert_eq!((images[0].size, images[0].dependent_image_1), (1000, 2));
+
+    assert!(images[1].is_dependent_child());
+    assert!(!images[1].is_representative());
+    assert_eq!(images[1].image_type_name(), "Large Thumbnail (VGA)");
+
+    assert_eq!(images[2].image_type_name(), "Multi-Frame Disparity");
+    assert_eq!(images[2].size, 300);
+}
+
+#[test]
+fn image_positions() {
+    let mpf = read_mpf(&mpf_segment());
+    let positions: Vec<Option<usize>> = mpf
+        .images
+        .iter()
+        .map(|image| image.position(mpf.header_position))
+        .collect();
+
+    // The first image is the one containing the MPF segment
+    assert_eq!(positions, [None, Some(10 + 0x400), Some(10 + 0x600)]);
+}
+
+#[test]
+fn changed_entries_are_written() {
+    let mut mpf = read_mpf(&mpf_segment());
+
+    mpf.set_image_size(1, 250).unwrap();
+    mpf.set_image_offset(2, 0x700).unwrap();
+    assert!(mpf.set_image_offset(3, 0).is_err());
+
+    let mut writer = Writer::new(vec![]);
+    writer.write_segment(&SegmentKind::App2Mpf(mpf)).unwrap();
+    let mpf = read_mpf(&writer.into_inner());
+
+    assert_eq!(mpf.images[1].size, 250);
+    assert_eq!(mpf.images[1].offset, 0x400);
+    assert_eq!(mpf.images[2].offset, 0x700);
+}
@@ -0,0 +1,110 @@
+use jfifdump::{App13Photoshop, Iptc, Reader, SegmentKind};
+
+/// IPTC-IIM dataset with a standard length field
+fn dataset(record: u8, dataset: u8, data: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![0x1C, record, dataset];
+    bytes.extend_from_slice(&(data.len() as u16).to_be_bytes());
+    bytes.extend_from_slice(data);
+    bytes
+}
+
+/// IPTC-IIM dataset with a four byte length field
+fn extended_dataset(record: u8, dataset: u8, data: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![0x1C, record, dataset, 0x80, 0x04];
+    bytes.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    bytes.extend_from_slice(data);
+    bytes
+}
+
+/// Read an image with an APP13 segment containing an IPTC-NAA resource
+fn read_photoshop(iptc: &[u8]) -> App13Photoshop {
+    let mut resources = b"8BIM\x04\x04\0\0".to_vec();
+    resources.extend_from_slice(&(iptc.len() as u32).to_be_bytes());
+    resources.extend_from_slice(iptc);
+    if iptc.len() % 2 == 1 {
+        resources.push(0);
+    }
+
+    let mut image = vec![0xFF, 0xD8, 0xFF, 0xED];
+    image.extend_from_slice(&(resources.len() as u16 + 16).to_be_bytes());
+    image.extend_from_slice(b"Photoshop 3.0\0");
+    image.extend_from_slice(&resources);
+    image.extend_from_slice(&[0xFF, 0xD9]);
+
+    let mut reader = Reader::new(image.as_slice()).unwrap();
+    reader.next_segment().unwrap();
+
+    match reader.next_segment().unwrap().kind {
+        SegmentKind::App13Photoshop(photoshop) => photoshop,
+        kind => panic!("Expected Photoshop segment, got {:?}", kind),
+    }
+}
+
+fn iptc(photoshop: &App13Photoshop) -> &Iptc {
+    assert_eq!(photoshop.resources.len(), 1);
+    assert_eq!(photoshop.resources[0].id_name(), "IPTC-NAA");
+    photoshop.resources[0].iptc.as_ref().unwrap()
+}
+
+#[test]
+fn extended_dataset_length() {
+    let preview = vec![0xAB; 1000];
+
+    let mut data = dataset(2, 0, &[0, 4]);
+    data.extend(extended_dataset(2, 202, &preview));
+    data.extend(dataset(2, 25, b"keyword"));
+
+    let photoshop = read_photoshop(&data);
+    let iptc = iptc(&photoshop);
+
+    assert_eq!(iptc.datasets.len(), 3);
+    assert_eq!(iptc.datasets[0].number(), Some(4));
+
+    // The dataset following the extended one is still found
+    let preview_data = &iptc.datasets[1];
+    assert_eq!(preview_data.name(), "ObjectPreviewData");
+    assert!(preview_data.is_binary());
+    assert_eq!(preview_data.data, preview);
+
+    assert_eq!(iptc.datasets[2].name(), "Keywords");
+    assert_eq!(iptc.text(&iptc.datasets[2]), "keyword");
+}
+
+#[test]
+fn truncated_extended_dataset() {
+    let mut data = dataset(2, 25, b"keyword");
+    data.extend(extended_dataset(2, 202, &[0xAB; 100]));
+    data.truncate(data.len() - 1);
+
+    let photoshop = read_photoshop(&data);
+    let iptc = iptc(&photoshop);
+
+    // Parsing stops at the dataset exceeding the resource
+    assert_eq!(iptc.datasets.len(), 1);
+    assert_eq!(iptc.datasets[0].name(), "Keywords");
+}
+
+#[test]
+fn utf8_coded_character_set() {
+    let mut data = dataset(1, 90, b"\x1B%G");
+    data.extend(dataset(2, 120, "Café".as_bytes()));
+
+    let photoshop = read_photoshop(&data);
+    let iptc = iptc(&photoshop);
+
+    assert!(iptc.utf8);
+    assert!(iptc.datasets[0].is_binary());
+    assert_eq!(iptc.datasets[1].name(), "Caption-Abstract");
+    assert_eq!(iptc.text(&iptc.datasets[1]), "Café");
+}
+
+#[test]
+fn latin1_without_coded_character_set() {
+    let data = dataset(2, 120, b"Caf\xE9");
+
+    let photoshop = read_photoshop(&data);
+    let iptc = iptc(&photoshop);
+
+    assert!(!iptc.utf8);
+    assert_eq!(iptc.text(&iptc.datasets[0]), "Café");
+}
@@ -0,0 +1,129 @@
+use jfifdump::{decode_coefficients, CoefficientDecoder, Reader, SegmentKind};
+
+/// Progressive 53x41 4:2:0 image with spectral selection and successive approximation
+const PROGRESSIVE: &[u8] = include_bytes!("images/progressive.jpg");
+
+/// Baseline image transcoded losslessly from the progressive one with jpegtran
+const BASELINE: &[u8] = include_bytes!("images/progressive_baseline.jpg");
+
+/// Natural order index of every coefficient in zigzag order
+#[rustfmt::skip]
+const ZIGZAG: [usize; 64] = [
+    0, 1, 8, 16, 9, 2, 3, 10,
+    17, 24, 32, 25, 18, 11, 4, 5,
+    12, 19, 26, 33, 40, 48, 41, 34,
+    27, 20, 13, 6, 7, 14, 21, 28,
+    35, 42, 49, 56, 57, 50, 43, 36,
+    29, 22, 15, 23, 30, 37, 44, 51,
+    58, 59, 52, 45, 38, 31, 39, 46,
+    53, 60, 61, 54, 47, 55, 62, 63,
+];
+
+/// Components and coefficient band of a scan
+struct ScanBand {
+    components: Vec<u8>,
+    start: usize,
+    end: usize,
+    low: u8,
+}
+
+/// Value of a coefficient after all scans up to a successive approximation bit position
+fn approximate(value: i16, index: usize, low: u8) -> i16 {
+    if index == 0 {
+        // The DC point transform is an arithmetic shift
+        (value >> low) << low
+    } else {
+        // AC coefficients are divided by a power of two
+        value.signum() * ((value.abs() >> low) << low)
+    }
+}
+
+/// Compare the coefficients after a scan with the approximation of the final coefficients
+///
+/// `precision` holds the successive approximation bit position of every coefficient
+/// already contained in a scan, indexed by component and zigzag position.
+fn assert_scan(
+    decoder: &CoefficientDecoder,
+    expected: &CoefficientDecoder,
+    precision: &[[Option<u8>; 64]],
+    scan: usize,
+) {
+    for (c, (component, expected)) in decoder
+        .components()
+        .iter()
+        .zip(expected.components())
+        .enumerate()
+    {
+        for (block, expected) in component.blocks.iter().zip(&expected.blocks) {
+            for (k, low) in precision[c].iter().enumerate() {
+                let index = ZIGZAG[k];
+                let value = low.map_or(0, |low| approximate(expected[index], k, low));
+
+                assert_eq!(
+                    block[index], value,
+                    "scan {}, component {}, coefficient {}",
+                    scan, component.id, k
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn refinement_scans_match_baseline() {
+    let expected = decode_coefficients(BASELINE).unwrap();
+    assert_eq!(expected.frame().unwrap().sof, 0xC0);
+
+    let mut reader = Reader::new(PROGRESSIVE).unwrap();
+    let mut decoder = CoefficientDecoder::new();
+    let mut precision: Vec<[Option<u8>; 64]> = vec![];
+    let mut current: Option<ScanBand> = None;
+    let mut scans = 0;
+
+    loop {
+        let segment = reader.next_segment().unwrap();
+
+        // A scan ends with the first segment that isn't one of its restart intervals
+        if !matches!(segment.kind, SegmentKind::Rst(_)) {
+            if let Some(band) = current.take() {
+                for id in &band.components {
+                    let c = decoder
+                        .components()
+                        .iter()
+                        .position(|c| c.id == *id)
+                        .unwrap();
+
+                    for low in &mut precision[c][band.start..=band.end] {
+                        *low = Some(band.low);
+                    }
+                }
+
+                assert_scan(&decoder, &expected, &precision, scans);
+                scans += 1;
+            }
+        }
+
+        match &segment.kind {
+            SegmentKind::Eoi => break,
+            SegmentKind::Frame(frame) => precision = vec![[None; 64]; frame.components.len()],
+            SegmentKind::Scan(scan) => {
+                current = Some(ScanBand {
+                    components: scan.components.iter().map(|c| c.id).collect(),
+                    start: scan.selection_start as usize,
+                    end: scan.selection_end as usize,
+                    // The low nibble (Al) is read into `approximation_high`
+                    low: scan.approximation_high,
+                })
+            }
+            _ => {}
+        }
+
+        decoder.process(&segment.kind).unwrap();
+    }
+
+    assert_eq!(scans, 10);
+
+    for (component, expected) in decoder.components().iter().zip(expected.components()) {
+        assert_eq!(component.blocks, expected.blocks);
+    }
+}
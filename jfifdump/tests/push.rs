@@ -0,0 +1,83 @@
+use std::io::ErrorKind;
+
+use jfifdump::{JfifError, PushParser, Reader, SegmentKind};
+
+const IMAGE: &[u8] = include_bytes!("images/progressive.jpg");
+
+/// Debug output of all segments up to the first EOI
+fn read_segments(data: &[u8]) -> Vec<String> {
+    let mut reader = Reader::new(data).unwrap();
+    let mut result = vec![];
+
+    loop {
+        let segment = reader.next_segment().unwrap();
+        result.push(format!("{:?}", segment));
+
+        if let SegmentKind::Eoi = segment.kind {
+            return result;
+        }
+    }
+}
+
+fn push_segments<'a>(chunks: impl IntoIterator<Item = &'a [u8]>) -> Vec<String> {
+    let mut parser = PushParser::new();
+    let mut result = vec![];
+
+    for chunk in chunks {
+        parser.feed(chunk);
+
+        while let Some(segment) = parser.next_segment().unwrap() {
+            result.push(format!("{:?}", segment));
+        }
+    }
+
+    while let Some(segment) = parser.finish().unwrap() {
+        result.push(format!("{:?}", segment));
+    }
+
+    assert_eq!(parser.buffered(), 0);
+
+    result
+}
+
+#[test]
+fn split_at_every_offset() {
+    let expected = read_segments(IMAGE);
+
+    // Covers splits inside segment headers, scan data and right after every 0xFF
+    for offset in 0..=IMAGE.len() {
+        let (first, second) = IMAGE.split_at(offset);
+        assert_eq!(push_segments(vec![first, second]), expected, "{}", offset);
+    }
+}
+
+#[test]
+fn single_byte_chunks() {
+    let expected = read_segments(IMAGE);
+
+    assert_eq!(push_segments(IMAGE.chunks(1)), expected);
+}
+
+#[test]
+fn truncated_input() {
+    let scan = IMAGE.windows(2).position(|w| w == [0xFF, 0xDA]).unwrap();
+
+    for end in [scan + 2, scan + 4, scan + 40, IMAGE.len() - 2] {
+        let mut parser = PushParser::new();
+        parser.feed(&IMAGE[..end]);
+
+        let error = loop {
+            match parser.finish() {
+                Ok(Some(_)) => {}
+                Ok(None) => panic!("truncation at {} not detected", end),
+                Err(err) => break err,
+            }
+        };
+
+        assert!(
+            matches!(&error, JfifError::IoError(err) if err.kind() == ErrorKind::UnexpectedEof),
+            "{}",
+            end
+        );
+    }
+}
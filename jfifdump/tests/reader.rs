@@ -0,0 +1,185 @@
+use std::io::{BufReader, Cursor, Read, Seek, SeekFrom};
+
+use jfifdump::{Reader, ReaderOptions, SegmentKind};
+
+/// Progressive 53x41 4:2:0 image with restart intervals
+const PROGRESSIVE: &[u8] = include_bytes!("images/progressive.jpg");
+
+/// Baseline image embedded after the primary image in [with_embedded_image]
+const BASELINE: &[u8] = include_bytes!("images/baseline.jpg");
+
+const TRAILER: &[u8] = b"data after the EOI";
+
+/// Zero bytes between the primary and the embedded image
+const PADDING: usize = 0x10000;
+
+/// Position, length and debug output of all segments up to the first EOI
+fn read_segments<R: Read>(reader: &mut Reader<R>) -> Vec<(usize, usize, String)> {
+    let mut segments = vec![];
+
+    loop {
+        let segment = reader.next_segment().unwrap();
+        let eoi = matches!(segment.kind, SegmentKind::Eoi);
+
+        segments.push((
+            segment.position,
+            segment.length,
+            format!("{:?}", segment.kind),
+        ));
+
+        if eoi {
+            return segments;
+        }
+    }
+}
+
+/// Progressive image followed by padding and a baseline image listed in an MPF segment
+fn with_embedded_image() -> Vec<u8> {
+    // The MPF segment is inserted after the SOI and offsets start at its TIFF header
+    let segment_length = 2 + 4 + 82;
+    let tiff_position = 2 + 4 + 4;
+    let primary_length = PROGRESSIVE.len() + 2 + segment_length;
+    let embedded_position = primary_length + PADDING;
+
+    let mut tiff = vec![];
+    tiff.extend_from_slice(b"MM\x00\x2A\x00\x00\x00\x08\x00\x03");
+    // MPFVersion, NumberOfImages and MPEntry at offset 50
+    tiff.extend_from_slice(b"\xB0\x00\x00\x07\x00\x00\x00\x04\x30\x31\x30\x30");
+    tiff.extend_from_slice(b"\xB0\x01\x00\x04\x00\x00\x00\x01\x00\x00\x00\x02");
+    tiff.extend_from_slice(b"\xB0\x02\x00\x07\x00\x00\x00\x20\x00\x00\x00\x32");
+    tiff.extend_from_slice(&[0, 0, 0, 0]);
+
+    for (attribute, size, offset) in [
+        (0x2003_0000u32, primary_length, 0),
+        (0, BASELINE.len(), embedded_position - tiff_position),
+    ] {
+        tiff.extend_from_slice(&attribute.to_be_bytes());
+        tiff.extend_from_slice(&(size as u32).to_be_bytes());
+        tiff.extend_from_slice(&(offset as u32).to_be_bytes());
+        tiff.extend_from_slice(&[0, 0, 0, 0]);
+    }
+
+    let mut image = vec![0xFF, 0xD8, 0xFF, 0xE2];
+    image.extend_from_slice(&(segment_length as u16).to_be_bytes());
+    image.extend_from_slice(b"MPF\0");
+    image.extend_from_slice(&tiff);
+    image.extend_from_slice(&PROGRESSIVE[2..]);
+    assert_eq!(image.len(), primary_length);
+
+    image.resize(embedded_position, 0);
+    image.extend_from_slice(BASELINE);
+    image
+}
+
+/// Seekable input counting the bytes read from it
+struct CountingReader {
+    inner: Cursor<Vec<u8>>,
+    count: usize,
+}
+
+impl Read for CountingReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let count = self.inner.read(buf)?;
+        self.count += count;
+        Ok(count)
+    }
+}
+
+impl Seek for CountingReader {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+#[test]
+fn all_inputs_return_the_same_segments() {
+    let expected = read_segments(&mut Reader::new(PROGRESSIVE).unwrap());
+    let options = ReaderOptions::default();
+
+    let buffered = Reader::with_buf_read(BufReader::with_capacity(7, PROGRESSIVE), options);
+    assert_eq!(read_segments(&mut buffered.unwrap()), expected);
+
+    let seekable = Reader::with_seek(Cursor::new(PROGRESSIVE), options);
+    assert_eq!(read_segments(&mut seekable.unwrap()), expected);
+}
+
+#[test]
+fn nothing_is_consumed_after_the_eoi() {
+    let mut data = PROGRESSIVE.to_vec();
+    data.extend_from_slice(TRAILER);
+
+    let mut input = data.as_slice();
+    read_segments(&mut Reader::new(&mut input).unwrap());
+    assert_eq!(input, TRAILER);
+
+    let mut input = BufReader::new(data.as_slice());
+    read_segments(&mut Reader::with_buf_read(&mut input, ReaderOptions::default()).unwrap());
+    let mut rest = vec![];
+    input.read_to_end(&mut rest).unwrap();
+    assert_eq!(rest, TRAILER);
+
+    // The read-ahead is given back by seeking when the reader is dropped
+    let mut input = Cursor::new(data.as_slice());
+    read_segments(&mut Reader::with_seek(&mut input, ReaderOptions::default()).unwrap());
+    assert_eq!(input.position() as usize, PROGRESSIVE.len());
+}
+
+#[test]
+fn scan_data_is_not_retained() {
+    let options = ReaderOptions {
+        retain_scan_data: false,
+    };
+    let mut reader = Reader::with_seek(Cursor::new(PROGRESSIVE), options).unwrap();
+    let mut expected = Reader::new(PROGRESSIVE).unwrap();
+    let mut scans = 0;
+
+    loop {
+        let segment = reader.next_segment().unwrap();
+        let expected = expected.next_segment().unwrap();
+        assert_eq!(segment.position, expected.position);
+
+        match (segment.kind, expected.kind) {
+            (SegmentKind::Eoi, _) => break,
+            (SegmentKind::Scan(scan), SegmentKind::Scan(expected)) => {
+                assert!(scan.data.is_empty());
+                assert_eq!(scan.data_position, expected.data_position);
+                assert_eq!(scan.data_length, expected.data_length);
+                scans += 1;
+            }
+            (SegmentKind::Rst(rst), SegmentKind::Rst(expected)) => {
+                assert!(rst.data.is_empty());
+                assert_eq!(rst.data_length, expected.data_length);
+            }
+            _ => {}
+        }
+    }
+
+    assert_eq!(scans, 10);
+}
+
+#[test]
+fn seek_to_embedded_image() {
+    let data = with_embedded_image();
+    let embedded_position = data.len() - BASELINE.len();
+
+    let mut input = CountingReader {
+        inner: Cursor::new(data),
+        count: 0,
+    };
+
+    let mut reader = Reader::with_seek(&mut input, ReaderOptions::default()).unwrap();
+    read_segments(&mut reader);
+
+    let soi = reader.next_segment().unwrap();
+    assert!(matches!(soi.kind, SegmentKind::Soi));
+    assert_eq!(soi.position, embedded_position);
+    assert_eq!(reader.image_index(), 1);
+    assert!(reader.mp_entry(embedded_position).is_some());
+
+    read_segments(&mut reader);
+    assert!(reader.warnings().is_empty());
+    drop(reader);
+
+    // The padding was seeked over instead of read
+    assert!(input.count < PADDING);
+}
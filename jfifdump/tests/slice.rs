@@ -0,0 +1,101 @@
+use jfifdump::{Reader, SegmentKind, SliceReader, SliceSegmentKind};
+
+/// Progressive 53x41 4:2:0 image with restart intervals
+const PROGRESSIVE: &[u8] = include_bytes!("images/progressive.jpg");
+
+/// Image with all parsed APP segments followed by an embedded image listed in an MPF segment
+const METADATA: &[u8] = include_bytes!("images/metadata.jpg");
+
+/// Name of the enum variant in the debug output of a segment kind
+fn variant(debug: String) -> String {
+    debug
+        .split(|c: char| !c.is_alphanumeric())
+        .next()
+        .unwrap()
+        .to_owned()
+}
+
+/// Assert that both readers return the same segments for all images of `input`
+fn assert_same_segments(input: &[u8], images: usize) {
+    let mut reader = Reader::new(input).unwrap();
+    let mut slice_reader = SliceReader::new(input).unwrap();
+    let mut eoi_count = 0;
+    let mut scan_count = 0;
+
+    while eoi_count < images {
+        let expected = reader.next_segment().unwrap();
+        let segment = slice_reader.next_segment().unwrap();
+
+        assert_eq!(segment.position, expected.position);
+        assert_eq!(segment.length, expected.length);
+        assert_eq!(
+            variant(format!("{:?}", segment.kind)),
+            variant(format!("{:?}", expected.kind))
+        );
+        assert_eq!(slice_reader.image_index(), reader.image_index());
+
+        match (&segment.kind, &expected.kind) {
+            (SliceSegmentKind::Scan(scan), SegmentKind::Scan(expected)) => {
+                assert_eq!(scan.data.raw(), expected.data.as_slice());
+                assert_eq!(
+                    scan.data.range(),
+                    expected.data_position..expected.data_position + expected.data_length
+                );
+                scan_count += 1;
+            }
+            (SliceSegmentKind::Rst(rst), SegmentKind::Rst(expected)) => {
+                assert_eq!(rst.nr, expected.nr);
+                assert_eq!(rst.data.raw(), expected.data.as_slice());
+            }
+            (SliceSegmentKind::Eoi, _) => eoi_count += 1,
+            _ => {}
+        }
+    }
+
+    assert!(scan_count > 0);
+    assert_eq!(slice_reader.warnings(), reader.warnings());
+    assert_eq!(
+        slice_reader.icc_profile().map(|profile| profile.data),
+        reader.icc_profile().map(|profile| profile.data)
+    );
+}
+
+#[test]
+fn same_segments_as_reader() {
+    assert_same_segments(PROGRESSIVE, 1);
+}
+
+#[test]
+fn same_segments_as_reader_with_embedded_image() {
+    assert_same_segments(METADATA, 2);
+}
+
+#[test]
+fn destuffed_entropy_data() {
+    let mut image = vec![0xFF, 0xD8];
+    // Scan header with a single component and all coefficients
+    image.extend_from_slice(&[0xFF, 0xDA, 0x00, 0x08, 0x01, 0x01, 0x00, 0x00, 0x3F, 0x00]);
+    let data = [0x12, 0xFF, 0x00, 0x34, 0xFF, 0xFF, 0x00, 0x56, 0xFF, 0x00];
+    image.extend_from_slice(&data);
+    image.extend_from_slice(&[0xFF, 0xD9]);
+
+    let mut reader = SliceReader::new(&image).unwrap();
+    reader.next_segment().unwrap();
+
+    let scan = match reader.next_segment().unwrap().kind {
+        SliceSegmentKind::Scan(scan) => scan,
+        kind => panic!("Expected scan, got {:?}", kind),
+    };
+
+    assert_eq!(scan.data.raw(), data);
+    assert_eq!(scan.data.range(), 12..22);
+
+    // The fill byte and stuffed zero bytes are removed
+    let bytes: Vec<u8> = scan.data.bytes().collect();
+    assert_eq!(bytes, [0x12, 0xFF, 0x34, 0xFF, 0x56, 0xFF]);
+
+    assert!(matches!(
+        reader.next_segment().unwrap().kind,
+        SliceSegmentKind::Eoi
+    ));
+}
@@ -0,0 +1,112 @@
+use jfifdump::{validate, Finding, Severity};
+
+/// Baseline 44x30 4:2:0 image with a restart interval of two MCUs
+const BASELINE: &[u8] = include_bytes!("images/baseline.jpg");
+
+/// Progressive 53x41 4:2:0 image with restart intervals
+const PROGRESSIVE: &[u8] = include_bytes!("images/progressive.jpg");
+
+/// Image with all parsed APP segments followed by an embedded image listed in an MPF segment
+const METADATA: &[u8] = include_bytes!("images/metadata.jpg");
+
+/// Position of the first segment with `marker`
+fn find_marker(data: &[u8], marker: u8) -> usize {
+    data.windows(2).position(|w| w == [0xFF, marker]).unwrap()
+}
+
+/// Copy of the baseline image with the segment starting with `marker` replaced
+fn replace_segment(marker: u8, replacement: &[u8]) -> Vec<u8> {
+    let position = find_marker(BASELINE, marker);
+    let length = u16::from_be_bytes([BASELINE[position + 2], BASELINE[position + 3]]) as usize;
+
+    let mut image = BASELINE[..position].to_vec();
+    image.extend_from_slice(replacement);
+    image.extend_from_slice(&BASELINE[position + 2 + length..]);
+    image
+}
+
+/// Rule ids and severities of all findings
+fn rules(findings: &[Finding]) -> Vec<(&'static str, Severity)> {
+    findings.iter().map(|f| (f.rule.id(), f.severity)).collect()
+}
+
+#[test]
+fn valid_images() {
+    for image in [BASELINE, PROGRESSIVE, METADATA] {
+        assert_eq!(rules(&validate(image).unwrap()), []);
+    }
+}
+
+#[test]
+fn missing_soi() {
+    let findings = validate(&BASELINE[2..]).unwrap();
+
+    assert_eq!(rules(&findings), [("soi-first", Severity::Error)]);
+}
+
+#[test]
+fn scan_without_frame() {
+    let image = replace_segment(0xC0, &[]);
+    let findings = validate(image.as_slice()).unwrap();
+
+    assert_eq!(rules(&findings), [("sof-before-sos", Severity::Error)]);
+    assert_eq!(findings[0].position, find_marker(&image, 0xDA));
+}
+
+#[test]
+fn restart_count_mismatch() {
+    // Six MCUs with a restart interval of one need five RST markers instead of two
+    let image = replace_segment(0xDD, &[0xFF, 0xDD, 0x00, 0x04, 0x00, 0x01]);
+    let findings = validate(image.as_slice()).unwrap();
+
+    assert_eq!(rules(&findings), [("rst-count", Severity::Warning)]);
+    assert_eq!(
+        findings[0].message,
+        "Scan contains 2 RST markers instead of 5"
+    );
+}
+
+#[test]
+fn huffman_table_violating_kraft_inequality() {
+    // Three codes of length 1 in an additional DC table
+    let mut dht = vec![0xFF, 0xC4, 0x00, 0x16, 0x02, 3];
+    dht.extend_from_slice(&[0; 15]);
+    dht.extend_from_slice(&[0, 1, 2]);
+
+    let position = find_marker(BASELINE, 0xDA);
+    let mut image = BASELINE[..position].to_vec();
+    image.extend_from_slice(&dht);
+    image.extend_from_slice(&BASELINE[position..]);
+
+    let findings = validate(image.as_slice()).unwrap();
+
+    assert_eq!(rules(&findings), [("dht-table", Severity::Error)]);
+    assert_eq!(findings[0].position, position);
+}
+
+#[test]
+fn garbage_before_marker() {
+    let position = find_marker(BASELINE, 0xC0);
+    let mut image = BASELINE[..position].to_vec();
+    image.extend_from_slice(&[0x00, 0x00]);
+    image.extend_from_slice(&BASELINE[position..]);
+
+    let findings = validate(image.as_slice()).unwrap();
+
+    assert_eq!(rules(&findings), [("recovered", Severity::Warning)]);
+    assert_eq!(findings[0].position, position);
+}
+
+#[test]
+fn missing_eoi() {
+    let findings = validate(&BASELINE[..BASELINE.len() - 2]).unwrap();
+
+    // The last RST segment is lost as its data ends without a marker
+    assert_eq!(
+        rules(&findings),
+        [
+            ("rst-count", Severity::Warning),
+            ("eoi-present", Severity::Error)
+        ]
+    );
+}
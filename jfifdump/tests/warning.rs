@@ -0,0 +1,60 @@
+use jfifdump::{Reader, SegmentKind, Warning, WarningKind};
+
+/// Read all segments of `image` and return the warnings
+fn warnings(image: &[u8]) -> Vec<Warning> {
+    let mut reader = Reader::new(image).unwrap();
+    while !matches!(reader.next_segment().unwrap().kind, SegmentKind::Eoi) {}
+    reader.warnings().to_vec()
+}
+
+#[test]
+fn surplus_bytes() {
+    let image = [
+        0xFF, 0xD8, // SOI
+        0xFF, 0xDD, 0x00, 0x06, 0x00, 0x02, 0xAA, 0xBB, // DRI with two surplus bytes
+        0xFF, 0xD9, // EOI
+    ];
+
+    let expected = Warning {
+        position: 8,
+        kind: WarningKind::SurplusBytes {
+            marker: 0xDD,
+            count: 2,
+        },
+    };
+
+    assert_eq!(warnings(&image), [expected]);
+}
+
+#[test]
+fn garbage_before_marker() {
+    let image = [
+        0xFF, 0xD8, // SOI
+        0x61, 0x62, 0x63, // Garbage
+        0xFF, 0xD9, // EOI
+    ];
+
+    let expected = Warning {
+        position: 2,
+        kind: WarningKind::GarbageBeforeMarker { count: 3 },
+    };
+
+    assert_eq!(warnings(&image), [expected]);
+}
+
+#[test]
+fn fill_bytes_in_scan_data() {
+    let image = [
+        0xFF, 0xD8, // SOI
+        0xFF, 0xDA, 0x00, 0x08, 0x01, 0x01, 0x00, 0x00, 0x3F, 0x00, // SOS
+        0x12, 0xFF, 0xFF, 0x00, 0x34, 0xFF, 0x00, // Scan data with one fill byte
+        0xFF, 0xD9, // EOI
+    ];
+
+    let expected = Warning {
+        position: 13,
+        kind: WarningKind::FillBytesInScanData { count: 1 },
+    };
+
+    assert_eq!(warnings(&image), [expected]);
+}
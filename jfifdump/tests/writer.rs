@@ -0,0 +1,89 @@
+use jfifdump::{JfifError, Reader, ReaderOptions, SegmentKind, Writer};
+
+const IMAGE: &[u8] = include_bytes!("images/progressive.jpg");
+
+/// Image with JFIF and JFXX thumbnails, EXIF, XMP, extended XMP, ICC, MPF, Photoshop
+/// and Adobe segments followed by an embedded image listed in the MPF segment
+const METADATA: &[u8] = include_bytes!("images/metadata.jpg");
+
+/// Write all segments of all images in `input` and return the result
+fn round_trip(input: &[u8]) -> Vec<u8> {
+    let mut reader = Reader::new(input).unwrap();
+    let mut writer = Writer::new(vec![]);
+
+    loop {
+        let segment = reader.next_segment().unwrap();
+        writer.write_segment(&segment.kind).unwrap();
+
+        if let SegmentKind::Eoi = segment.kind {
+            if segment.position + segment.length == input.len() {
+                return writer.into_inner();
+            }
+        }
+    }
+}
+
+#[test]
+fn round_trip_is_byte_identical() {
+    assert_eq!(round_trip(IMAGE), IMAGE);
+}
+
+#[test]
+fn round_trip_of_parsed_app_segments_is_byte_identical() {
+    let mut reader = Reader::new(METADATA).unwrap();
+    let mut kinds = vec![];
+
+    loop {
+        let segment = reader.next_segment().unwrap();
+
+        if let SegmentKind::Eoi = segment.kind {
+            break;
+        }
+
+        kinds.push(format!("{:?}", segment.kind));
+    }
+
+    // All APP segments are parsed instead of written from their raw data
+    for prefix in [
+        "App0Jfif",
+        "App0Jfxx",
+        "App1Exif",
+        "App1Xmp(",
+        "App1XmpExtension",
+        "App2Icc",
+        "App2Mpf",
+        "App13Photoshop",
+        "App14Adobe",
+    ] {
+        assert!(
+            kinds.iter().any(|kind| kind.starts_with(prefix)),
+            "{}",
+            prefix
+        );
+    }
+
+    assert!(!kinds.iter().any(|kind| kind.starts_with("App {")));
+
+    assert_eq!(round_trip(METADATA), METADATA);
+}
+
+#[test]
+fn scan_without_retained_data_is_rejected() {
+    let options = ReaderOptions {
+        retain_scan_data: false,
+    };
+    let mut reader = Reader::with_options(IMAGE, options).unwrap();
+    let mut writer = Writer::new(vec![]);
+
+    let scan = loop {
+        let segment = reader.next_segment().unwrap();
+        if let SegmentKind::Scan(_) = segment.kind {
+            break segment.kind;
+        }
+    };
+
+    assert!(matches!(
+        writer.write_segment(&scan),
+        Err(JfifError::MissingScanData)
+    ));
+}
@@ -0,0 +1,82 @@
+use jfifdump::{ExtendedXmp, Reader, SegmentKind};
+
+/// Read an image with an extended XMP chunk for every `(offset, data)` and return the document
+fn extended_xmp(guid: &str, full_length: u32, chunks: &[(u32, &[u8])]) -> ExtendedXmp {
+    let mut image = vec![0xFF, 0xD8];
+
+    for (offset, data) in chunks {
+        let mut payload = b"http://ns.adobe.com/xmp/extension/\0".to_vec();
+        payload.extend_from_slice(guid.as_bytes());
+        payload.extend_from_slice(&full_length.to_be_bytes());
+        payload.extend_from_slice(&offset.to_be_bytes());
+        payload.extend_from_slice(data);
+
+        image.extend_from_slice(&[0xFF, 0xE1]);
+        image.extend_from_slice(&(payload.len() as u16 + 2).to_be_bytes());
+        image.extend_from_slice(&payload);
+    }
+
+    image.extend_from_slice(&[0xFF, 0xD9]);
+
+    let mut reader = Reader::new(image.as_slice()).unwrap();
+    while !matches!(reader.next_segment().unwrap().kind, SegmentKind::Eoi) {}
+
+    let mut documents = reader.extended_xmp();
+    assert_eq!(documents.len(), 1);
+    documents.remove(0)
+}
+
+/// Assert that the GUID matches the MD5 digest of a complete document
+fn assert_checksum(guid: &str, document: &[u8]) {
+    // The document is split into two chunks in reverse order
+    let half = document.len() / 2;
+    let chunks: [(u32, &[u8]); 2] = [(half as u32, &document[half..]), (0, &document[..half])];
+
+    let xmp = extended_xmp(guid, document.len() as u32, &chunks);
+
+    assert!(xmp.complete);
+    assert_eq!(xmp.data, document);
+    assert!(xmp.checksum_valid, "{}", guid);
+}
+
+// MD5 test suite of RFC 1321
+
+#[test]
+fn checksum_of_empty_document() {
+    assert_checksum("D41D8CD98F00B204E9800998ECF8427E", b"");
+}
+
+#[test]
+fn checksum_of_short_document() {
+    assert_checksum("900150983CD24FB0D6963F7D28E17F72", b"abc");
+    assert_checksum("F96B697D7CB7938D525A2F31AAF161D0", b"message digest");
+}
+
+#[test]
+fn checksum_of_multiple_blocks() {
+    assert_checksum(
+        "57EDF4A22BE3C955AC49DA2E2107B67A",
+        b"12345678901234567890123456789012345678901234567890123456789012345678901234567890",
+    );
+}
+
+#[test]
+fn wrong_checksum() {
+    let xmp = extended_xmp("900150983CD24FB0D6963F7D28E17F73", 3, &[(0, b"abc")]);
+
+    assert!(xmp.complete);
+    assert!(!xmp.checksum_valid);
+}
+
+#[test]
+fn missing_chunk_keeps_offsets() {
+    let xmp = extended_xmp(
+        "00000000000000000000000000000000",
+        12,
+        &[(0, b"abc"), (6, b"ghi")],
+    );
+
+    assert!(!xmp.complete);
+    assert!(!xmp.checksum_valid);
+    assert_eq!(xmp.data, b"abc\0\0\0ghi");
+}